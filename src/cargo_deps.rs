@@ -0,0 +1,126 @@
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Source directories of the named dependencies of the cargo project in the
+/// current directory, located through `cargo metadata`. Each name may carry a
+/// version (`serde@1.0.200`) to pick one of several resolved versions.
+pub fn source_dirs(names: &[String]) -> Result<Vec<PathBuf>> {
+    let output = Command::new("cargo")
+        .args(["metadata", "--format-version", "1"])
+        .output()
+        .context("Failed to run `cargo metadata` (is cargo installed?)")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "`cargo metadata` failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let metadata: Value =
+        serde_json::from_slice(&output.stdout).context("invalid `cargo metadata` output")?;
+    names
+        .iter()
+        .map(|name| find_source_dir(&metadata, name))
+        .collect()
+}
+
+/// Ignore patterns added by `--rust`: build output and the lockfile.
+pub const RUST_PRESET_IGNORES: &[&str] = &["target/", "Cargo.lock"];
+
+/// Inputs selected by `--rust` in the crate in the current directory:
+/// `Cargo.toml` first, then `build.rs` and `src/` when they exist.
+pub fn rust_preset_paths() -> Result<Vec<String>> {
+    if !Path::new("Cargo.toml").is_file() {
+        anyhow::bail!("--rust needs a Cargo.toml in the current directory");
+    }
+    Ok(["Cargo.toml", "build.rs", "src"]
+        .into_iter()
+        .filter(|name| Path::new(name).exists())
+        .map(String::from)
+        .collect())
+}
+
+/// The dependency's `src/` directory, or its package root if it has none.
+fn find_source_dir(metadata: &Value, spec: &str) -> Result<PathBuf> {
+    let (name, version) = match spec.split_once('@') {
+        Some((name, version)) => (name, Some(version)),
+        None => (spec, None),
+    };
+    // Cargo treats `-` and `_` in package names as equivalent.
+    let normalize = |s: &str| s.replace('-', "_");
+    let candidates: Vec<&Value> = metadata
+        .get("packages")
+        .and_then(Value::as_array)
+        .map(|packages| {
+            packages
+                .iter()
+                .filter(|p| {
+                    p.get("name")
+                        .and_then(Value::as_str)
+                        .is_some_and(|n| normalize(n) == normalize(name))
+                })
+                .filter(|p| {
+                    version.is_none_or(|v| p.get("version").and_then(Value::as_str) == Some(v))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let package = match candidates.as_slice() {
+        [] => anyhow::bail!("--with-dep: '{spec}' is not in this project's dependency graph"),
+        [only] => only,
+        [first, ..] => {
+            let versions: Vec<&str> = candidates
+                .iter()
+                .filter_map(|p| p.get("version").and_then(Value::as_str))
+                .collect();
+            eprintln!(
+                "Warning: --with-dep: several versions of '{name}' ({}); using the first. \
+                 Pick one with {name}@VERSION",
+                versions.join(", ")
+            );
+            first
+        }
+    };
+    let manifest = package
+        .get("manifest_path")
+        .and_then(Value::as_str)
+        .with_context(|| format!("--with-dep: no manifest path for '{spec}'"))?;
+    let root = Path::new(manifest)
+        .parent()
+        .with_context(|| format!("--with-dep: bad manifest path '{manifest}'"))?;
+    let src = root.join("src");
+    Ok(if src.is_dir() {
+        src
+    } else {
+        root.to_path_buf()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_dependency_by_normalized_name_and_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("foo-bar-0.1.0");
+        let new = dir.path().join("foo-bar-0.2.0");
+        std::fs::create_dir_all(new.join("src")).unwrap();
+        std::fs::create_dir_all(&old).unwrap();
+        let manifest = |d: &Path| d.join("Cargo.toml").to_string_lossy().into_owned();
+        let metadata = serde_json::json!({
+            "packages": [
+                { "name": "foo-bar", "version": "0.1.0", "manifest_path": manifest(&old) },
+                { "name": "foo-bar", "version": "0.2.0", "manifest_path": manifest(&new) },
+            ]
+        });
+
+        assert_eq!(
+            find_source_dir(&metadata, "foo_bar@0.2.0").unwrap(),
+            new.join("src")
+        );
+        assert_eq!(find_source_dir(&metadata, "foo-bar@0.1.0").unwrap(), old);
+        assert!(find_source_dir(&metadata, "baz").is_err());
+    }
+}
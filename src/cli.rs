@@ -9,23 +9,146 @@ pub enum PathHeader {
     None,
 }
 
+/// Relative header for a file outside the current directory (`--relative-fallback`).
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, clap::ValueEnum)]
+pub enum RelativeFallback {
+    /// Show its absolute path.
+    #[default]
+    Abs,
+    /// Climb out with `../` segments.
+    Dotdot,
+}
+
+/// Order of top-level inputs for `--order`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum InputOrder {
+    /// Left to right as given; brace and glob expansions keep their argument's slot.
+    AsListed,
+    /// Inputs sorted by path before aggregation.
+    Sorted,
+}
+
+/// How a path list piped on stdin is laid out (`--input-format`).
+#[derive(Clone, Copy, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum InputFormat {
+    /// One path per line.
+    Lines,
+    /// NUL-separated, as from `fd -0` or `git ls-files -z`.
+    Null,
+    /// `git status --porcelain` output; deleted and ignored entries are dropped.
+    Porcelain,
+}
+
+impl InputFormat {
+    pub fn parse(self, input: &str) -> Vec<String> {
+        match self {
+            InputFormat::Lines => input
+                .lines()
+                .map(|l| l.trim_end_matches('\r'))
+                .filter(|l| !l.is_empty())
+                .map(str::to_string)
+                .collect(),
+            InputFormat::Null => input
+                .split('\0')
+                .map(|p| p.trim_end_matches(['\r', '\n']))
+                .filter(|p| !p.is_empty())
+                .map(str::to_string)
+                .collect(),
+            InputFormat::Porcelain => input
+                .lines()
+                .filter_map(|l| porcelain_path(l.trim_end_matches('\r')))
+                .collect(),
+        }
+    }
+}
+
+/// Paths from a `--paths-from-json` document: an array of strings, or of
+/// objects with a string `path` field. Syntax errors carry serde_json's line
+/// and column; shape errors name the offending entry.
+pub fn parse_json_paths(text: &str) -> Result<Vec<String>, String> {
+    let value: serde_json::Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    let serde_json::Value::Array(entries) = value else {
+        return Err("expected a JSON array of paths".into());
+    };
+    entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            entry
+                .as_str()
+                .or_else(|| entry.get("path").and_then(|p| p.as_str()))
+                .map(str::to_string)
+                .ok_or_else(|| {
+                    format!("entry {i} is neither a string nor an object with a string \"path\"")
+                })
+        })
+        .collect()
+}
+
+/// Path from one `git status --porcelain` line (`XY path`, or `XY old -> new`
+/// for renames), unquoting git's C-style quoting. None for deletions,
+/// ignored files and malformed lines.
+fn porcelain_path(line: &str) -> Option<String> {
+    let status = line.get(..2)?;
+    let rest = line.get(3..)?;
+    if status.contains('D') || status == "!!" {
+        return None;
+    }
+    let path = match rest.rsplit_once(" -> ") {
+        Some((_, new)) => new,
+        None => rest,
+    };
+    let unquoted = match path.strip_prefix('"').and_then(|p| p.strip_suffix('"')) {
+        Some(quoted) => quoted.replace("\\\"", "\"").replace("\\\\", "\\"),
+        None => path.to_string(),
+    };
+    (!unquoted.is_empty()).then_some(unquoted)
+}
+
+/// Fields available to `--header-meta`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum MetaField {
+    Size,
+    Lines,
+    Modified,
+}
+
 // ── Destination ──────────────────────────────────────────────────────────────
 
 pub enum Destination {
     /// Write to clipboard; echo=true also tees to stdout.
     Clipboard { echo: bool },
-    /// Write to a file; path already has .gz suffix when gzip=true.
-    File { path: PathBuf, gzip: bool },
+    /// Write the same content to each file; paths already have the .gz
+    /// suffix when gzip=true.
+    File {
+        paths: Vec<PathBuf>,
+        gzip: bool,
+        append: bool,
+    },
     /// Write to stdout (--ci --print).
     Stdout,
-    /// Discard output (--ci alone — still validates paths).
+    /// Discard output (reports such as --line-counts, or --ci with --manifest-stdout).
     Discard,
 }
 
 // ── Mode ─────────────────────────────────────────────────────────────────────
 
 pub enum Mode {
+    InitConfig {
+        force: bool,
+    },
+    ClearClipboard,
+    Paste {
+        into: Option<PathBuf>,
+        from: Option<String>,
+        conflict: crate::paste::Conflict,
+    },
+    DiffPaste {
+        into: Option<PathBuf>,
+        from: Option<String>,
+    },
     ListLanguages,
+    ListClipboardBackends,
     GitDiff(u8),
     Aggregate,
 }
@@ -37,6 +160,7 @@ pub enum Mode {
     name = "cxt",
     about = "Aggregates file/directory contents and sends them to the clipboard (default), a file, or stdout",
     version,
+    args_conflicts_with_subcommands = true,
     long_about = "cxt is a command-line tool that aggregates the contents of specified files and directories into a single string, then directs it to the clipboard, a file, or standard output."
 )]
 pub struct Args {
@@ -54,10 +178,78 @@ pub struct Args {
 
     #[command(flatten)]
     pub output: OutputArgs,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(clap::Subcommand)]
+pub enum Command {
+    /// Write a commented default config file and print its path
+    InitConfig {
+        /// Overwrite an existing config file
+        #[arg(long)]
+        force: bool,
+    },
+    /// Empty the clipboard, through the same backends a copy would use
+    ClearClipboard,
+    /// Write the files in XML or Markdown cxt output on the clipboard back to disk
+    Paste {
+        /// Directory the pasted paths are relative to (default: the current directory)
+        #[arg(long, value_name = "DIR")]
+        into: Option<PathBuf>,
+        /// Read the cxt output from FILE (- for stdin) instead of the clipboard
+        #[arg(long, value_name = "FILE|-")]
+        from: Option<String>,
+        /// Replace files that already exist
+        #[arg(long, conflicts_with = "append")]
+        overwrite: bool,
+        /// Add to the end of files that already exist
+        #[arg(long)]
+        append: bool,
+    },
+    /// Show how the files in cxt output on the clipboard differ from those on disk
+    #[command(alias = "diff-against-clipboard")]
+    Diff {
+        /// Directory the pasted paths are relative to (default: the current directory)
+        #[arg(long, value_name = "DIR")]
+        into: Option<PathBuf>,
+        /// Read the cxt output from FILE (- for stdin) instead of the clipboard
+        #[arg(long, value_name = "FILE|-")]
+        from: Option<String>,
+    },
 }
 
 impl Args {
     pub fn mode(&self) -> Mode {
+        match &self.command {
+            Some(Command::InitConfig { force }) => return Mode::InitConfig { force: *force },
+            Some(Command::ClearClipboard) => return Mode::ClearClipboard,
+            Some(Command::Paste {
+                into,
+                from,
+                overwrite,
+                append,
+            }) => {
+                let conflict = match (overwrite, append) {
+                    (true, _) => crate::paste::Conflict::Overwrite,
+                    (_, true) => crate::paste::Conflict::Append,
+                    _ => crate::paste::Conflict::Ask,
+                };
+                return Mode::Paste {
+                    into: into.clone(),
+                    from: from.clone(),
+                    conflict,
+                };
+            }
+            Some(Command::Diff { into, from }) => {
+                return Mode::DiffPaste {
+                    into: into.clone(),
+                    from: from.clone(),
+                };
+            }
+            None => {}
+        }
         if self
             .select
             .lang
@@ -66,6 +258,9 @@ impl Args {
         {
             return Mode::ListLanguages;
         }
+        if self.output.list_clipboard_backends {
+            return Mode::ListClipboardBackends;
+        }
         if let Some(n) = self.source.df {
             return Mode::GitDiff(n);
         }
@@ -80,6 +275,44 @@ impl Args {
                 }
             }
         }
+        if let Some(replacement) = &self.render.ascii_only {
+            if !replacement.is_ascii() {
+                return Err(format!(
+                    "--ascii-only replacement '{replacement}' must itself be ASCII"
+                ));
+            }
+        }
+        if self.render.embed_command && self.render.format() == crate::formatter::FormatChoice::Csv
+        {
+            return Err("--embed-command would break the header row of --format csv".into());
+        }
+        if self.render.separators_only
+            && matches!(
+                self.render.format(),
+                crate::formatter::FormatChoice::Csv | crate::formatter::FormatChoice::Summary
+            )
+        {
+            return Err("--separators-only has no file blocks to separate in this --format".into());
+        }
+        if self.render.toc
+            && matches!(
+                self.render.format(),
+                crate::formatter::FormatChoice::Csv | crate::formatter::FormatChoice::Summary
+            )
+        {
+            return Err("--toc needs a --format that includes file contents".into());
+        }
+        if self.render.with_diff
+            && matches!(
+                self.render.format(),
+                crate::formatter::FormatChoice::Csv | crate::formatter::FormatChoice::Summary
+            )
+        {
+            return Err("--with-diff needs a --format that includes file contents".into());
+        }
+        if self.output.dedup_headers_in_append && self.output.write.len() > 1 {
+            return Err("--dedup-headers-in-append works with a single --write target".into());
+        }
         for raw in &self.select.lang {
             for token in raw.split(',') {
                 let token = token.trim();
@@ -112,6 +345,42 @@ pub struct SourceArgs {
     )]
     pub tui: bool,
 
+    #[arg(
+        long,
+        alias = "select-by-glob",
+        value_name = "GLOB",
+        help = "Open the TUI with files matching GLOB (gitignore syntax, e.g. '*.rs') already \
+                selected. Repeatable; implies --tui",
+        action = clap::ArgAction::Append,
+        conflicts_with_all = ["df", "st", "edit_paths", "stdin_content"],
+    )]
+    pub select: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Open the TUI in the current directory with the positional paths already selected \
+                (directories select every file under them). Implies --tui",
+        conflicts_with_all = ["df", "st", "edit_paths", "stdin_content"],
+    )]
+    pub tui_start_selected: bool,
+
+    #[arg(
+        long,
+        value_name = "CODE",
+        default_value_t = 130,
+        help = "Exit status when the TUI is quit without confirming a selection (0 treats it as success)"
+    )]
+    pub cancel_exit_code: i32,
+
+    #[arg(
+        long,
+        value_name = "CMD",
+        help = "Generate the TUI preview pane (v) with this command, `{}` standing for the file \
+                (e.g. 'bat --color=always {}'); opens the pane on start. Falls back to a plain \
+                preview if the command fails"
+    )]
+    pub preview_command: Option<String>,
+
     #[arg(
         long = "df",
         num_args = 0..=1,
@@ -131,6 +400,97 @@ pub struct SourceArgs {
         conflicts_with = "df",
     )]
     pub st: Option<u8>,
+
+    #[arg(
+        long,
+        value_name = "REF",
+        help = "Aggregate files changed since REF (commit, branch or tag), uncommitted changes included",
+        conflicts_with_all = ["df", "st"],
+    )]
+    pub since_ref: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "LANG",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "auto",
+        help = "Treat the given files as entry points and add the project files they import, \
+                transitively: Rust mod/use, C #include \"...\", Python and JS/TS relative imports. \
+                --follow-includes=LANG (rust, c, python, js) forces one syntax instead of \
+                picking by extension",
+        conflicts_with_all = ["df", "st", "since_ref", "tui", "edit_paths", "stdin_content"],
+    )]
+    pub follow_includes: Option<crate::includes::IncludeLang>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 5,
+        help = "How many import hops --follow-includes goes from the entry files"
+    )]
+    pub include_depth: usize,
+
+    #[arg(
+        long,
+        help = "Type the paths to aggregate in $EDITOR (one per line) instead of using the TUI",
+        conflicts_with_all = ["tui", "df", "st"],
+    )]
+    pub edit_paths: bool,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Read file content (not paths) from stdin and include it as a virtual file NAME",
+        conflicts_with_all = ["tui", "df", "edit_paths"],
+    )]
+    pub stdin_content: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Resolve relative paths read from stdin or --edit-paths against DIR instead of the current directory"
+    )]
+    pub paths_base: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "FILE|-",
+        conflicts_with_all = ["tui", "df", "edit_paths", "stdin_content"],
+        help = "Read input paths from a JSON array of strings or of objects with a `path` field \
+                (- reads stdin); added to any positional paths"
+    )]
+    pub paths_from_json: Option<String>,
+
+    #[arg(
+        long,
+        alias = "input-list-format",
+        value_enum,
+        default_value = "lines",
+        help = "Layout of the path list piped on stdin: lines, null (NUL-separated, e.g. fd -0) \
+                or porcelain (git status --porcelain; deletions are skipped)"
+    )]
+    pub input_format: InputFormat,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Also include the src/ of cargo dependency NAME (or NAME@VERSION), found via \
+                `cargo metadata`. Repeatable",
+        action = clap::ArgAction::Append,
+        conflicts_with_all = ["df", "st"],
+    )]
+    pub with_dep: Vec<String>,
+
+    #[arg(
+        long,
+        alias = "include-cargo-toml-context",
+        conflicts_with_all = ["tui", "df"],
+        help = "Rust crate preset: Cargo.toml first, then build.rs and src/, ignoring target/ \
+                and Cargo.lock. Other paths are added after"
+    )]
+    pub rust: bool,
 }
 
 #[derive(ClapArgs)]
@@ -139,6 +499,24 @@ pub struct SelectArgs {
           action = clap::ArgAction::Append)]
     pub ignore: Vec<String>,
 
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Read ignore patterns (gitignore syntax) from FILE, e.g. .dockerignore, anchored at \
+                FILE's directory as git anchors a nested .gitignore. Repeatable.",
+        action = clap::ArgAction::Append,
+    )]
+    pub ignore_file: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "PATTERN",
+        help = "Always include files matching PATTERN (gitignore syntax), overriding \
+                ignores, hidden-file rules and --ext/--lang. Repeatable; also read from ./.cxtinclude",
+        action = clap::ArgAction::Append,
+    )]
+    pub force_include: Vec<String>,
+
     #[arg(
         long,
         value_name = "EXT[,EXT...]",
@@ -159,23 +537,180 @@ pub struct SelectArgs {
     )]
     pub lang: Vec<String>,
 
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = parse_duration,
+        help = "Include only files modified within DURATION, e.g. 30m, 2h, 3d, 1w"
+    )]
+    pub modified_within: Option<std::time::Duration>,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value = "heuristic",
+        help = "How to spot binary files: heuristic (NUL bytes) or mime (magic-number sniffing, \
+                then UTF-8 validity)"
+    )]
+    pub binary_detection: crate::content_aggregator::BinaryDetection,
+
     #[arg(long, help = "Include hidden files when walking directories")]
     pub hidden: bool,
 
+    #[arg(
+        long,
+        help = "Skip common generated files and build output (dist/, build/, *.min.js, *.pb.go, \
+                *_generated.rs, ...). See README for the full list."
+    )]
+    pub exclude_generated: bool,
+
+    #[arg(
+        long,
+        help = "Include files that look like they hold secrets (.env, id_rsa, *.pem, .npmrc, \
+                credentials, ...), which are otherwise left out with a warning"
+    )]
+    pub allow_sensitive: bool,
+
+    #[arg(
+        long,
+        help = "Drop files more than 5x the median file size (usually data or generated blobs) \
+                and report which were dropped"
+    )]
+    pub drop_outliers: bool,
+
+    #[arg(
+        long,
+        help = "Include only files tracked by git (as listed by `git ls-files`); errors outside a repository"
+    )]
+    pub only_tracked: bool,
+
+    #[arg(
+        long,
+        help = "Treat initialized git submodules as part of the repository (their tracked files \
+                pass --only-tracked) and note uninitialized ones, which have nothing to read"
+    )]
+    pub include_submodules: bool,
+
+    #[arg(
+        long,
+        help = "Walk a hidden directory given on the command line (e.g. ~/.config) with its \
+                dotfiles, except .git; only the directory's own name counts, not its parents"
+    )]
+    pub hidden_root: bool,
+
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Abort if walking the input directories visits more than N entries (default \
+                1,000,000, or max_entries in the config; 0 disables the limit)"
+    )]
+    pub max_entries: Option<usize>,
+
     #[arg(
         long,
         help = "Output files in arbitrary order (faster for large directories; implies non-deterministic output)"
     )]
     pub no_sort: bool,
+
+    #[arg(
+        long,
+        help = "Also ignore paths that resolve through symlinks into an --ignore'd path \
+                (canonicalizes every walked entry, which slows large walks)"
+    )]
+    pub resolve_ignores: bool,
+
+    #[arg(
+        long,
+        help = "Ignore the directory each --write target lives in, so earlier dumps inside \
+                the tree are not read back in (skipped if that directory holds an input)"
+    )]
+    pub ignore_output_dir: bool,
+
+    #[arg(
+        long,
+        alias = "max-open",
+        value_name = "N",
+        value_parser = clap::value_parser!(u16).range(1..),
+        help = "Walk and read with at most N threads, so at most N files are open at once \
+                (default: one per CPU, fewer when the open-file limit is low)"
+    )]
+    pub jobs: Option<u16>,
+
+    #[arg(
+        long,
+        help = "In directory arguments, emit each directory's own files before the contents of \
+                its subdirectories (top-down)",
+        conflicts_with = "dir_first"
+    )]
+    pub files_first: bool,
+
+    #[arg(
+        long,
+        alias = "dirs-first",
+        help = "In directory arguments, emit the contents of each directory's subdirectories \
+                before its own files"
+    )]
+    pub dir_first: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value = "as-listed",
+        help = "Order of the given paths: as-listed (left to right, expansions in place) or sorted. \
+                Files inside a directory argument are emitted sorted, in that argument's slot"
+    )]
+    pub order: InputOrder,
 }
 
 impl SelectArgs {
+    pub fn dir_grouping(&self) -> Option<crate::content_aggregator::DirGrouping> {
+        use crate::content_aggregator::DirGrouping;
+        if self.files_first {
+            Some(DirGrouping::FilesFirst)
+        } else if self.dir_first {
+            Some(DirGrouping::DirsFirst)
+        } else {
+            None
+        }
+    }
+
     pub fn extensions(&self) -> Result<std::collections::HashSet<String>, String> {
         crate::lang::build_extension_filter(&self.lang, &self.ext)
     }
+
+    /// Every `--ignore-file`, each matched relative to its own directory.
+    pub fn ignore_files(
+        &self,
+    ) -> Result<Vec<std::sync::Arc<ignore::gitignore::Gitignore>>, String> {
+        self.ignore_file
+            .iter()
+            .map(|file| {
+                crate::content_aggregator::load_ignore_file(std::path::Path::new(file))
+                    .map_err(|e| format!("Cannot read ignore file '{file}': {e}"))
+            })
+            .collect()
+    }
+
+    /// `--force-include` patterns plus those in `./.cxtinclude`, if present.
+    pub fn force_include_patterns(&self) -> Vec<String> {
+        let mut patterns = self.force_include.clone();
+        if let Ok(content) = std::fs::read_to_string(".cxtinclude") {
+            patterns.extend(pattern_lines(&content));
+        }
+        patterns
+    }
 }
 
-#[derive(ClapArgs, Clone, Copy)]
+/// Lines of a gitignore-style file, with comments and blank lines dropped.
+fn pattern_lines(content: &str) -> impl Iterator<Item = String> + '_ {
+    content
+        .lines()
+        .map(|l| l.trim_end_matches('\r'))
+        .filter(|l| !l.trim().is_empty() && !l.starts_with('#'))
+        .map(String::from)
+}
+
+#[derive(ClapArgs, Clone)]
 pub struct RenderArgs {
     #[arg(
         short,
@@ -185,27 +720,297 @@ pub struct RenderArgs {
     )]
     pub relative: bool,
 
-    #[arg(short, long, help = "Disable file path headers")]
+    #[arg(
+        long,
+        help = "Use absolute paths in headers (the default; overrides a configured path_header)",
+        conflicts_with_all = ["relative", "no_path"]
+    )]
+    pub absolute: bool,
+
+    #[arg(
+        long,
+        help = "Open with a numbered index of the included files (path and line count) and \
+                number each header to match; output is held in memory until the end",
+        conflicts_with_all = ["no_path", "separators_only"]
+    )]
+    pub toc: bool,
+
+    #[arg(
+        short,
+        long,
+        visible_alias = "content-only",
+        help = "Leave file paths out of headers; files stay separated by a blank line"
+    )]
     pub no_path: bool,
 
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FIELD[,FIELD...]",
+        num_args = 0..=1,
+        require_equals = true,
+        value_delimiter = ',',
+        default_missing_value = "size,lines,modified",
+        alias = "include-file-metadata",
+        help = "Add a metadata summary to each file header \
+                (size, lines, modified; default: all). Use --header-meta=size,lines to pick fields"
+    )]
+    pub header_meta: Option<Vec<MetaField>>,
+
+    #[arg(
+        long,
+        help = "Add each changed file's added/removed line counts, e.g. (+12 -3), to its header \
+                (against --since-ref, --st's base, or HEAD)"
+    )]
+    pub with_diffstat: bool,
+
+    #[arg(
+        long,
+        help = "End the output with the inputs' unified diff against the same base as --with-diffstat"
+    )]
+    pub with_diff: bool,
+
+    #[arg(
+        long,
+        help = "Always use forward slashes in header paths (portable output on Windows)"
+    )]
+    pub posix_paths: bool,
+
+    #[arg(
+        long,
+        help = "Prefix relative header paths with ./ (e.g. ./src/main.rs)"
+    )]
+    pub dot_slash: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "MODE",
+        default_value = "abs",
+        help = "Relative headers for files outside the current directory: abs (absolute path) \
+                or dotdot (../ chain)"
+    )]
+    pub relative_fallback: RelativeFallback,
+
+    #[arg(
+        long,
+        alias = "basename",
+        help = "Show only file names in headers; repeated names get a (2), (3)... suffix",
+        conflicts_with = "no_path"
+    )]
+    pub flat_headers: bool,
+
+    #[arg(
+        long,
+        help = "Markdown: put each file's path on its opening fence line (```rust src/main.rs) \
+                instead of a separate ## heading"
+    )]
+    pub collapse_headers: bool,
+
+    #[arg(
+        long,
+        help = "Keep markdown cells (as commented # %% [markdown] cells) when rendering .ipynb notebooks"
+    )]
+    pub notebook_markdown: bool,
+
+    #[arg(
+        long,
+        help = "List empty and whitespace-only files on one summary line instead of one block each"
+    )]
+    pub dedup_blank_files: bool,
+
+    #[arg(
+        long,
+        alias = "copy-filenames-in-content",
+        help = "Start each file's content with a comment naming it in the file's own syntax \
+                (// main.rs, # script.py); useful with --no-path"
+    )]
+    pub inline_filename: bool,
+
+    #[arg(
+        long,
+        alias = "no-path-but-separators",
+        conflicts_with_all = ["relative", "absolute", "flat_headers"],
+        help = "Leave file paths out of headers like --no-path, but put a separator line \
+                between files (see --separator)"
+    )]
+    pub separators_only: bool,
+
+    #[arg(
+        long,
+        value_name = "TEXT",
+        default_value = "---",
+        requires = "separators_only",
+        help = "Separator line for --separators-only"
+    )]
+    pub separator: String,
+
+    #[arg(
+        long,
+        help = "Keep a leading UTF-8 byte order mark in file content (stripped by default)"
+    )]
+    pub keep_bom: bool,
+
+    #[arg(
+        long,
+        help = "End the output with a `--- Skipped (binary): logo.png (42K) ---` line naming \
+                skipped binary and outlier files"
+    )]
+    pub note_skipped: bool,
+
+    #[arg(
+        long,
+        help = "Start the output with the cxt command line that produced it and a timestamp, \
+                as `# Generated by: ...` / `# Generated at: ...` lines"
+    )]
+    pub embed_command: bool,
+
+    #[arg(
+        long,
+        help = "Emit a `=== Directory: src/utils/ ===` line whenever the output moves into another directory"
+    )]
+    pub dir_markers: bool,
+
+    #[arg(
+        long,
+        alias = "include-symlink-targets-as-paths",
+        help = "Annotate headers of symlinked files with the resolved target path"
+    )]
+    pub symlink_targets: bool,
+
+    #[arg(
+        long,
+        value_name = "CMD",
+        help = "Pipe each file's content through CMD and include its output instead, with {} \
+                replaced by the path (e.g. --content-filter 'prettier --stdin-filepath {}'). \
+                Falls back to the raw content if CMD fails"
+    )]
+    pub content_filter: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "COLUMNS",
+        value_parser = clap::value_parser!(u16).range(10..),
+        help = "Hard-wrap content lines longer than COLUMNS, breaking at spaces and keeping \
+                indentation. Meant for prose and logs: wrapping changes the meaning of some code"
+    )]
+    pub wrap: Option<u16>,
+
+    #[arg(
+        long,
+        help = "Shrink content without changing what code means: strip trailing whitespace, \
+                collapse blank-line runs to one and drop blank lines at each file's start and end"
+    )]
+    pub compact: bool,
+
+    #[arg(
+        long,
+        alias = "max-line-count",
+        value_name = "N",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        help = "Cap each file at its first N lines, ending it with a marker giving its total. \
+                A safety limit: shorter files are untouched"
+    )]
+    pub max_lines: Option<usize>,
+
+    #[arg(
+        long,
+        alias = "every-nth-line",
+        value_name = "N",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(2..),
+        conflicts_with = "max_lines",
+        help = "For files longer than --sample-above lines (huge logs), include only every Nth \
+                line, under a marker saying the file was sampled"
+    )]
+    pub sample_lines: Option<usize>,
+
+    #[arg(
+        long,
+        value_name = "LINES",
+        default_value_t = 1000,
+        requires = "sample_lines",
+        help = "Line count a file must exceed before --sample-lines thins it"
+    )]
+    pub sample_above: usize,
+
+    #[arg(
+        long,
+        help = "Drop the single newline at the very end of the output (newlines between files are kept)"
+    )]
+    pub no_final_newline: bool,
+
+    #[arg(
+        long,
+        value_name = "REPLACEMENT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "",
+        help = "Replace non-ASCII characters in the output with REPLACEMENT \
+                (default: drop them), e.g. --ascii-only='?'"
+    )]
+    pub ascii_only: Option<String>,
+
     #[arg(
         long,
         value_enum,
         default_value = "xml",
         help = "Output format: xml (default) wraps files in <file path=\"...\"> tags \
-                inside a <context> block; markdown uses ## headings and fenced code blocks"
+                inside a <context> block; markdown uses ## headings and fenced code blocks; \
+                csv lists path,size,lines,extension per file without content; summary lists \
+                path: summary per file; single-block puts everything in one fenced code block"
     )]
     pub format: crate::formatter::FormatChoice,
+
+    #[arg(
+        long,
+        help = "List each file with a one-line summary (its leading doc comment or first \
+                meaningful line) instead of its content. Same as --format summary",
+        conflicts_with = "format"
+    )]
+    pub summary_only: bool,
+
+    #[arg(
+        long,
+        help = "Put all files in one markdown code block, each introduced by a \
+                --- File: path --- line, instead of a block per file. The fence is made \
+                longer than any backtick run in the content. Same as --format single-block",
+        conflicts_with_all = ["format", "summary_only"]
+    )]
+    pub single_block: bool,
+
+    #[arg(
+        long,
+        value_name = "EXT=LANG",
+        value_parser = crate::formatter::parse_lang_mapping,
+        help = "Use LANG as the markdown fence language for files ending in EXT \
+                (e.g. --lang-map .tsx=typescript). Repeatable; overrides the config file",
+        action = clap::ArgAction::Append,
+    )]
+    pub lang_map: Vec<(String, String)>,
 }
 
 impl RenderArgs {
-    pub fn header(&self) -> PathHeader {
-        if self.no_path {
+    /// `--format`, or the format `--summary-only` / `--single-block` stand for.
+    pub fn format(&self) -> crate::formatter::FormatChoice {
+        if self.summary_only {
+            crate::formatter::FormatChoice::Summary
+        } else if self.single_block {
+            crate::formatter::FormatChoice::SingleBlock
+        } else {
+            self.format
+        }
+    }
+
+    /// Header style from the flags, or `default` (the configured one) if none was given.
+    pub fn header(&self, default: PathHeader) -> PathHeader {
+        if self.no_path || self.separators_only {
             PathHeader::None
         } else if self.relative {
             PathHeader::Relative
-        } else {
+        } else if self.absolute {
             PathHeader::Absolute
+        } else {
+            default
         }
     }
 }
@@ -215,8 +1020,26 @@ pub struct OutputArgs {
     #[arg(short, long, help = "Print content to stdout")]
     pub print: bool,
 
-    #[arg(short, long, help = "Write content to specified file")]
-    pub write: Option<String>,
+    #[arg(
+        short,
+        long,
+        help = "List every skipped file (binary, unreadable) after the summary"
+    )]
+    pub verbose: bool,
+
+    #[arg(
+        long,
+        help = "List the clipboard backends cxt tries on this system, in order, and exit"
+    )]
+    pub list_clipboard_backends: bool,
+
+    #[arg(
+        short,
+        long,
+        value_name = "FILE",
+        help = "Write content to the specified file; repeat to write the same content to several"
+    )]
+    pub write: Vec<String>,
 
     #[arg(
         long,
@@ -225,31 +1048,248 @@ pub struct OutputArgs {
     )]
     pub compress: bool,
 
-    /// Run in non-interactive CI mode (disables clipboard operations)
-    #[arg(long, hide = true)]
+    #[arg(
+        long,
+        help = "Append to the --write file instead of overwriting it",
+        requires = "write",
+        conflicts_with_all = ["compress", "chunk_by_tokens"],
+    )]
+    pub append: bool,
+
+    #[arg(
+        long,
+        help = "With --append, skip files whose header is already in the target file",
+        requires = "append"
+    )]
+    pub dedup_headers_in_append: bool,
+
+    #[arg(
+        long,
+        value_name = "PATTERN",
+        requires = "write",
+        conflicts_with = "ci",
+        alias = "exclude-from-clipboard-only",
+        help = "Also copy to the clipboard, omitting files matching PATTERN (gitignore syntax); \
+                the --write file still gets everything. Repeatable",
+        action = clap::ArgAction::Append,
+    )]
+    pub clipboard_exclude: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Print wc-style line, word and byte counts of the output to stderr"
+    )]
+    pub wc: bool,
+
+    #[arg(
+        long,
+        help = "Print a SHA-256 of the output to stderr (over the exact bytes copied or written)"
+    )]
+    pub hash: bool,
+
+    #[arg(
+        long,
+        alias = "copy-and-print-path",
+        help = "After the run, print one JSON line to stdout with the destination actually used, \
+                the file count and the bytes written; status messages move to stderr"
+    )]
+    pub report_json: bool,
+
+    #[arg(
+        long,
+        alias = "count-per-extension",
+        help = "Print file count and total bytes per extension instead of copying content"
+    )]
+    pub ext_summary: bool,
+
+    #[arg(
+        long,
+        alias = "count-lines-per-file",
+        help = "List each file with its line count, longest first, instead of copying content"
+    )]
+    pub line_counts: bool,
+
+    #[arg(
+        long,
+        value_name = "MODEL",
+        value_parser = clap::builder::PossibleValuesParser::new(
+            crate::token_counter::INPUT_PRICES.iter().map(|(model, _)| *model)
+        ),
+        help = "Print the approximate input cost of the output's tokens for MODEL to stderr"
+    )]
+    pub estimate_cost: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "CMD",
+        help = "Run CMD for each resolved file instead of copying content, with {} replaced by \
+                the path (e.g. --exec 'wc -l {}'). End CMD with '{} +' to run it once with all paths",
+        conflicts_with_all = ["write", "print", "ext_summary", "line_counts", "stdin_content"],
+    )]
+    pub exec: Option<String>,
+
+    #[arg(
+        long,
+        alias = "copy-as-file-uri-list",
+        help = "Copy the resolved files as file:// URIs (text/uri-list, one per line) instead of \
+                their content, for pasting files into other applications",
+        conflicts_with_all = ["exec", "ext_summary", "line_counts", "stdin_content", "clipboard_exclude", "wc"],
+    )]
+    pub as_uri_list: bool,
+
+    #[arg(
+        long,
+        help = "Print the paths of the included files to stdout, one per line, while the content \
+                goes to the clipboard or --write file; summaries move to stderr",
+        conflicts_with_all = ["print", "as_uri_list", "exec", "ext_summary", "line_counts"],
+    )]
+    pub manifest_stdout: bool,
+
+    #[arg(
+        long,
+        alias = "entries-first-line-preview",
+        requires = "manifest_stdout",
+        help = "With --manifest-stdout, follow each path with its first non-blank line, truncated"
+    )]
+    pub list_preview: bool,
+
+    #[arg(
+        long,
+        value_name = "TOKENS",
+        help = "Split the output into chunks of at most TOKENS tokens, breaking only between \
+                files. Chunks are written next to the --write file as NAME.1.EXT, NAME.2.EXT, ...",
+        requires = "write",
+        conflicts_with_all = ["exec", "ext_summary", "line_counts", "as_uri_list", "clipboard_exclude",
+                              "stdin_content"],
+    )]
+    pub chunk_by_tokens: Option<usize>,
+
+    #[arg(
+        long,
+        value_name = "TOKENS",
+        alias = "copy-chunked-to-clipboard",
+        help = "Copy the output to the clipboard in chunks of at most TOKENS tokens, breaking only \
+                between files, and wait for Enter before copying the next one",
+        conflicts_with_all = ["write", "print", "ci", "chunk_by_tokens", "exec", "ext_summary",
+                              "line_counts", "as_uri_list", "clipboard_exclude", "manifest_stdout",
+                              "stdin_content"],
+    )]
+    pub clipboard_paginate: Option<usize>,
+
+    #[arg(
+        long,
+        alias = "print-only-on-failure",
+        help = "If copying to the clipboard fails, print the content to stdout instead"
+    )]
+    pub fallback_stdout: bool,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "If copying to the clipboard fails, write the content to PATH instead. \
+                Without it, headless systems fall back to a new cxt_clipboard_*.txt in the temp dir"
+    )]
+    pub clipboard_fallback_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "BYTES",
+        default_value = "0",
+        value_parser = parse_size,
+        help = "Don't copy output larger than BYTES (e.g. 512K, 2M; 0, the default, = no limit) \
+                to the clipboard; write it to a temp file (or stdout with --fallback-stdout) instead"
+    )]
+    pub max_clipboard_size: u64,
+
+    #[arg(
+        long,
+        help = "Non-interactive mode for scripts and CI: never touch the clipboard or open the TUI. \
+                Output goes to stdout unless --write is given; paths come from arguments or stdin"
+    )]
     pub ci: bool,
 }
 
+/// Parse a human duration: a number followed by s, m, h, d or w.
+fn parse_duration(s: &str) -> Result<std::time::Duration, String> {
+    let s = s.trim();
+    let unit_at = s
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| format!("missing unit in '{s}' (use s, m, h, d or w)"))?;
+    let (digits, unit) = s.split_at(unit_at);
+    let n: u64 = digits
+        .parse()
+        .map_err(|_| format!("invalid duration '{s}'"))?;
+    let secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3_600,
+        "d" => 86_400,
+        "w" => 604_800,
+        _ => {
+            return Err(format!(
+                "unknown duration unit '{unit}' (use s, m, h, d or w)"
+            ))
+        }
+    };
+    n.checked_mul(secs)
+        .map(std::time::Duration::from_secs)
+        .ok_or_else(|| format!("duration '{s}' is too large"))
+}
+
+/// Parse a byte count with an optional K/M/G suffix (powers of 1024).
+fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let (digits, multiplier) = match s.char_indices().last() {
+        Some((i, c)) if c.is_ascii_alphabetic() => {
+            let multiplier = match c.to_ascii_uppercase() {
+                'K' => 1024,
+                'M' => 1024 * 1024,
+                'G' => 1024 * 1024 * 1024,
+                _ => return Err(format!("unknown size suffix '{c}' (use K, M or G)")),
+            };
+            (&s[..i], multiplier)
+        }
+        _ => (s, 1),
+    };
+    let n = digits
+        .trim()
+        .parse::<u64>()
+        .map_err(|_| format!("invalid size '{s}'"))?;
+    n.checked_mul(multiplier)
+        .ok_or_else(|| format!("size '{s}' is too large"))
+}
+
 impl OutputArgs {
     pub fn destination(&self) -> Destination {
-        if let Some(ref file_path) = self.write {
-            let path = if self.compress && !file_path.ends_with(".gz") {
-                PathBuf::from(format!("{file_path}.gz"))
-            } else {
-                PathBuf::from(file_path)
-            };
+        if self.ext_summary || self.line_counts || self.exec.is_some() {
+            return Destination::Discard;
+        }
+        if !self.write.is_empty() {
+            let paths = self
+                .write
+                .iter()
+                .map(|file_path| {
+                    if self.compress && !file_path.ends_with(".gz") {
+                        PathBuf::from(format!("{file_path}.gz"))
+                    } else {
+                        PathBuf::from(file_path)
+                    }
+                })
+                .collect();
             return Destination::File {
-                path,
+                paths,
                 gzip: self.compress,
+                append: self.append,
             };
         }
-        if self.print && self.ci {
-            return Destination::Stdout;
-        }
         if !self.ci {
             return Destination::Clipboard { echo: self.print };
         }
-        Destination::Discard
+        // The manifest owns stdout, so the content has nowhere else to go.
+        if self.manifest_stdout {
+            return Destination::Discard;
+        }
+        Destination::Stdout
     }
 }
 
@@ -285,10 +1325,11 @@ mod tests {
     }
 
     #[test]
-    fn dest_discard() {
+    fn dest_ci_alone_prints() {
         let a = parse(&["cxt", "src/", "--ci"]);
-        let d = a.output.destination();
-        assert!(matches!(d, Destination::Discard));
+        assert!(matches!(a.output.destination(), Destination::Stdout));
+        let a = parse(&["cxt", "src/", "--ci", "--manifest-stdout"]);
+        assert!(matches!(a.output.destination(), Destination::Discard));
     }
 
     #[test]
@@ -296,8 +1337,8 @@ mod tests {
         let a = parse(&["cxt", "src/", "--write", "out.txt"]);
         let d = a.output.destination();
         match d {
-            Destination::File { path, gzip } => {
-                assert_eq!(path, PathBuf::from("out.txt"));
+            Destination::File { paths, gzip, .. } => {
+                assert_eq!(paths, [PathBuf::from("out.txt")]);
                 assert!(!gzip);
             }
             _ => panic!("expected File"),
@@ -309,8 +1350,8 @@ mod tests {
         let a = parse(&["cxt", "src/", "--write", "out.txt", "--compress"]);
         let d = a.output.destination();
         match d {
-            Destination::File { path, gzip } => {
-                assert_eq!(path, PathBuf::from("out.txt.gz"));
+            Destination::File { paths, gzip, .. } => {
+                assert_eq!(paths, [PathBuf::from("out.txt.gz")]);
                 assert!(gzip);
             }
             _ => panic!("expected File"),
@@ -322,32 +1363,71 @@ mod tests {
         let a = parse(&["cxt", "src/", "--write", "out.gz", "--compress"]);
         let d = a.output.destination();
         match d {
-            Destination::File { path, gzip } => {
-                assert_eq!(path, PathBuf::from("out.gz"));
+            Destination::File { paths, gzip, .. } => {
+                assert_eq!(paths, [PathBuf::from("out.gz")]);
                 assert!(gzip);
             }
             _ => panic!("expected File"),
         }
     }
 
+    #[test]
+    fn dest_file_repeated_write_keeps_every_target() {
+        let a = parse(&[
+            "cxt",
+            "src/",
+            "-w",
+            "a.txt",
+            "--write",
+            "b.gz",
+            "--compress",
+        ]);
+        match a.output.destination() {
+            Destination::File { paths, .. } => {
+                assert_eq!(paths, [PathBuf::from("a.txt.gz"), PathBuf::from("b.gz")]);
+            }
+            _ => panic!("expected File"),
+        }
+    }
+
     // ── PathHeader ────────────────────────────────────────────────────────────
 
     #[test]
     fn header_absolute() {
         let a = parse(&["cxt", "src/"]);
-        assert_eq!(a.render.header(), PathHeader::Absolute);
+        assert_eq!(a.render.header(PathHeader::Absolute), PathHeader::Absolute);
+    }
+
+    #[test]
+    fn header_absolute_overrides_default() {
+        let a = parse(&["cxt", "src/", "--absolute"]);
+        assert_eq!(a.render.header(PathHeader::Relative), PathHeader::Absolute);
+        let b = parse(&["cxt", "src/"]);
+        assert_eq!(b.render.header(PathHeader::Relative), PathHeader::Relative);
+    }
+
+    #[test]
+    fn content_only_is_no_path() {
+        let a = parse(&["cxt", "src/", "--content-only"]);
+        assert_eq!(a.render.header(PathHeader::Absolute), PathHeader::None);
+    }
+
+    #[test]
+    fn conflict_absolute_and_no_path() {
+        let result = Args::try_parse_from(["cxt", "src/", "--absolute", "--no-path"]);
+        assert!(result.is_err());
     }
 
     #[test]
     fn header_relative() {
         let a = parse(&["cxt", "src/", "--relative"]);
-        assert_eq!(a.render.header(), PathHeader::Relative);
+        assert_eq!(a.render.header(PathHeader::Absolute), PathHeader::Relative);
     }
 
     #[test]
     fn header_none() {
         let a = parse(&["cxt", "src/", "--no-path"]);
-        assert_eq!(a.render.header(), PathHeader::None);
+        assert_eq!(a.render.header(PathHeader::Absolute), PathHeader::None);
     }
 
     // ── Mode ──────────────────────────────────────────────────────────────────
@@ -358,6 +1438,25 @@ mod tests {
         assert!(matches!(a.mode(), Mode::Aggregate));
     }
 
+    #[test]
+    fn mode_list_clipboard_backends() {
+        let a = parse(&["cxt", "--list-clipboard-backends"]);
+        assert!(matches!(a.mode(), Mode::ListClipboardBackends));
+    }
+
+    #[test]
+    fn mode_subcommands() {
+        assert!(matches!(
+            parse(&["cxt", "clear-clipboard"]).mode(),
+            Mode::ClearClipboard
+        ));
+        assert!(matches!(
+            parse(&["cxt", "init-config", "--force"]).mode(),
+            Mode::InitConfig { force: true }
+        ));
+        assert!(Args::try_parse_from(["cxt", "clear-clipboard", "-p"]).is_err());
+    }
+
     #[test]
     fn mode_list_languages() {
         let a = parse(&["cxt", "--lang", "help"]);
@@ -380,31 +1479,185 @@ mod tests {
 
     #[test]
     fn conflict_relative_and_no_path() {
-        let result = Args::try_parse_from(&["cxt", "src/", "--relative", "--no-path"]);
+        let result = Args::try_parse_from(["cxt", "src/", "--relative", "--no-path"]);
         assert!(result.is_err());
     }
 
     #[test]
     fn conflict_df_and_st() {
-        let result = Args::try_parse_from(&["cxt", "--df", "--st"]);
+        let result = Args::try_parse_from(["cxt", "--df", "--st"]);
         assert!(result.is_err());
     }
 
     #[test]
     fn conflict_df_and_write() {
-        let result = Args::try_parse_from(&["cxt", "--df", "--write", "out.txt"]);
+        let result = Args::try_parse_from(["cxt", "--df", "--write", "out.txt"]);
         assert!(result.is_err());
     }
 
     #[test]
     fn conflict_df_and_tui() {
-        let result = Args::try_parse_from(&["cxt", "--df", "--tui"]);
+        let result = Args::try_parse_from(["cxt", "--df", "--tui"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn conflict_clipboard_exclude_and_ci() {
+        let result = Args::try_parse_from([
+            "cxt",
+            "src/",
+            "--ci",
+            "-w",
+            "out.txt",
+            "--clipboard-exclude",
+            "*.md",
+        ]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn conflict_chunk_by_tokens_and_stdin_content() {
+        let result = Args::try_parse_from([
+            "cxt",
+            "--stdin-content",
+            "virt.txt",
+            "-w",
+            "out.txt",
+            "--chunk-by-tokens",
+            "1000",
+        ]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn conflict_edit_paths_and_tui() {
+        let result = Args::try_parse_from(["cxt", "--edit-paths", "--tui"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn header_meta_defaults_to_all_fields() {
+        let a = parse(&["cxt", "src/", "--header-meta"]);
+        assert_eq!(
+            a.render.header_meta,
+            Some(vec![MetaField::Size, MetaField::Lines, MetaField::Modified])
+        );
+        let a = parse(&["cxt", "src/", "--header-meta=lines"]);
+        assert_eq!(a.render.header_meta, Some(vec![MetaField::Lines]));
+        assert_eq!(a.paths, vec!["src/".to_string()]);
+    }
+
+    #[test]
+    fn max_clipboard_size_parses_suffixes() {
+        let a = parse(&["cxt", "src/"]);
+        assert_eq!(a.output.max_clipboard_size, 0);
+        let a = parse(&["cxt", "src/", "--max-clipboard-size", "512K"]);
+        assert_eq!(a.output.max_clipboard_size, 512 * 1024);
+        let a = parse(&["cxt", "src/", "--max-clipboard-size", "0"]);
+        assert_eq!(a.output.max_clipboard_size, 0);
+        assert!(Args::try_parse_from(["cxt", "--max-clipboard-size", "2X"]).is_err());
+    }
+
+    #[test]
+    fn input_formats_parse_path_lists() {
+        assert_eq!(
+            InputFormat::Lines.parse("a.rs\r\n\nb c.rs\n"),
+            ["a.rs", "b c.rs"]
+        );
+        assert_eq!(
+            InputFormat::Null.parse("a.rs\0b\nc.rs\0"),
+            ["a.rs", "b\nc.rs"]
+        );
+        let status = " M src/a.rs\n?? notes.txt\nR  old.rs -> new.rs\n D gone.rs\n\
+                      !! target\nA  \"with \\\"quote\\\".rs\"\n";
+        assert_eq!(
+            InputFormat::Porcelain.parse(status),
+            ["src/a.rs", "notes.txt", "new.rs", "with \"quote\".rs"]
+        );
+    }
+
+    #[test]
+    fn json_path_lists_parse_with_locations() {
+        assert_eq!(
+            parse_json_paths(r#"["a.rs", {"path": "b.rs", "line": 3}]"#).unwrap(),
+            ["a.rs", "b.rs"]
+        );
+        let err = parse_json_paths("[\n  \"a.rs\",\n  oops\n]").unwrap_err();
+        assert!(err.contains("line 3 column 3"), "{err}");
+        assert!(parse_json_paths(r#"{"path": "a.rs"}"#).is_err());
+        let err = parse_json_paths(r#"["a.rs", 7]"#).unwrap_err();
+        assert!(err.contains("entry 1"), "{err}");
+    }
+
+    #[test]
+    fn select_globs_accumulate_and_conflict_with_edit_paths() {
+        let a = parse(&["cxt", "--select", "*.rs", "--select-by-glob", "*.toml"]);
+        assert_eq!(a.source.select, ["*.rs", "*.toml"]);
+        assert!(Args::try_parse_from(["cxt", "--select", "*.rs", "--edit-paths"]).is_err());
+        let a = parse(&["cxt", "--tui-start-selected", "src", "Cargo.toml"]);
+        assert!(a.source.tui_start_selected);
+        assert_eq!(a.paths, ["src", "Cargo.toml"]);
+    }
+
+    #[test]
+    fn cancel_exit_code_defaults_to_130() {
+        assert_eq!(parse(&["cxt", "--tui"]).source.cancel_exit_code, 130);
+        let a = parse(&["cxt", "--tui", "--cancel-exit-code", "0"]);
+        assert_eq!(a.source.cancel_exit_code, 0);
+    }
+
+    #[test]
+    fn clipboard_exclude_requires_write() {
+        let result = Args::try_parse_from(["cxt", "src/", "--clipboard-exclude", "*.env"]);
         assert!(result.is_err());
+        let a = parse(&[
+            "cxt",
+            "src/",
+            "-w",
+            "out.txt",
+            "--clipboard-exclude",
+            "*.env",
+        ]);
+        assert_eq!(a.output.clipboard_exclude, vec!["*.env".to_string()]);
+    }
+
+    #[test]
+    fn modified_within_parses_units() {
+        assert_eq!(
+            parse_duration("90s"),
+            Ok(std::time::Duration::from_secs(90))
+        );
+        assert_eq!(
+            parse_duration("2h"),
+            Ok(std::time::Duration::from_secs(7_200))
+        );
+        assert_eq!(
+            parse_duration("3d"),
+            Ok(std::time::Duration::from_secs(259_200))
+        );
+        assert!(parse_duration("3").is_err());
+        assert!(parse_duration("3y").is_err());
+        assert_eq!(
+            parse_duration("99999999999999999w"),
+            Err("duration '99999999999999999w' is too large".to_string())
+        );
+    }
+
+    #[test]
+    fn sizes_parse_suffixes_and_reject_overflow() {
+        assert_eq!(parse_size("512"), Ok(512));
+        assert_eq!(parse_size("2M"), Ok(2 * 1024 * 1024));
+        assert_eq!(parse_size("1g"), Ok(1024 * 1024 * 1024));
+        assert!(parse_size("2T").is_err());
+        assert_eq!(
+            parse_size("99999999999G"),
+            Err("size '99999999999G' is too large".to_string())
+        );
     }
 
     #[test]
     fn compress_requires_write() {
-        let result = Args::try_parse_from(&["cxt", "src/", "--compress"]);
+        let result = Args::try_parse_from(["cxt", "src/", "--compress"]);
         assert!(result.is_err());
     }
 }
@@ -8,6 +8,8 @@ use std::rc::Rc;
 /// Process-based backends stream directly; `flush_to_clipboard` is a no-op for them.
 /// `ArboardBackend` must buffer first, so it overrides `flush_to_clipboard`.
 pub trait ClipboardBackend {
+    /// Short label shown by `--list-clipboard-backends`.
+    fn name(&self) -> &str;
     fn is_available(&self) -> bool;
     fn get_writer(&mut self) -> Result<Box<dyn Write>>;
     fn flush_to_clipboard(&mut self) -> Result<()> {
@@ -127,18 +129,30 @@ fn command_available(program: &str) -> bool {
 
 // ── Backends ─────────────────────────────────────────────────────────────────
 
-pub struct WlCopyBackend;
+/// `mime` is passed as `wl-copy --type`; `None` lets wl-copy infer plain text.
+pub struct WlCopyBackend {
+    pub mime: Option<&'static str>,
+}
 impl ClipboardBackend for WlCopyBackend {
+    fn name(&self) -> &str {
+        "wl-copy"
+    }
     fn is_available(&self) -> bool {
         command_available("wl-copy")
     }
     fn get_writer(&mut self) -> Result<Box<dyn Write>> {
-        spawn_process_writer("wl-copy", &[])
+        match self.mime {
+            Some(mime) => spawn_process_writer("wl-copy", &["--type", mime]),
+            None => spawn_process_writer("wl-copy", &[]),
+        }
     }
 }
 
 pub struct X11Backend;
 impl ClipboardBackend for X11Backend {
+    fn name(&self) -> &str {
+        "xclip"
+    }
     fn is_available(&self) -> bool {
         !std::env::var("DISPLAY").unwrap_or_default().is_empty() && command_available("xclip")
     }
@@ -152,6 +166,9 @@ pub struct PbcopyBackend;
 
 #[cfg(target_os = "macos")]
 impl ClipboardBackend for PbcopyBackend {
+    fn name(&self) -> &str {
+        "pbcopy"
+    }
     fn is_available(&self) -> bool {
         command_available("pbcopy")
     }
@@ -160,18 +177,95 @@ impl ClipboardBackend for PbcopyBackend {
     }
 }
 
-pub struct WslBackend;
+/// `verify` reads the clipboard back through PowerShell after `clip.exe`
+/// exits and warns if it doesn't match what was sent.
+pub struct WslBackend {
+    verify: bool,
+    sent: Rc<RefCell<Vec<u8>>>,
+}
+
+impl WslBackend {
+    pub fn new(verify: bool) -> Self {
+        Self {
+            verify,
+            sent: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+}
+
 impl ClipboardBackend for WslBackend {
+    fn name(&self) -> &str {
+        "clip.exe (WSL)"
+    }
     fn is_available(&self) -> bool {
         (std::env::var("WSL_DISTRO_NAME").is_ok() || std::env::var("WSL_ENV").is_ok())
             && std::path::Path::new("/mnt/c/Windows/System32/clip.exe").exists()
     }
     fn get_writer(&mut self) -> Result<Box<dyn Write>> {
         let inner = spawn_process_writer("/mnt/c/Windows/System32/clip.exe", &[])?;
-        Ok(Box::new(CrlfWriter { inner }))
+        let clip = Box::new(CrlfWriter { inner });
+        if !self.verify {
+            return Ok(clip);
+        }
+        self.sent.borrow_mut().clear();
+        Ok(Box::new(RecordingWriter {
+            inner: clip,
+            record: Rc::clone(&self.sent),
+        }))
+    }
+    fn flush_to_clipboard(&mut self) -> Result<()> {
+        if !self.verify {
+            return Ok(());
+        }
+        let output = Command::new("powershell.exe")
+            .args(["-NoProfile", "-Command", "Get-Clipboard -Raw"])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output();
+        let sent = self.sent.borrow();
+        match output {
+            Ok(out) if out.status.success() => {
+                let expected = String::from_utf8_lossy(&sent);
+                let actual = String::from_utf8_lossy(&out.stdout);
+                if !clipboard_text_matches(&expected, &actual) {
+                    eprintln!(
+                        "Warning: clipboard readback does not match what was copied \
+                         ({} bytes sent, {} bytes read back)",
+                        sent.len(),
+                        out.stdout.len()
+                    );
+                }
+            }
+            _ => eprintln!("Warning: could not read the clipboard back via powershell.exe"),
+        }
+        Ok(())
+    }
+}
+
+/// Pass-through writer that keeps a copy of everything written, for readback checks.
+struct RecordingWriter {
+    inner: Box<dyn Write>,
+    record: Rc<RefCell<Vec<u8>>>,
+}
+
+impl Write for RecordingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.record.borrow_mut().extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
     }
 }
 
+/// Compare sent and read-back clipboard text, ignoring CRLF/LF differences and
+/// the trailing newline PowerShell appends.
+fn clipboard_text_matches(sent: &str, read_back: &str) -> bool {
+    let normalize = |s: &str| s.replace("\r\n", "\n").trim_end_matches('\n').to_string();
+    normalize(sent) == normalize(read_back)
+}
+
 /// Shared buffer writer for ArboardBackend (arboard cannot accept a stream).
 struct SharedVecWriter(Rc<RefCell<Vec<u8>>>);
 impl Write for SharedVecWriter {
@@ -184,6 +278,32 @@ impl Write for SharedVecWriter {
     }
 }
 
+/// Delays between `arboard::Clipboard::new()` attempts. The first connection
+/// can fail transiently right after login while the display server settles.
+const ARBOARD_RETRY_DELAYS_MS: [u64; 3] = [50, 150, 400];
+
+/// Open an arboard clipboard, retrying with backoff before giving up.
+pub fn open_arboard() -> Result<arboard::Clipboard, arboard::Error> {
+    let mut result = arboard::Clipboard::new();
+    for delay in ARBOARD_RETRY_DELAYS_MS {
+        if result.is_ok() {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(delay));
+        result = arboard::Clipboard::new();
+    }
+    result
+}
+
+/// The clipboard's current text, for `cxt paste`.
+pub fn read_text() -> Result<String> {
+    let mut clipboard =
+        open_arboard().map_err(|e| anyhow::anyhow!("Clipboard not available: {e}"))?;
+    clipboard
+        .get_text()
+        .map_err(|e| anyhow::anyhow!("Could not read text from the clipboard: {e}"))
+}
+
 pub struct ArboardBackend {
     buffer: Rc<RefCell<Vec<u8>>>,
     clipboard: Option<arboard::Clipboard>,
@@ -199,6 +319,11 @@ impl ArboardBackend {
 }
 
 impl ClipboardBackend for ArboardBackend {
+    fn name(&self) -> &str {
+        "arboard"
+    }
+    /// A single attempt: probing shouldn't pay the retry backoff, which only
+    /// the actual copy in `flush_to_clipboard` needs.
     fn is_available(&self) -> bool {
         arboard::Clipboard::new().is_ok()
     }
@@ -211,7 +336,7 @@ impl ClipboardBackend for ArboardBackend {
 
     fn flush_to_clipboard(&mut self) -> Result<()> {
         if self.clipboard.is_none() {
-            self.clipboard = arboard::Clipboard::new().ok();
+            self.clipboard = open_arboard().ok();
         }
         let buf = self.buffer.borrow();
         let text = String::from_utf8_lossy(&buf);
@@ -239,6 +364,9 @@ impl NamedProcessBackend {
 }
 
 impl ClipboardBackend for NamedProcessBackend {
+    fn name(&self) -> &str {
+        self.program
+    }
     fn is_available(&self) -> bool {
         command_available(self.program)
     }
@@ -246,3 +374,15 @@ impl ClipboardBackend for NamedProcessBackend {
         spawn_process_writer(self.program, self.args)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn readback_comparison_ignores_line_endings() {
+        assert!(clipboard_text_matches("a\nb\n", "a\r\nb\r\n"));
+        assert!(clipboard_text_matches("a\nb", "a\r\nb\r\n"));
+        assert!(!clipboard_text_matches("a\nb\n", "a\r\n"));
+    }
+}
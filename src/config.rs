@@ -0,0 +1,385 @@
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+
+use crate::cli::{Destination, PathHeader};
+
+/// Where output goes when no destination flag (`-p`, `-w`, `--ci`) is given.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DefaultOutput {
+    Clipboard,
+    /// Clipboard and stdout, like `--print`.
+    Print,
+    /// Stdout only; the clipboard is not touched.
+    Stdout,
+}
+
+impl DefaultOutput {
+    pub fn destination(self) -> Destination {
+        match self {
+            DefaultOutput::Clipboard => Destination::Clipboard { echo: false },
+            DefaultOutput::Print => Destination::Clipboard { echo: true },
+            DefaultOutput::Stdout => Destination::Stdout,
+        }
+    }
+}
+
+/// User defaults read from `config.toml`. CLI flags always take precedence.
+#[derive(Default, Debug)]
+pub struct Config {
+    pub default_output: Option<DefaultOutput>,
+    /// Header style when none of `--absolute`/`--relative`/`--no-path` is given.
+    pub path_header: Option<PathHeader>,
+    /// `[lang_map]` entries: extension (no dot) → markdown fence language.
+    pub lang_map: Vec<(String, String)>,
+    /// Directory-walk entry cap when `--max-entries` isn't given.
+    pub max_entries: Option<usize>,
+    /// Replaces the built-in list of file names treated as sensitive.
+    pub sensitive_patterns: Option<Vec<String>>,
+}
+
+/// `$CXT_CONFIG`, else `$XDG_CONFIG_HOME/cxt/config.toml`, else
+/// `~/.config/cxt/config.toml` (`%APPDATA%\cxt\config.toml` on Windows).
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("CXT_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))?;
+    Some(base.join("cxt").join("config.toml"))
+}
+
+/// Written by `cxt init-config` (through `template`): every key, commented
+/// out at its default.
+const TEMPLATE: &str = r#"# cxt configuration. Command-line flags always take precedence.
+# Uncomment a line to change that default.
+
+# Where output goes when no -p / -w flag is given: "clipboard", "print" (clipboard
+# and stdout) or "stdout"
+# default_output = "clipboard"
+
+# Header paths when no --absolute / --relative / --no-path is given: "absolute",
+# "relative" or "none"
+# path_header = "absolute"
+
+# Abort directory walks that visit more entries than this (0 disables the limit)
+# max_entries = 1_000_000
+
+# File names left out unless --allow-sensitive is given (gitignore syntax, matched
+# against the name). Setting it replaces the built-in list, shown here in full, so
+# keep every entry you still want. Quote each pattern; commas inside quotes (as in
+# "*.{pem,key}") stay part of the pattern.
+# sensitive_patterns = SENSITIVE_PATTERNS
+
+# Markdown fence languages for extra or unusual extensions; --lang-map EXT=LANG
+# wins over these. Keep this table after the keys above.
+[lang_map]
+# tsx = "typescript"
+# tpl = "jinja"
+"#;
+
+/// Write the commented default config to `config_path()` (`cxt init-config`),
+/// returning where it went. An existing file is kept unless `force`.
+pub fn init(force: bool) -> Result<PathBuf> {
+    let path = config_path().context("Cannot locate a config directory (HOME is not set)")?;
+    if path.exists() && !force {
+        anyhow::bail!(
+            "'{}' already exists; pass --force to overwrite it",
+            path.display()
+        );
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create '{}'", dir.display()))?;
+    }
+    std::fs::write(&path, template())
+        .with_context(|| format!("Failed to write '{}'", path.display()))?;
+    Ok(path)
+}
+
+/// `TEMPLATE` with the built-in sensitive patterns filled in.
+fn template() -> String {
+    let patterns: Vec<String> = crate::content_aggregator::SENSITIVE_PATTERNS
+        .iter()
+        .map(|p| format!("\"{p}\""))
+        .collect();
+    TEMPLATE.replace(
+        "= SENSITIVE_PATTERNS",
+        &format!("= [{}]", patterns.join(", ")),
+    )
+}
+
+/// Load the config file; a missing file yields the defaults. Problems are
+/// reported as warnings so a bad config never blocks a copy.
+pub fn load() -> Config {
+    let Some(path) = config_path() else {
+        return Config::default();
+    };
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        log::debug!("no config file at {}", path.display());
+        return Config::default();
+    };
+    log::debug!("loaded config from {}", path.display());
+    let (config, warnings) = parse(&content);
+    for warning in warnings {
+        eprintln!("Warning: {}: {warning}", path.display());
+    }
+    config
+}
+
+/// Parse the `key = "value"` subset of TOML that cxt uses, plus a
+/// `[lang_map]` table of `ext = "language"` entries. Strings may be basic
+/// (`"..."`, with escapes) or literal (`'...'`), and `#` starts a comment
+/// outside them.
+fn parse(content: &str) -> (Config, Vec<String>) {
+    let mut config = Config::default();
+    let mut warnings = Vec::new();
+    let mut in_lang_map = false;
+    for (n, raw) in content.lines().enumerate() {
+        let line = strip_comment(raw).trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(table) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_lang_map = table.trim() == "lang_map";
+            if !in_lang_map {
+                warnings.push(format!("line {}: unknown table [{table}]", n + 1));
+            }
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            warnings.push(format!("line {}: expected `key = value`", n + 1));
+            continue;
+        };
+        let key = unquote(key.trim());
+        let key = key.as_str();
+        let value = value.trim();
+        let scalar = unquote(value);
+        if in_lang_map {
+            match crate::formatter::parse_lang_mapping(&format!("{key}={scalar}")) {
+                Ok(mapping) => config.lang_map.push(mapping),
+                Err(e) => warnings.push(format!("line {}: {e}", n + 1)),
+            }
+            continue;
+        }
+        match key {
+            "default_output" => match scalar.as_str() {
+                "clipboard" => config.default_output = Some(DefaultOutput::Clipboard),
+                "print" => config.default_output = Some(DefaultOutput::Print),
+                "stdout" => config.default_output = Some(DefaultOutput::Stdout),
+                _ => warnings.push(format!(
+                    "line {}: default_output must be \"clipboard\", \"print\" or \"stdout\"",
+                    n + 1
+                )),
+            },
+            "path_header" => match scalar.as_str() {
+                "absolute" => config.path_header = Some(PathHeader::Absolute),
+                "relative" => config.path_header = Some(PathHeader::Relative),
+                "none" => config.path_header = Some(PathHeader::None),
+                _ => warnings.push(format!(
+                    "line {}: path_header must be \"absolute\", \"relative\" or \"none\"",
+                    n + 1
+                )),
+            },
+            "max_entries" => match scalar.replace('_', "").parse() {
+                Ok(max) => config.max_entries = Some(max),
+                Err(_) => warnings.push(format!(
+                    "line {}: max_entries must be a whole number",
+                    n + 1
+                )),
+            },
+            "sensitive_patterns" => match parse_list(value) {
+                Some(patterns) => config.sensitive_patterns = Some(patterns),
+                None => warnings.push(format!(
+                    "line {}: sensitive_patterns must be a list like [\".env\", \"*.pem\"]",
+                    n + 1
+                )),
+            },
+            _ => warnings.push(format!("line {}: unknown key '{key}'", n + 1)),
+        }
+    }
+    (config, warnings)
+}
+
+/// Byte offsets in `line` that lie outside any string, paired with their
+/// character. A backslash escapes the next character in basic strings only.
+fn unquoted_chars(line: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+    let mut quote = None;
+    let mut escaped = false;
+    line.char_indices().filter(move |&(_, c)| {
+        match quote {
+            Some('"') if escaped => escaped = false,
+            Some('"') if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None => return true,
+        }
+        false
+    })
+}
+
+/// `line` up to a `#` that isn't inside a string.
+fn strip_comment(line: &str) -> &str {
+    match unquoted_chars(line).find(|&(_, c)| c == '#') {
+        Some((at, _)) => &line[..at],
+        None => line,
+    }
+}
+
+/// The contents of a `"basic"` (escapes resolved) or `'literal'` string;
+/// anything else is returned as it is.
+fn unquote(value: &str) -> String {
+    if let Some(literal) = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+        return literal.to_string();
+    }
+    let Some(basic) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) else {
+        return value.to_string();
+    };
+    let mut out = String::with_capacity(basic.len());
+    let mut chars = basic.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// `["a", 'b']` as a list of strings; None if it isn't bracketed. Commas
+/// only separate items outside quotes, so `"*.{pem,key}"` stays whole.
+fn parse_list(value: &str) -> Option<Vec<String>> {
+    let inner = value.strip_prefix('[')?.strip_suffix(']')?;
+    let mut items = Vec::new();
+    let mut start = 0;
+    let commas = unquoted_chars(inner).filter(|&(_, c)| c == ',');
+    for end in commas.map(|(at, _)| at).chain([inner.len()]) {
+        let item = inner[start..end].trim();
+        if !item.is_empty() {
+            items.push(unquote(item));
+        }
+        start = end + 1;
+    }
+    Some(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_keys_are_valid_when_uncommented() {
+        let template = template();
+        let (config, warnings) = parse(&template);
+        assert!(warnings.is_empty() && config.default_output.is_none());
+        let uncommented: String = template
+            .lines()
+            .map(|l| match l.strip_prefix("# ") {
+                Some(rest) if rest.contains(" = ") => rest,
+                _ => l,
+            })
+            .map(|l| format!("{l}\n"))
+            .collect();
+        let (config, warnings) = parse(&uncommented);
+        assert_eq!(warnings, Vec::<String>::new());
+        assert_eq!(
+            config.max_entries,
+            Some(crate::content_aggregator::DEFAULT_MAX_ENTRIES)
+        );
+        assert_eq!(config.lang_map.len(), 2);
+        // Uncommenting the line restates the built-in list exactly.
+        let builtin: Vec<String> = crate::content_aggregator::SENSITIVE_PATTERNS
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        assert_eq!(config.sensitive_patterns, Some(builtin));
+    }
+
+    #[test]
+    fn parses_default_output() {
+        let (config, warnings) = parse("# defaults\ndefault_output = \"stdout\"\n");
+        assert_eq!(config.default_output, Some(DefaultOutput::Stdout));
+        assert!(warnings.is_empty());
+
+        let (config, _) = parse("path_header = \"relative\"\n");
+        assert_eq!(config.path_header, Some(PathHeader::Relative));
+
+        let (config, warnings) = parse("max_entries = 50_000\n");
+        assert_eq!(config.max_entries, Some(50_000));
+        assert!(warnings.is_empty());
+
+        let (config, warnings) = parse("sensitive_patterns = [\"*.pem\", \"*.secret\"]\n");
+        assert_eq!(
+            config.sensitive_patterns,
+            Some(vec!["*.pem".to_string(), "*.secret".to_string()])
+        );
+        assert!(warnings.is_empty());
+
+        let (config, _) = parse("sensitive_patterns = [\"*.{pem,key}\", \".env\"]\n");
+        assert_eq!(
+            config.sensitive_patterns,
+            Some(vec!["*.{pem,key}".to_string(), ".env".to_string()])
+        );
+    }
+
+    #[test]
+    fn trailing_comments_are_dropped_outside_strings() {
+        let (config, warnings) = parse("default_output = \"stdout\"  # comment\n");
+        assert_eq!(config.default_output, Some(DefaultOutput::Stdout));
+        assert!(warnings.is_empty());
+
+        let (config, warnings) =
+            parse("sensitive_patterns = [\"#notes\", '*.pem'] # not a pattern\n");
+        assert_eq!(
+            config.sensitive_patterns,
+            Some(vec!["#notes".to_string(), "*.pem".to_string()])
+        );
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn single_quoted_and_escaped_strings() {
+        let (config, warnings) = parse("default_output = 'print'\npath_header = 'none'\n");
+        assert_eq!(config.default_output, Some(DefaultOutput::Print));
+        assert_eq!(config.path_header, Some(PathHeader::None));
+        assert!(warnings.is_empty());
+
+        let (config, _) = parse(r#"sensitive_patterns = ["a\"b", 'c\d', "e\\f"]"#);
+        assert_eq!(
+            config.sensitive_patterns,
+            Some(vec![
+                "a\"b".to_string(),
+                r"c\d".to_string(),
+                r"e\f".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn parses_lang_map_table() {
+        let (config, warnings) = parse(
+            "default_output = \"print\"\n[lang_map]\n\".tsx\" = \"typescript\"\nbad = \"\"\n",
+        );
+        assert_eq!(config.default_output, Some(DefaultOutput::Print));
+        assert_eq!(
+            config.lang_map,
+            vec![("tsx".to_string(), "typescript".to_string())]
+        );
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn warns_on_unknown_keys_and_values() {
+        let (config, warnings) = parse("default_output = \"fax\"\ncolour = \"red\"\n");
+        assert_eq!(config.default_output, None);
+        assert_eq!(warnings.len(), 2);
+    }
+}
@@ -1,61 +1,292 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use rayon::prelude::*;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
-use std::io::{Read, Seek, SeekFrom, Write};
-use std::path::{Path, PathBuf};
+use std::io::{BufRead, Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::{mpsc, Arc};
 
+use crate::formatter::{FileHeader, FileStats};
+
 enum FileReadResult {
     Content(Vec<u8>),
     Binary,
-    LargeFile,
+    /// Too large to hold in memory; carries the size so it isn't re-read.
+    LargeFile(u64),
     Notebook,
-    ReadError(String),
+    ReadError(std::io::Error),
+}
+
+/// Why a file was left out of the output.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum SkipReason {
+    Binary,
+    PermissionDenied,
+    NotFound,
+    Unreadable,
+    /// Dropped by `--drop-outliers` for being much larger than the median file.
+    Outlier,
+    /// Already in the file `--dedup-headers-in-append` is appending to.
+    AlreadyPresent,
+    /// Named like a secrets file (`.env`, `id_rsa`, ...); see `--allow-sensitive`.
+    Sensitive,
+}
+
+impl SkipReason {
+    fn from_io(e: &std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::PermissionDenied => SkipReason::PermissionDenied,
+            std::io::ErrorKind::NotFound => SkipReason::NotFound,
+            _ => SkipReason::Unreadable,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SkipReason::Binary => "binary",
+            SkipReason::PermissionDenied => "permission denied",
+            SkipReason::NotFound => "not found",
+            SkipReason::Unreadable => "unreadable",
+            SkipReason::Outlier => "size outlier",
+            SkipReason::AlreadyPresent => "already in output",
+            SkipReason::Sensitive => "possibly sensitive",
+        }
+    }
+}
+
+/// A file that was skipped, kept for the end-of-run summary.
+pub struct SkippedFile {
+    pub path: PathBuf,
+    pub reason: SkipReason,
+    /// The underlying error message, if any.
+    pub detail: Option<String>,
+}
+
+/// A file's size and mtime from a single stat (the walker's, for walked
+/// files), passed along so later checks don't stat the file again.
+#[derive(Clone, Copy, Default)]
+struct FileMeta {
+    size: u64,
+    modified: Option<std::time::SystemTime>,
+}
+
+impl FileMeta {
+    fn of(metadata: &fs::Metadata) -> Self {
+        Self {
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+        }
+    }
+
+    /// True if modified at or after `cutoff`; files whose mtime can't be read
+    /// are kept rather than silently dropped.
+    fn modified_since(&self, cutoff: std::time::SystemTime) -> bool {
+        self.modified.is_none_or(|t| t >= cutoff)
+    }
 }
 
-fn read_file_for_aggregation(path: &Path) -> FileReadResult {
+fn read_file_for_aggregation(
+    path: &Path,
+    file_size: u64,
+    detection: BinaryDetection,
+) -> FileReadResult {
     if is_notebook(path) {
         return FileReadResult::Notebook;
     }
-    let file_size = match path.metadata() {
-        Ok(m) => m.len(),
-        Err(e) => {
-            return FileReadResult::ReadError(format!(
-                "Warning: Failed to read file '{}': {e}",
-                path.display()
-            ))
-        }
-    };
     if file_size > MAX_EXACT_BYTES {
         let mut file = match fs::File::open(path) {
             Ok(f) => f,
-            Err(e) => {
-                return FileReadResult::ReadError(format!(
-                    "Warning: Failed to open file '{}': {e}",
-                    path.display()
-                ))
-            }
+            Err(e) => return FileReadResult::ReadError(e),
         };
         let mut header = [0u8; 8192];
         let n = file.read(&mut header).unwrap_or(0);
-        if is_binary_content(&header[..n]) {
+        if detection.is_binary(&header[..n]) {
             return FileReadResult::Binary;
         }
-        return FileReadResult::LargeFile;
+        return FileReadResult::LargeFile(file_size);
     }
     match fs::read(path) {
         Ok(bytes) => {
-            if is_binary_content(&bytes) {
+            if detection.is_binary(&bytes) {
                 FileReadResult::Binary
             } else {
                 FileReadResult::Content(bytes)
             }
         }
-        Err(e) => FileReadResult::ReadError(format!(
-            "Warning: Failed to read file '{}': {e}",
-            path.display()
-        )),
+        Err(e) => FileReadResult::ReadError(e),
+    }
+}
+
+/// Render the `--header-meta` summary, e.g. "1.2K, 48 lines, modified 2024-03-01".
+/// The line count is omitted when `content` isn't in memory.
+fn describe_file(
+    meta: FileMeta,
+    content: Option<&[u8]>,
+    fields: &[crate::cli::MetaField],
+) -> String {
+    use crate::cli::MetaField;
+    let mut parts = Vec::new();
+    for field in fields {
+        match field {
+            MetaField::Size => parts.push(format_size(meta.size)),
+            MetaField::Lines => {
+                if let Some(bytes) = content {
+                    let lines = count_lines(bytes);
+                    parts.push(format!("{lines} line{}", if lines == 1 { "" } else { "s" }));
+                }
+            }
+            MetaField::Modified => {
+                if let Some(modified) = meta.modified {
+                    parts.push(format!("modified {}", format_date(modified)));
+                }
+            }
+        }
+    }
+    parts.join(", ")
+}
+
+/// Newline count, plus one for a trailing partial line.
+fn count_lines(bytes: &[u8]) -> usize {
+    let lines = bytes.iter().filter(|&&b| b == b'\n').count();
+    if ends_with_newline(bytes) {
+        lines
+    } else {
+        lines + 1
+    }
+}
+
+/// Like `count_lines`, but streams the file instead of loading it.
+fn count_file_lines(path: &Path) -> usize {
+    let Ok(file) = fs::File::open(path) else {
+        return 0;
+    };
+    let mut reader = std::io::BufReader::with_capacity(256 * 1024, file);
+    let (mut lines, mut last) = (0, b'\n');
+    loop {
+        let buf = match std::io::BufRead::fill_buf(&mut reader) {
+            Ok([]) | Err(_) => break,
+            Ok(buf) => buf,
+        };
+        lines += buf.iter().filter(|&&b| b == b'\n').count();
+        last = buf[buf.len() - 1];
+        let n = buf.len();
+        std::io::BufRead::consume(&mut reader, n);
+    }
+    if last == b'\n' {
+        lines
+    } else {
+        lines + 1
+    }
+}
+
+/// `path` relative to the cwd when it lies beneath it, else as given.
+fn cwd_relative(path: &Path) -> String {
+    std::env::current_dir()
+        .ok()
+        .and_then(|c| path.strip_prefix(c).ok().map(Path::to_path_buf))
+        .unwrap_or_else(|| path.to_path_buf())
+        .display()
+        .to_string()
+}
+
+/// Human-readable size with one decimal: 512 → "512B", 1229 → "1.2K".
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["K", "M", "G", "T"];
+    if bytes < 1024 {
+        return format!("{bytes}B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1}{}", UNITS[unit])
+}
+
+/// UTC calendar date (YYYY-MM-DD) of `time`, via the days-to-civil algorithm.
+fn format_date(time: std::time::SystemTime) -> String {
+    let secs = time
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let z = secs.div_euclid(86_400) + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// UTC `YYYY-MM-DD HH:MM:SS UTC` of `time`.
+pub fn format_timestamp(time: std::time::SystemTime) -> String {
+    let secs = time
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+        % 86_400;
+    format!(
+        "{} {:02}:{:02}:{:02} UTC",
+        format_date(time),
+        secs / 3_600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+/// Empty content counts as terminated: there is no partial line to close.
+fn ends_with_newline(content: &[u8]) -> bool {
+    content.is_empty() || content.ends_with(b"\n")
+}
+
+/// `std::io::copy` that also reports whether what it copied ends in a newline
+/// (true when nothing was copied, as for `ends_with_newline`).
+fn copy_noting_newline(reader: &mut dyn Read, writer: &mut dyn Write) -> std::io::Result<bool> {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut ends_nl = true;
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => return Ok(ends_nl),
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        writer.write_all(&buf[..n])?;
+        ends_nl = buf[n - 1] == b'\n';
+    }
+}
+
+fn is_symlink(path: &Path) -> bool {
+    path.symlink_metadata()
+        .map(|m| m.file_type().is_symlink())
+        .unwrap_or(false)
+}
+
+/// True if the symlinked directory `path` resolves to one of its own ancestors,
+/// i.e. following it would walk the same tree forever.
+fn is_symlink_cycle(path: &Path) -> bool {
+    let (Ok(target), Some(parent)) = (path.canonicalize(), path.parent()) else {
+        return false;
+    };
+    parent
+        .canonicalize()
+        .map(|p| p.starts_with(&target))
+        .unwrap_or(false)
+}
+
+fn is_loop_error(err: &ignore::Error) -> bool {
+    match err {
+        ignore::Error::Loop { .. } => true,
+        ignore::Error::WithPath { err, .. }
+        | ignore::Error::WithDepth { err, .. }
+        | ignore::Error::WithLineNumber { err, .. } => is_loop_error(err),
+        _ => false,
     }
 }
 
@@ -69,6 +300,62 @@ fn is_notebook(path: &Path) -> bool {
 /// Files larger than this use byte estimation instead of exact BPE counting.
 const MAX_EXACT_BYTES: u64 = 5 * 1024 * 1024; // 5 MB
 
+/// Cap on how much of a single file is emitted when the output goes to the
+/// clipboard (`with_stream_cap`). Clipboard backends that buffer (arboard) hold
+/// the whole output in memory, so one stray multi-gigabyte file would otherwise
+/// exhaust it. Files and stdout are not capped.
+const MAX_STREAM_BYTES: u64 = 256 * 1024 * 1024; // 256 MB
+
+/// Ignore patterns (gitignore syntax) applied by `--exclude-generated`: build
+/// output directories, minified bundles, source maps and codegen output.
+pub const GENERATED_PATTERNS: &[&str] = &[
+    "dist/",
+    "build/",
+    "out/",
+    "target/",
+    "node_modules/",
+    ".next/",
+    "__generated__/",
+    "*.min.js",
+    "*.min.css",
+    "*.map",
+    "*.pb.go",
+    "*.pb.cc",
+    "*.pb.h",
+    "*_pb2.py",
+    "*_pb2_grpc.py",
+    "*_generated.rs",
+    "*_generated.go",
+    "*.generated.*",
+    "*.g.dart",
+    "*.freezed.dart",
+];
+
+/// File names left out unless `--allow-sensitive` is given (gitignore syntax,
+/// matched against the name alone). `sensitive_patterns` in the config
+/// replaces this list.
+pub const SENSITIVE_PATTERNS: &[&str] = &[
+    ".env",
+    ".env.*",
+    "!.env.example",
+    "!.env.sample",
+    "!.env.template",
+    "*.pem",
+    "*.key",
+    "*.p12",
+    "*.pfx",
+    "id_rsa",
+    "id_dsa",
+    "id_ecdsa",
+    "id_ed25519",
+    ".npmrc",
+    ".pypirc",
+    ".netrc",
+    ".git-credentials",
+    "credentials",
+    "credentials.json",
+];
+
 pub fn is_glob_pattern(s: &str) -> bool {
     s.contains(['*', '?', '{', '['])
 }
@@ -77,9 +364,75 @@ fn is_binary_content(bytes: &[u8]) -> bool {
     bytes[..bytes.len().min(8192)].contains(&0u8)
 }
 
+/// Whether a walked directory's own files come before or after the contents
+/// of its subdirectories (`--files-first` / `--dir-first`).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DirGrouping {
+    FilesFirst,
+    DirsFirst,
+}
+
+impl DirGrouping {
+    /// Name order, except that where two paths part ways a file sorts before
+    /// (or after) a directory at the same level.
+    fn compare(self, a: &Path, b: &Path) -> std::cmp::Ordering {
+        let (mut rest_a, mut rest_b) = (a.components(), b.components());
+        loop {
+            match (rest_a.next(), rest_b.next()) {
+                (Some(x), Some(y)) if x == y => continue,
+                (Some(x), Some(y)) => {
+                    let a_file = rest_a.clone().next().is_none();
+                    let b_file = rest_b.clone().next().is_none();
+                    let files_first = match (a_file, b_file) {
+                        (true, false) => std::cmp::Ordering::Less,
+                        (false, true) => std::cmp::Ordering::Greater,
+                        _ => return x.cmp(&y),
+                    };
+                    return match self {
+                        DirGrouping::FilesFirst => files_first,
+                        DirGrouping::DirsFirst => files_first.reverse(),
+                    };
+                }
+                _ => return a.cmp(b),
+            }
+        }
+    }
+}
+
+/// How files are classified as binary (`--binary-detection`).
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, clap::ValueEnum)]
+pub enum BinaryDetection {
+    /// A NUL byte in the first 8 KiB means binary.
+    #[default]
+    Heuristic,
+    /// Sniff magic numbers first; unrecognised content is text if it is valid
+    /// UTF-8, otherwise the NUL-byte heuristic decides.
+    Mime,
+}
+
+impl BinaryDetection {
+    fn is_binary(self, bytes: &[u8]) -> bool {
+        match self {
+            BinaryDetection::Heuristic => is_binary_content(bytes),
+            BinaryDetection::Mime => {
+                let sample = &bytes[..bytes.len().min(8192)];
+                if let Some(kind) = infer::get(sample) {
+                    return kind.matcher_type() != infer::MatcherType::Text;
+                }
+                match std::str::from_utf8(sample) {
+                    Ok(_) => false,
+                    // A character cut off by the sample boundary is still text.
+                    Err(e) if e.error_len().is_none() => false,
+                    Err(_) => is_binary_content(sample),
+                }
+            }
+        }
+    }
+}
+
 /// Compile a list of ignore patterns (gitignore syntax) against a base directory.
 /// Returns an Arc so it can be cloned cheaply into parallel walker closures.
-fn build_gitignore(patterns: &[String], base_dir: &Path) -> Arc<Gitignore> {
+pub(crate) fn build_gitignore(patterns: &[String], base_dir: &Path) -> Arc<Gitignore> {
     let mut builder = GitignoreBuilder::new(base_dir);
     for pattern in patterns {
         let _ = builder.add_line(None, pattern);
@@ -87,6 +440,378 @@ fn build_gitignore(patterns: &[String], base_dir: &Path) -> Arc<Gitignore> {
     Arc::new(builder.build().unwrap_or_else(|_| Gitignore::empty()))
 }
 
+/// Returns true if `path` matches any of `patterns` (gitignore syntax), anchored
+/// at the cwd: `target` matches any component named "target", `*.o` matches by
+/// filename, `build/` matches only directories. `is_dir` comes from the
+/// caller's metadata so the path isn't stat'ed again.
+fn matches_patterns(patterns: &[String], path: &Path, is_dir: bool) -> bool {
+    if patterns.is_empty() {
+        return false;
+    }
+    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    // Resolve to absolute so the gitignore root-stripping works correctly.
+    let abs = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        cwd.join(path)
+    };
+    // The matcher requires paths under its root; anchor paths outside the
+    // cwd at their own parent so name patterns like "*.log" still apply.
+    let base = if abs.starts_with(&cwd) {
+        cwd
+    } else {
+        abs.parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| abs.clone())
+    };
+    let gitignore = build_gitignore(patterns, &base);
+    gitignore
+        .matched_path_or_any_parents(&abs, is_dir)
+        .is_ignore()
+}
+
+/// True if the last component of `path` is a dotfile name; ancestors don't count.
+fn has_hidden_name(path: &Path) -> bool {
+    match path.components().next_back() {
+        Some(Component::Normal(name)) => name.to_str().is_some_and(|n| n.starts_with('.')),
+        _ => false,
+    }
+}
+
+/// The canonical paths of ignore patterns that name an existing file or
+/// directory; glob and negated patterns are left to the gitignore matcher.
+fn resolve_ignore_paths(patterns: &[String]) -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = patterns
+        .iter()
+        .filter(|p| !p.starts_with('!') && !p.contains(['*', '?', '[']))
+        .filter_map(|p| {
+            let p = p.trim_end_matches('/');
+            Path::new(p)
+                .canonicalize()
+                .or_else(|_| Path::new(p.trim_start_matches('/')).canonicalize())
+                .ok()
+        })
+        .collect();
+    roots.dedup();
+    roots
+}
+
+/// Compile an `--ignore-file` rooted at its own directory, so `/build` in
+/// `sub/.myignore` means `sub/build`, as in a nested `.gitignore`.
+pub fn load_ignore_file(path: &Path) -> Result<Arc<Gitignore>> {
+    let dir = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    fs::metadata(path)?;
+    let mut builder = GitignoreBuilder::new(dir.canonicalize()?);
+    if let Some(err) = builder.add(path) {
+        return Err(err.into());
+    }
+    Ok(Arc::new(builder.build()?))
+}
+
+/// True if an `--ignore-file` ignores the absolute `path`. Each file only
+/// covers paths under its own directory.
+fn ignored_by_files(files: &[Arc<Gitignore>], path: &Path, is_dir: bool) -> bool {
+    files.iter().any(|file| {
+        path.starts_with(file.path()) && file.matched_path_or_any_parents(path, is_dir).is_ignore()
+    })
+}
+
+/// True if `path` resolves (following symlinks) to one of `roots` or below it.
+fn resolves_into(roots: &[PathBuf], path: &Path) -> bool {
+    !roots.is_empty()
+        && path
+            .canonicalize()
+            .is_ok_and(|real| roots.iter().any(|root| real.starts_with(root)))
+}
+
+/// Run the `--content-filter` command over a file's content. On failure, warn
+/// and keep the raw content.
+fn filter_content(filter: Option<&str>, path: &Path, content: Vec<u8>) -> Vec<u8> {
+    let Some(template) = filter else {
+        return content;
+    };
+    match crate::exec::filter(template, path, &content) {
+        Ok(filtered) => filtered,
+        Err(e) => {
+            eprintln!(
+                "Warning: --content-filter failed for '{}': {e}. Using raw content.",
+                path.display()
+            );
+            content
+        }
+    }
+}
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Drop a leading UTF-8 byte order mark unless `--keep-bom` asked to keep it.
+fn strip_bom(keep: bool, mut content: Vec<u8>) -> Vec<u8> {
+    if !keep && content.starts_with(UTF8_BOM) {
+        content.drain(..UTF8_BOM.len());
+    }
+    content
+}
+
+/// `--compact`: strip trailing spaces and tabs, collapse runs of blank lines
+/// to one and drop blank lines at the start and end. Indentation and line
+/// endings are kept, so code means the same thing.
+fn compact_content(content: Vec<u8>) -> Vec<u8> {
+    let mut out = Vec::with_capacity(content.len());
+    let mut compactor = Compactor::default();
+    for line in content.split_inclusive(|&b| b == b'\n') {
+        compactor.line(line, &mut out);
+    }
+    out
+}
+
+/// `--compact` state carried from line to line, so content can be compacted
+/// whole (`compact_content`) or as it streams past.
+#[derive(Default)]
+struct Compactor {
+    /// Ending of a blank line seen after text, written only if more text follows.
+    pending_blank: Option<Vec<u8>>,
+    started: bool,
+}
+
+impl Compactor {
+    /// Append the compacted form of `line` (ending included) to `out`.
+    fn line(&mut self, line: &[u8], out: &mut Vec<u8>) {
+        let body_end = line
+            .iter()
+            .rposition(|&b| b != b'\n' && b != b'\r')
+            .map_or(0, |i| i + 1);
+        let ending = &line[body_end..];
+        let kept = line[..body_end]
+            .iter()
+            .rposition(|&b| b != b' ' && b != b'\t')
+            .map_or(0, |i| i + 1);
+        if kept == 0 {
+            if self.started {
+                self.pending_blank = Some(ending.to_vec());
+            }
+            return;
+        }
+        if let Some(blank) = self.pending_blank.take() {
+            out.extend_from_slice(&blank);
+        }
+        self.started = true;
+        out.extend_from_slice(&line[..kept]);
+        out.extend_from_slice(ending);
+    }
+}
+
+/// `--max-lines`: keep the first `max` lines of longer content and end it
+/// with a marker giving the total.
+fn cap_lines(max: Option<usize>, mut content: Vec<u8>) -> Vec<u8> {
+    let Some(max) = max else {
+        return content;
+    };
+    let total = count_lines(&content);
+    if total <= max {
+        return content;
+    }
+    let cut = content
+        .split_inclusive(|&b| b == b'\n')
+        .take(max)
+        .map(<[u8]>::len)
+        .sum();
+    content.truncate(cut);
+    content.extend_from_slice(line_cap_marker(total).as_bytes());
+    content
+}
+
+fn line_cap_marker(total: usize) -> String {
+    format!("... ({total} lines total, truncated) ...\n")
+}
+
+/// `--sample-lines`: for content longer than `above` lines, keep lines 1,
+/// 1 + every, 1 + 2 * every, ... under a marker saying it was sampled.
+fn sample_lines(sample: Option<(usize, usize)>, content: Vec<u8>) -> Vec<u8> {
+    let Some((every, above)) = sample else {
+        return content;
+    };
+    let total = count_lines(&content);
+    if total <= above {
+        return content;
+    }
+    let mut out = sample_marker(every, total).into_bytes();
+    for line in content.split_inclusive(|&b| b == b'\n').step_by(every) {
+        out.extend_from_slice(line);
+    }
+    out
+}
+
+fn sample_marker(every: usize, total: usize) -> String {
+    format!("... (sampled: 1 line in {every} of {total}) ...\n")
+}
+
+/// Hard-wrap lines longer than `width` characters (`--wrap`), breaking at the
+/// last space that fits and repeating the line's indentation on continuation
+/// lines. Content that isn't valid UTF-8 is left alone.
+fn wrap_content(width: Option<u16>, content: Vec<u8>) -> Vec<u8> {
+    let Some(width) = width.map(usize::from) else {
+        return content;
+    };
+    let Ok(text) = std::str::from_utf8(&content) else {
+        return content;
+    };
+    if text.lines().all(|line| line.chars().count() <= width) {
+        return content;
+    }
+    let mut out = String::with_capacity(text.len() + text.len() / width);
+    for line in text.split_inclusive('\n') {
+        let body = line.trim_end_matches(['\n', '\r']);
+        let ending = &line[body.len()..];
+        let indent = &body[..body.len() - body.trim_start().len()];
+        // Deep indentation would leave little room; continue such lines flush left.
+        let indent = if indent.chars().count() * 2 > width {
+            ""
+        } else {
+            indent
+        };
+        let mut pieces = Vec::new();
+        let mut rest = body;
+        loop {
+            let room = if pieces.is_empty() {
+                width
+            } else {
+                width - indent.chars().count()
+            };
+            let Some((cut, _)) = rest.char_indices().nth(room) else {
+                pieces.push(rest);
+                break;
+            };
+            let head = &rest[..cut];
+            match head.rfind(' ').filter(|&i| !head[..i].trim().is_empty()) {
+                Some(space) => {
+                    pieces.push(head[..space].trim_end());
+                    rest = rest[space..].trim_start_matches(' ');
+                }
+                None => {
+                    pieces.push(head);
+                    rest = &rest[cut..];
+                }
+            }
+            if rest.is_empty() {
+                break;
+            }
+        }
+        out.push_str(&pieces.join(&format!("\n{indent}")));
+        out.push_str(ending);
+    }
+    out.into_bytes()
+}
+
+/// Files git tracks under `dir`, as paths joined onto its canonical form so
+/// they compare equal to walked paths (`--only-tracked`). With
+/// `recurse_submodules`, files tracked by initialized submodules count too.
+fn tracked_files(dir: &Path, recurse_submodules: bool) -> Result<HashSet<PathBuf>> {
+    let output = std::process::Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["ls-files", "-z"])
+        .args(recurse_submodules.then_some("--recurse-submodules"))
+        .output()
+        .context("Failed to run `git ls-files` for --only-tracked")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "--only-tracked: '{}' is not inside a git repository",
+            dir.display()
+        );
+    }
+    let base = dir.canonicalize()?;
+    Ok(output
+        .stdout
+        .split(|&b| b == 0)
+        .filter(|rel| !rel.is_empty())
+        .map(|rel| base.join(String::from_utf8_lossy(rel).as_ref()))
+        .collect())
+}
+
+/// `tracked_files` for `dir`, listed once per canonical directory in `cache`.
+fn cached_tracked_files<'a>(
+    cache: &'a mut HashMap<PathBuf, HashSet<PathBuf>>,
+    dir: &Path,
+    recurse_submodules: bool,
+) -> Result<&'a HashSet<PathBuf>> {
+    let key = dir.canonicalize()?;
+    if !cache.contains_key(&key) {
+        let tracked = tracked_files(dir, recurse_submodules)?;
+        cache.insert(key.clone(), tracked);
+    }
+    Ok(&cache[&key])
+}
+
+/// Submodules registered under `dir` (gitlink entries in the index) that
+/// were never initialized, so their directories hold no checkout. Empty
+/// outside a git repository.
+fn uninitialized_submodules(dir: &Path) -> Vec<PathBuf> {
+    let Ok(output) = std::process::Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["ls-files", "--stage", "-z"])
+        .output()
+    else {
+        return Vec::new();
+    };
+    output
+        .stdout
+        .split(|&b| b == 0)
+        .filter_map(|entry| {
+            let entry = String::from_utf8_lossy(entry);
+            let (meta, path) = entry.split_once('\t')?;
+            meta.starts_with("160000 ").then(|| dir.join(path))
+        })
+        .filter(|path| !path.join(".git").exists())
+        .collect()
+}
+
+/// Default cap on entries visited across all directory walks (`--max-entries`).
+pub const DEFAULT_MAX_ENTRIES: usize = 1_000_000;
+
+/// `--drop-outliers` drops files larger than this multiple of the median file size.
+const OUTLIER_FACTOR: u64 = 5;
+
+/// Size above which a file counts as an outlier: `OUTLIER_FACTOR` times the
+/// median of the non-empty sizes. Empty files are left out so a tree full of
+/// `__init__.py` stubs doesn't push the median to zero.
+fn outlier_limit(sizes: impl IntoIterator<Item = u64>) -> Option<u64> {
+    let mut sizes: Vec<u64> = sizes.into_iter().filter(|&s| s > 0).collect();
+    if sizes.is_empty() {
+        return None;
+    }
+    sizes.sort_unstable();
+    Some(sizes[(sizes.len() - 1) / 2] * OUTLIER_FACTOR)
+}
+
+/// Group `files` (path, tokens), in order, into chunks of at most `budget`
+/// tokens for `--chunk-by-tokens`. A file over the budget gets a chunk of its own.
+pub fn chunk_by_tokens(files: &[(PathBuf, usize)], budget: usize) -> Vec<Vec<PathBuf>> {
+    let mut chunks: Vec<Vec<PathBuf>> = Vec::new();
+    let mut used = 0;
+    for (path, tokens) in files {
+        if chunks.is_empty() || used + tokens > budget {
+            chunks.push(Vec::new());
+            used = 0;
+        }
+        used += tokens;
+        chunks.last_mut().unwrap().push(path.clone());
+    }
+    chunks
+}
+
+/// One input path, resolved before anything is written so `--drop-outliers`
+/// can see every file size up front.
+enum PlannedInput {
+    File(PathBuf, FileMeta),
+    /// Files found under a directory argument, already filtered and sorted,
+    /// with what the walker learned about each.
+    Directory(Vec<(PathBuf, FileMeta)>),
+}
+
 pub struct ContentAggregator {
     formatter: Box<dyn crate::formatter::Formatter>,
     include_hidden_in_dirs: bool,
@@ -98,7 +823,100 @@ pub struct ContentAggregator {
     sort: bool,
     /// Extensions to include. Empty means all files are allowed.
     allowed_extensions: std::collections::HashSet<String>,
-    skipped_binary: usize,
+    binary_detection: BinaryDetection,
+    /// Empty/whitespace-only files folded into one summary line (`--dedup-blank-files`).
+    blank_files: Option<Vec<PathBuf>>,
+    /// List skipped binary and outlier files at the end (`--note-skipped`).
+    note_skipped: bool,
+    /// Names of files that may hold secrets; None with `--allow-sensitive`.
+    sensitive: Option<Arc<Gitignore>>,
+    /// Group each directory's files before or after its subdirectories.
+    dir_grouping: Option<DirGrouping>,
+    /// Lines written before everything else (`--embed-command`).
+    preamble: Option<String>,
+    /// Changed line counts by absolute path, shown in headers (`--with-diffstat`).
+    diffstat: HashMap<PathBuf, crate::diffstat::Change>,
+    /// Closing section holding the inputs' unified diff (`--with-diff`).
+    diff_section: Option<String>,
+    /// Directory of the last emitted file, tracked for `--dir-markers`.
+    last_dir: Option<Option<PathBuf>>,
+    /// Keep markdown cells when rendering `.ipynb` files (`--notebook-markdown`).
+    notebook_markdown: bool,
+    /// Files left out of the output, reported together at the end of the run.
+    skipped: Vec<SkippedFile>,
+    /// Annotate headers of symlinked files with their resolved target.
+    symlink_targets: bool,
+    /// Per-extension (file count, bytes), collected only for `--ext-summary`.
+    ext_stats: Option<BTreeMap<String, (usize, u64)>>,
+    /// Fields for the `--header-meta` summary; empty disables it.
+    header_meta: Vec<crate::cli::MetaField>,
+    /// `--stdin-content` body, emitted first under its virtual name.
+    virtual_file: Option<(PathBuf, Vec<u8>)>,
+    /// Only files modified at or after this time (`--modified-within`).
+    modified_cutoff: Option<std::time::SystemTime>,
+    /// `--force-include` / `.cxtinclude` patterns; matches bypass every exclusion rule.
+    force_include: Vec<String>,
+    /// `force_include` compiled once against the cwd it was set under.
+    force_matcher: Option<(PathBuf, Arc<Gitignore>)>,
+    /// The `--write` targets, never read back in when a walk reaches them.
+    output_files: Vec<PathBuf>,
+    /// Also ignore the directories holding `output_files` (`--ignore-output-dir`).
+    ignore_output_dir: bool,
+    /// Paths of emitted files in output order, collected only for `--exec`.
+    file_list: Option<Vec<PathBuf>>,
+    /// Current content of the file being appended to, for `--dedup-headers-in-append`.
+    existing_headers: Option<HashSet<String>>,
+    /// Per-file token counts, collected only for `--chunk-by-tokens`.
+    file_tokens: Option<Vec<(PathBuf, usize)>>,
+    /// Per-file line counts, collected only for `--line-counts`.
+    line_counts: Option<Vec<(PathBuf, usize)>>,
+    /// Header path and line count of each emitted file, for `--toc`.
+    toc: Option<Vec<(String, usize)>>,
+    /// Per-file output cap; `u64::MAX` unless `with_stream_cap` applies `MAX_STREAM_BYTES`.
+    max_stream_bytes: u64,
+    /// Drop files much larger than the median (`--drop-outliers`).
+    drop_outliers: bool,
+    /// Keep only files tracked by git (`--only-tracked`).
+    only_tracked: bool,
+    /// Count files of initialized submodules as tracked and report
+    /// uninitialized ones (`--include-submodules`).
+    include_submodules: bool,
+    /// `--hidden-root`: walk a directory whose own name is hidden with its dotfiles.
+    hidden_root: bool,
+    /// Abort once the walks have visited this many entries; 0 means no limit.
+    max_entries: usize,
+    /// Entries visited so far, across every directory argument.
+    entries_seen: Arc<AtomicUsize>,
+    /// Canonical directories ignored along with everything that resolves
+    /// under them (`--resolve-ignores`, `--ignore-output-dir`).
+    resolved_ignores: Arc<Vec<PathBuf>>,
+    /// `--ignore-file` matchers, each rooted at its file's directory.
+    ignore_files: Arc<Vec<Arc<Gitignore>>>,
+    /// Directory-walk threads (`--jobs`); 0 lets the walker pick.
+    walk_threads: usize,
+    /// Leave a leading UTF-8 BOM in file content (`--keep-bom`).
+    keep_bom: bool,
+    /// Start each file's content with a comment naming it (`--inline-filename`).
+    inline_filename: bool,
+    /// Line written between file blocks (`--separators-only`).
+    separator: Option<String>,
+    /// Column to hard-wrap content lines at (`--wrap`).
+    wrap: Option<u16>,
+    /// Drop trailing whitespace and surplus blank lines (`--compact`).
+    compact: bool,
+    /// Per-file line cap (`--max-lines`).
+    max_lines: Option<usize>,
+    /// `--sample-lines` step and the line count a file must exceed.
+    sample: Option<(usize, usize)>,
+    /// Bytes `--compact` removed so far; atomic so walked files can be
+    /// transformed in parallel.
+    compact_saved: AtomicU64,
+    /// Shell command each file's content is piped through (`--content-filter`).
+    content_filter: Option<String>,
+    /// Outlier size cutoff for the current run, set once all inputs are planned.
+    outlier_limit: Option<u64>,
+    /// Header rows of a `metadata_only` format, written once every file is seen.
+    metadata_rows: Vec<u8>,
 }
 
 impl ContentAggregator {
@@ -118,265 +936,1193 @@ impl ContentAggregator {
             ignore_patterns: ignore,
             sort,
             allowed_extensions,
-            skipped_binary: 0,
+            binary_detection: BinaryDetection::default(),
+            notebook_markdown: false,
+            blank_files: None,
+            note_skipped: false,
+            preamble: None,
+            dir_grouping: None,
+            sensitive: None,
+            diffstat: HashMap::new(),
+            diff_section: None,
+            last_dir: None,
+            skipped: Vec::new(),
+            symlink_targets: false,
+            ext_stats: None,
+            header_meta: Vec::new(),
+            virtual_file: None,
+            modified_cutoff: None,
+            force_include: Vec::new(),
+            force_matcher: None,
+            line_counts: None,
+            toc: None,
+            file_tokens: None,
+            existing_headers: None,
+            file_list: None,
+            output_files: Vec::new(),
+            ignore_output_dir: false,
+            max_stream_bytes: u64::MAX,
+            drop_outliers: false,
+            only_tracked: false,
+            include_submodules: false,
+            hidden_root: false,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            entries_seen: Arc::new(AtomicUsize::new(0)),
+            content_filter: None,
+            wrap: None,
+            compact: false,
+            max_lines: None,
+            sample: None,
+            compact_saved: AtomicU64::new(0),
+            inline_filename: false,
+            separator: None,
+            keep_bom: false,
+            walk_threads: 0,
+            resolved_ignores: Arc::default(),
+            ignore_files: Arc::default(),
+            outlier_limit: None,
+            metadata_rows: Vec::new(),
         }
     }
 
-    /// Returns true if `path` should be excluded based on the ignore patterns.
-    /// Patterns follow gitignore semantics: `target` matches any component named
-    /// "target", `*.o` matches by filename, `build/` matches only directories.
-    fn is_ignored(&self, path: &Path) -> bool {
-        if self.ignore_patterns.is_empty() {
-            return false;
-        }
-        let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-        let gitignore = build_gitignore(&self.ignore_patterns, &cwd);
-        // Resolve to absolute so the gitignore root-stripping works correctly.
-        let abs = if path.is_absolute() {
-            path.to_path_buf()
-        } else {
-            cwd.join(path)
-        };
-        let is_dir = path.is_dir();
-        gitignore
-            .matched_path_or_any_parents(&abs, is_dir)
-            .is_ignore()
+    pub fn with_symlink_targets(mut self, enabled: bool) -> Self {
+        self.symlink_targets = enabled;
+        self
     }
 
-    /// Returns true if `path` passes the extension filter.
-    /// When `allowed_extensions` is empty, all files pass.
-    fn extension_allowed(&self, path: &Path) -> bool {
-        if self.allowed_extensions.is_empty() {
-            return true;
-        }
-        match path.extension().and_then(|e| e.to_str()) {
-            Some(ext) => self.allowed_extensions.contains(&ext.to_lowercase()),
-            None => false,
-        }
+    pub fn with_ext_summary(mut self, enabled: bool) -> Self {
+        self.ext_stats = enabled.then(BTreeMap::new);
+        self
     }
 
-    pub fn aggregate_paths(&mut self, paths: &[String], writer: &mut dyn Write) -> Result<()> {
-        writer.write_all(self.formatter.document_start().as_bytes())?;
-        for path_str in paths {
-            let path = Path::new(path_str);
-            if !path.exists() {
-                return Err(anyhow::anyhow!("Path does not exist: {}", path_str));
-            }
-            if self.is_ignored(path) {
-                continue;
-            }
-            if path.is_file() {
-                self.aggregate_file(path, writer)?;
-            } else if path.is_dir() {
-                if !self.include_hidden_in_dirs
-                    && self.is_hidden_file(path)
-                    && !self.is_explicit_path(path, paths)
-                {
-                    continue;
-                }
-                self.aggregate_directory(path, writer)?;
-            }
+    pub fn with_dedup_blank_files(mut self, enabled: bool) -> Self {
+        self.blank_files = enabled.then(Vec::new);
+        self
+    }
+
+    pub fn with_note_skipped(mut self, enabled: bool) -> Self {
+        self.note_skipped = enabled;
+        self
+    }
+
+    /// Leave out files whose names match `patterns`; empty allows everything.
+    pub fn with_sensitive_patterns(mut self, patterns: &[String]) -> Self {
+        self.sensitive = (!patterns.is_empty()).then(|| build_gitignore(patterns, Path::new("")));
+        self
+    }
+
+    pub fn with_dir_grouping(mut self, grouping: Option<DirGrouping>) -> Self {
+        self.dir_grouping = grouping;
+        self
+    }
+
+    pub fn with_preamble(mut self, preamble: Option<String>) -> Self {
+        self.preamble = preamble;
+        self
+    }
+
+    pub fn with_diffstat(mut self, diffstat: HashMap<PathBuf, crate::diffstat::Change>) -> Self {
+        self.diffstat = diffstat;
+        self
+    }
+
+    pub fn with_diff_section(mut self, section: Option<String>) -> Self {
+        self.diff_section = section;
+        self
+    }
+
+    pub fn with_dir_markers(mut self, enabled: bool) -> Self {
+        self.last_dir = enabled.then_some(None);
+        self
+    }
+
+    pub fn with_notebook_markdown(mut self, enabled: bool) -> Self {
+        self.notebook_markdown = enabled;
+        self
+    }
+
+    pub fn with_binary_detection(mut self, detection: BinaryDetection) -> Self {
+        self.binary_detection = detection;
+        self
+    }
+
+    /// Keep only files modified within `window` of now.
+    pub fn with_modified_within(mut self, window: Option<std::time::Duration>) -> Self {
+        self.modified_cutoff = window.and_then(|w| std::time::SystemTime::now().checked_sub(w));
+        self
+    }
+
+    pub fn with_walk_threads(mut self, threads: usize) -> Self {
+        self.walk_threads = threads;
+        self
+    }
+
+    pub fn with_ignore_files(mut self, files: Vec<Arc<Gitignore>>) -> Self {
+        self.ignore_files = Arc::new(files);
+        self
+    }
+
+    /// Also ignore paths that resolve, through symlinks, into an ignored path.
+    /// Costs a `canonicalize()` per walked entry.
+    pub fn with_resolve_ignores(mut self, enabled: bool) -> Self {
+        if enabled {
+            let roots = resolve_ignore_paths(&self.ignore_patterns);
+            Arc::make_mut(&mut self.resolved_ignores).extend(roots);
         }
-        writer.write_all(self.formatter.document_end().as_bytes())?;
-        Ok(())
+        self
     }
 
-    fn is_explicit_path(&self, path: &Path, input_paths: &[String]) -> bool {
-        input_paths.iter().any(|p| Path::new(p) == path)
+    /// Truncate each file at `MAX_STREAM_BYTES`, for output bound for the clipboard.
+    pub fn with_stream_cap(mut self, enabled: bool) -> Self {
+        self.max_stream_bytes = if enabled { MAX_STREAM_BYTES } else { u64::MAX };
+        self
     }
 
-    /// Try to handle `read_path` as a Jupyter notebook.
-    /// Returns Ok(true) if it was handled (written or deliberately skipped),
-    /// Ok(false) if the caller should fall back to normal raw-text handling.
-    fn try_write_notebook(
-        &mut self,
-        read_path: &Path,
-        display_path: &Path,
-        writer: &mut dyn Write,
-    ) -> Result<bool> {
-        let size = read_path.metadata().map(|m| m.len()).unwrap_or(0);
-        if size > crate::notebook::MAX_NOTEBOOK_BYTES {
-            eprintln!(
-                "Warning: notebook '{}' exceeds parse limit; using raw text.",
-                read_path.display()
-            );
-            return Ok(false);
+    pub fn with_keep_bom(mut self, keep: bool) -> Self {
+        self.keep_bom = keep;
+        self
+    }
+
+    pub fn with_inline_filename(mut self, enabled: bool) -> Self {
+        self.inline_filename = enabled;
+        self
+    }
+
+    pub fn with_separator(mut self, separator: Option<String>) -> Self {
+        self.separator = separator;
+        self
+    }
+
+    pub fn with_wrap(mut self, width: Option<u16>) -> Self {
+        self.wrap = width;
+        self
+    }
+
+    pub fn with_compact(mut self, enabled: bool) -> Self {
+        self.compact = enabled;
+        self
+    }
+
+    pub fn with_max_lines(mut self, max: Option<usize>) -> Self {
+        self.max_lines = max;
+        self
+    }
+
+    /// Sample every `every`th line of files longer than `above` lines.
+    pub fn with_sample_lines(mut self, every: Option<usize>, above: usize) -> Self {
+        self.sample = every.map(|every| (every, above));
+        self
+    }
+
+    /// Every per-file content transform, in order: BOM, `--content-filter`,
+    /// `--compact` (counting the bytes saved), `--max-lines`, `--sample-lines`
+    /// and `--wrap`. New transform flags go here and, for files too large to
+    /// hold, in `stream_large_file`.
+    fn transform(&self, path: &Path, content: Vec<u8>) -> Vec<u8> {
+        let content = strip_bom(self.keep_bom, content);
+        let mut content = filter_content(self.content_filter.as_deref(), path, content);
+        if self.compact {
+            let before = content.len();
+            content = compact_content(content);
+            self.compact_saved
+                .fetch_add((before - content.len()) as u64, Ordering::Relaxed);
         }
-        let bytes = match fs::read(read_path) {
-            Ok(b) => b,
-            Err(e) => {
+        let content = cap_lines(self.max_lines, content);
+        let content = sample_lines(self.sample, content);
+        wrap_content(self.wrap, content)
+    }
+
+    pub fn with_content_filter(mut self, command: Option<String>) -> Self {
+        self.content_filter = command;
+        self
+    }
+
+    pub fn with_max_entries(mut self, max: usize) -> Self {
+        self.max_entries = max;
+        self
+    }
+
+    pub fn with_only_tracked(mut self, enabled: bool) -> Self {
+        self.only_tracked = enabled;
+        self
+    }
+
+    pub fn with_include_submodules(mut self, enabled: bool) -> Self {
+        self.include_submodules = enabled;
+        self
+    }
+
+    pub fn with_hidden_root(mut self, enabled: bool) -> Self {
+        self.hidden_root = enabled;
+        self
+    }
+
+    pub fn with_drop_outliers(mut self, enabled: bool) -> Self {
+        self.drop_outliers = enabled;
+        self
+    }
+
+    pub fn with_force_include(mut self, patterns: Vec<String>) -> Self {
+        self.force_matcher = (!patterns.is_empty()).then(|| {
+            let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+            let matcher = build_gitignore(&patterns, &cwd);
+            (cwd, matcher)
+        });
+        self.force_include = patterns;
+        self
+    }
+
+    pub fn with_output_files(mut self, paths: Vec<PathBuf>) -> Self {
+        self.output_files = paths;
+        self
+    }
+
+    pub fn with_ignore_output_dir(mut self, enabled: bool) -> Self {
+        self.ignore_output_dir = enabled;
+        self
+    }
+
+    /// Ignore the directories the `--write` targets live in, except one that
+    /// holds an input: ignoring it would drop the input too.
+    fn ignore_output_dirs(&mut self, inputs: &[String]) {
+        let inputs: Vec<PathBuf> = inputs
+            .iter()
+            .filter_map(|p| Path::new(p).canonicalize().ok())
+            .collect();
+        let dirs: Vec<PathBuf> = self
+            .output_files
+            .iter()
+            .filter_map(|f| f.parent().map(Path::to_path_buf))
+            .collect();
+        for dir in dirs {
+            if let Some(input) = inputs.iter().find(|i| i.starts_with(&dir)) {
                 eprintln!(
-                    "Warning: Failed to read file '{}': {e}",
-                    read_path.display()
+                    "Warning: --ignore-output-dir: not ignoring '{}' because it holds the input '{}'.",
+                    dir.display(),
+                    input.display()
                 );
-                return Ok(true); // skip; raw path would also fail
+            } else if !self.resolved_ignores.contains(&dir) {
+                Arc::make_mut(&mut self.resolved_ignores).push(dir);
             }
-        };
-        match crate::notebook::extract_notebook_code(&bytes) {
-            Ok(code) => {
-                self.formatter.write_file_header(display_path, writer)?;
-                self.token_count += self.token_counter.count(&code);
-                writer.write_all(code.as_bytes())?;
-                writer.write_all(self.formatter.file_footer().as_bytes())?;
-                self.file_count += 1;
-                Ok(true)
+        }
+    }
+
+    pub fn with_file_list(mut self, enabled: bool) -> Self {
+        self.file_list = enabled.then(Vec::new);
+        self
+    }
+
+    /// Index the header lines of the output being appended to, once, by every
+    /// prefix a `Formatter::header_marker` could be: one followed by the end of
+    /// the line, a space or `>`.
+    pub fn with_existing_output(mut self, content: Option<String>) -> Self {
+        let lead = self.formatter.header_lead();
+        self.existing_headers = content.map(|text| {
+            let mut markers = HashSet::new();
+            for line in text.lines() {
+                let line = crate::formatter::without_toc_index(line);
+                if !lead.is_some_and(|lead| line.starts_with(lead)) {
+                    continue;
+                }
+                for (end, _) in line.match_indices([' ', '>']) {
+                    markers.insert(line[..end].to_string());
+                }
+                markers.insert(line.into_owned());
             }
-            Err(e) => {
-                eprintln!(
-                    "Warning: failed to parse notebook '{}': {e}. Using raw text.",
-                    read_path.display()
-                );
-                Ok(false)
+            markers
+        });
+        self
+    }
+
+    pub fn with_file_tokens(mut self, enabled: bool) -> Self {
+        self.file_tokens = enabled.then(Vec::new);
+        self
+    }
+
+    pub fn with_line_counts(mut self, enabled: bool) -> Self {
+        self.line_counts = enabled.then(Vec::new);
+        self
+    }
+
+    /// Open the output with a numbered index of the emitted files and number
+    /// each header to match. Output is buffered until the index is known.
+    pub fn with_toc(mut self, enabled: bool) -> Self {
+        self.toc = enabled.then(Vec::new);
+        self
+    }
+
+    pub fn with_header_meta(mut self, fields: Vec<crate::cli::MetaField>) -> Self {
+        self.header_meta = fields;
+        self
+    }
+
+    /// Include `content` as if it were a file called `name` that exists only in memory.
+    pub fn with_virtual_file(mut self, name: impl Into<PathBuf>, content: Vec<u8>) -> Self {
+        self.virtual_file = Some((name.into(), content));
+        self
+    }
+
+    /// Write the file header. `content` is the file body when already in memory
+    /// (used for the `--header-meta` line count; large streamed files omit it).
+    fn write_header(
+        &mut self,
+        path: &Path,
+        stat: FileMeta,
+        content: Option<&[u8]>,
+        writer: &mut dyn Write,
+    ) -> std::io::Result<()> {
+        let size = || stat.size;
+        let lines = || content.map_or_else(|| count_file_lines(path), count_lines);
+        if let Some(stats) = self.ext_stats.as_mut() {
+            let ext = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(|e| e.to_lowercase())
+                .unwrap_or_default();
+            let entry = stats.entry(ext).or_default();
+            entry.0 += 1;
+            entry.1 += size();
+        }
+        if let Some(counts) = self.line_counts.as_mut() {
+            counts.push((path.to_path_buf(), lines()));
+        }
+        if let Some(files) = self.file_list.as_mut() {
+            files.push(path.to_path_buf());
+        }
+        if let Some(tokens) = self.file_tokens.as_mut() {
+            // Counted from here; write_footer replaces it with the difference.
+            tokens.push((path.to_path_buf(), self.token_count));
+        }
+        let metadata_only = self.formatter.metadata_only();
+        // Exactly one blank line separates consecutive file blocks.
+        if self.file_count > 0 {
+            writer.write_all(b"\n")?;
+            if let Some(separator) = &self.separator {
+                self.token_count += self.token_counter.count(separator);
+                write!(writer, "{separator}\n\n")?;
             }
         }
+        self.write_dir_marker(path, writer)?;
+        let target = if self.symlink_targets && is_symlink(path) {
+            path.canonicalize().ok()
+        } else {
+            None
+        };
+        let change = if self.diffstat.is_empty() {
+            None
+        } else {
+            path.canonicalize()
+                .ok()
+                .and_then(|p| self.diffstat.get(&p))
+                .map(|&c| crate::diffstat::describe(c))
+        };
+        let meta = match (self.header_meta.is_empty(), change) {
+            (true, change) => change,
+            (false, None) => Some(describe_file(stat, content, &self.header_meta)),
+            (false, Some(change)) => Some(format!(
+                "{}, {change}",
+                describe_file(stat, content, &self.header_meta)
+            )),
+        };
+        let index = self.toc.as_mut().map(|toc| {
+            toc.push((self.formatter.display_path(path), lines()));
+            toc.len()
+        });
+        let header = FileHeader {
+            path,
+            target: target.as_deref(),
+            meta: meta.as_deref(),
+            index,
+            stats: metadata_only.then(|| FileStats {
+                size: size(),
+                lines: lines(),
+                summary: match content {
+                    Some(c) => crate::summary::summarize(path, c),
+                    None => crate::summary::summarize_file(path),
+                },
+            }),
+        };
+        if metadata_only {
+            return self
+                .formatter
+                .write_file_header(&header, &mut self.metadata_rows);
+        }
+        self.formatter.write_file_header(&header, writer)?;
+        if self.inline_filename {
+            let name = path
+                .file_name()
+                .unwrap_or(path.as_os_str())
+                .to_string_lossy();
+            let line = crate::summary::comment_line(path, &name);
+            self.token_count += self.token_counter.count(&line);
+            writeln!(writer, "{line}")?;
+        }
+        Ok(())
     }
 
-    /// Aggregate a single file; canonicalises path before passing to formatter.
-    fn aggregate_file(&mut self, path: &Path, writer: &mut dyn Write) -> Result<()> {
-        if !self.extension_allowed(path) {
+    /// With `--dir-markers`, note that `path` starts a new directory. Files
+    /// arrive sorted, so this fires each time the containing directory changes.
+    /// It runs only for files that are emitted, so a directory whose files
+    /// were all filtered out or skipped never gets a marker.
+    fn write_dir_marker(&mut self, path: &Path, writer: &mut dyn Write) -> std::io::Result<()> {
+        let Some(last_dir) = self.last_dir.as_mut() else {
             return Ok(());
-        }
-        let display_path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
-        if is_notebook(path) && self.try_write_notebook(path, &display_path, writer)? {
+        };
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => return Ok(()),
+        };
+        if last_dir.as_deref() == Some(dir) {
             return Ok(());
         }
-        let file_size = path.metadata().map(|m| m.len()).unwrap_or(0);
-        if file_size <= MAX_EXACT_BYTES {
-            let content = match fs::read(path) {
-                Ok(bytes) => bytes,
-                Err(e) => {
-                    eprintln!("Warning: Failed to read file '{}': {e}", path.display());
-                    return Ok(());
+        *last_dir = Some(dir.to_path_buf());
+        match self.formatter.dir_marker(dir) {
+            Some(marker) => writeln!(writer, "{marker}"),
+            None => Ok(()),
+        }
+    }
+
+    /// Close a file block. Content that doesn't end in a newline gets one so the
+    /// footer always starts on its own line.
+    fn write_footer(
+        &mut self,
+        content_ends_with_newline: bool,
+        writer: &mut dyn Write,
+    ) -> Result<()> {
+        if !content_ends_with_newline {
+            writer.write_all(b"\n")?;
+        }
+        writer.write_all(self.formatter.file_footer().as_bytes())?;
+        if let Some((_, tokens)) = self.file_tokens.as_mut().and_then(|t| t.last_mut()) {
+            *tokens = self.token_count - *tokens;
+        }
+        self.file_count += 1;
+        Ok(())
+    }
+
+    /// The streaming counterpart of `transform`, for a file too large to hold in
+    /// memory (`--wrap` is not applied). Copies it, truncating at
+    /// `max_stream_bytes` (and `--max-lines`) with a marker line, or sampling it
+    /// (`--sample-lines`, marker after the lines, since the total is only known
+    /// at the end). With `--content-filter` the file streams through the command
+    /// and its output is what gets copied; `--compact` applies line by line.
+    /// `file` must be positioned at its start. Returns whether the emitted
+    /// content ends in a newline.
+    fn stream_large_file(
+        &mut self,
+        file: &mut fs::File,
+        path: &Path,
+        file_size: u64,
+        writer: &mut dyn Write,
+    ) -> Result<bool> {
+        let limit = self.max_stream_bytes;
+        self.token_count += crate::token_counter::estimate_from_bytes(file_size.min(limit));
+        if !self.keep_bom {
+            let mut head = [0u8; 3];
+            let has_bom = file.read_exact(&mut head).is_ok() && head == UTF8_BOM;
+            file.seek(SeekFrom::Start(if has_bom { 3 } else { 0 }))?;
+        }
+        if file_size > limit {
+            eprintln!(
+                "Warning: '{}' is {}; only the first {} is included",
+                path.display(),
+                format_size(file_size),
+                format_size(limit)
+            );
+        }
+        let mut raw = file.try_clone()?.take(limit);
+        let mut filter = match self.content_filter.as_deref() {
+            Some(template) => {
+                match crate::exec::filter_stream(template, path, file.try_clone()?.take(limit)) {
+                    Ok(stream) => Some(stream),
+                    Err(e) => {
+                        eprintln!(
+                            "Warning: --content-filter failed for '{}': {e}. Using raw content.",
+                            path.display()
+                        );
+                        None
+                    }
                 }
-            };
-            if is_binary_content(&content) {
-                eprintln!("Warning: skipping binary file '{}'", path.display());
-                self.skipped_binary += 1;
-                return Ok(());
             }
-            self.formatter.write_file_header(&display_path, writer)?;
-            let text = String::from_utf8_lossy(&content);
-            self.token_count += self.token_counter.count(&text);
-            if let Err(e) = writer.write_all(&content) {
-                eprintln!("Warning: Failed to write file '{}': {e}", path.display());
+            None => None,
+        };
+        let mut capped = false;
+        let source: &mut dyn Read = match filter.as_mut() {
+            Some(stream) => stream,
+            None => &mut raw,
+        };
+        let ends_nl = if self.compact || self.max_lines.is_some() || self.sample.is_some() {
+            let max = self.max_lines.unwrap_or(usize::MAX);
+            // The first `hold` lines wait until we know whether the file runs
+            // past --sample-above; everything is read in this one pass.
+            let (every, hold) = self.sample.unwrap_or((1, 0));
+            let mut reader = std::io::BufReader::new(source);
+            let mut compactor = self.compact.then(Compactor::default);
+            let (mut read, mut lines) = (Vec::new(), Vec::new());
+            let (mut total, mut ends_nl) = (0, true);
+            let (mut held, mut sampling) = (Vec::new(), false);
+            let (mut read_bytes, mut kept_bytes) = (0u64, 0u64);
+            while reader.read_until(b'\n', &mut read)? > 0 {
+                match compactor.as_mut() {
+                    Some(compactor) => {
+                        compactor.line(&read, &mut lines);
+                        read_bytes += read.len() as u64;
+                        kept_bytes += lines.len() as u64;
+                    }
+                    None => std::mem::swap(&mut read, &mut lines),
+                }
+                // Compaction may hold a blank line back, or release it with this one.
+                for line in lines.split_inclusive(|&b| b == b'\n') {
+                    total += 1;
+                    if total <= hold {
+                        held.push(line.to_vec());
+                        continue;
+                    }
+                    if self.sample.is_some() && !sampling {
+                        sampling = true;
+                        for kept in std::mem::take(&mut held).iter().step_by(every) {
+                            writer.write_all(kept)?;
+                            ends_nl = kept.ends_with(b"\n");
+                        }
+                    }
+                    if total <= max && (total - 1) % every == 0 {
+                        writer.write_all(line)?;
+                        ends_nl = line.ends_with(b"\n");
+                    }
+                }
+                read.clear();
+                lines.clear();
             }
-        } else {
-            let mut file = match fs::File::open(path) {
-                Ok(f) => f,
-                Err(e) => {
-                    eprintln!("Warning: Failed to read file '{}': {e}", path.display());
-                    return Ok(());
+            self.compact_saved
+                .fetch_add(read_bytes - kept_bytes, Ordering::Relaxed);
+            // Never got past --sample-above: the held lines are the whole file.
+            for kept in &held {
+                writer.write_all(kept)?;
+                ends_nl = kept.ends_with(b"\n");
+            }
+            if sampling {
+                if !ends_nl {
+                    writer.write_all(b"\n")?;
                 }
-            };
-            let mut header = [0u8; 8192];
-            let n = file.read(&mut header).unwrap_or(0);
-            if is_binary_content(&header[..n]) {
-                eprintln!("Warning: skipping binary file '{}'", path.display());
-                self.skipped_binary += 1;
-                return Ok(());
+                writer.write_all(sample_marker(every, total).as_bytes())?;
+                ends_nl = true;
             }
-            file.seek(SeekFrom::Start(0))?;
-            self.formatter.write_file_header(&display_path, writer)?;
-            self.token_count += crate::token_counter::estimate_from_bytes(file_size);
-            if let Err(e) = std::io::copy(&mut file, writer) {
+            if total > max {
+                // The line cap's marker already says the file was cut short.
+                writer.write_all(line_cap_marker(total).as_bytes())?;
+                capped = true;
+                ends_nl = true;
+            }
+            ends_nl
+        } else {
+            copy_noting_newline(source, writer).unwrap_or_else(|e| {
                 eprintln!("Warning: Failed to copy file '{}': {e}", path.display());
+                true
+            })
+        };
+        if let Some(Err(e)) = filter.map(crate::exec::FilterStream::finish) {
+            // Output already written can't be taken back; say it may be partial.
+            eprintln!(
+                "Warning: --content-filter failed for '{}': {e}. Its output may be incomplete.",
+                path.display()
+            );
+        }
+        if capped || file_size <= limit {
+            return Ok(ends_nl);
+        }
+        if !ends_nl {
+            writer.write_all(b"\n")?;
+        }
+        writeln!(
+            writer,
+            "[truncated: {} of {} bytes omitted]",
+            file_size - limit,
+            file_size
+        )?;
+        Ok(true)
+    }
+
+    /// Returns true if `path` should be excluded based on the ignore patterns,
+    /// unless a force-include pattern rescues it.
+    fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        !self.is_force_included(path)
+            && (matches_patterns(&self.ignore_patterns, path, is_dir)
+                || std::path::absolute(path)
+                    .is_ok_and(|abs| ignored_by_files(&self.ignore_files, &abs, is_dir))
+                || resolves_into(&self.resolved_ignores, path))
+    }
+
+    /// Force-include patterns only ever rescue files.
+    fn is_force_included(&self, path: &Path) -> bool {
+        let Some((cwd, matcher)) = &self.force_matcher else {
+            return false;
+        };
+        let abs = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            cwd.join(path)
+        };
+        if abs.starts_with(cwd) {
+            matcher.matched_path_or_any_parents(&abs, false).is_ignore()
+        } else {
+            // Outside the cwd the patterns are anchored at the file's own parent.
+            matches_patterns(&self.force_include, path, false)
+        }
+    }
+
+    /// Returns true if `path` passes the extension filter.
+    /// When `allowed_extensions` is empty, all files pass.
+    fn extension_allowed(&self, path: &Path) -> bool {
+        if self.allowed_extensions.is_empty() || self.is_force_included(path) {
+            return true;
+        }
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) => self.allowed_extensions.contains(&ext.to_lowercase()),
+            None => false,
+        }
+    }
+
+    pub fn aggregate_paths(&mut self, paths: &[String], writer: &mut dyn Write) -> Result<()> {
+        if let Some(preamble) = &self.preamble {
+            writer.write_all(preamble.as_bytes())?;
+        }
+        writer.write_all(self.formatter.document_start().as_bytes())?;
+        if self.formatter.metadata_only() {
+            // File bodies go nowhere; only the rows gathered by write_header are output.
+            self.aggregate_inputs(paths, &mut std::io::sink())?;
+            let rows = std::mem::take(&mut self.metadata_rows);
+            self.token_count = self.token_counter.count(&String::from_utf8_lossy(&rows));
+            writer.write_all(&rows)?;
+        } else if self.toc.is_some() || self.formatter.single_block() {
+            // The index, and a fence no content can close, can only be written
+            // once every file has been emitted.
+            let mut body = Vec::new();
+            self.aggregate_inputs(paths, &mut body)?;
+            let mut head = Vec::new();
+            if let Some(entries) = self.toc.as_deref() {
+                self.formatter.write_toc(entries, &mut head)?;
+            }
+            let fence = self.formatter.single_block().then(|| {
+                let fence = crate::formatter::block_fence(&[&head, &body]);
+                format!("{fence}\n")
+            });
+            if let Some(fence) = &fence {
+                head.splice(0..0, fence.bytes());
             }
+            self.token_count += self.token_counter.count(&String::from_utf8_lossy(&head));
+            writer.write_all(&head)?;
+            writer.write_all(&body)?;
+            if let Some(fence) = &fence {
+                self.token_count += self.token_counter.count(fence);
+                writer.write_all(fence.as_bytes())?;
+            }
+        } else {
+            self.aggregate_inputs(paths, writer)?;
         }
-        writer.write_all(self.formatter.file_footer().as_bytes())?;
-        self.file_count += 1;
+        writer.write_all(self.formatter.document_end().as_bytes())?;
         Ok(())
     }
 
-    /// Like `aggregate_file` but skips `canonicalize()` — path is already canonical.
-    /// Called from `aggregate_directory` which pre-canonicalises the base directory once.
-    fn aggregate_file_precanon(&mut self, path: &Path, writer: &mut dyn Write) -> Result<()> {
-        if is_notebook(path) && self.try_write_notebook(path, path, writer)? {
-            return Ok(());
+    fn aggregate_inputs(&mut self, paths: &[String], writer: &mut dyn Write) -> Result<()> {
+        let plan = self.plan_inputs(paths)?;
+        self.emit_plan(plan, writer)
+    }
+
+    /// Resolve every input to the files it contributes, without reading any.
+    fn plan_inputs(&mut self, paths: &[String]) -> Result<Vec<PlannedInput>> {
+        if self.ignore_output_dir {
+            self.ignore_output_dirs(paths);
         }
-        let file_size = path.metadata().map(|m| m.len()).unwrap_or(0);
-        if file_size <= MAX_EXACT_BYTES {
-            let content = match fs::read(path) {
-                Ok(bytes) => bytes,
-                Err(e) => {
-                    eprintln!("Warning: Failed to read file '{}': {e}", path.display());
-                    return Ok(());
-                }
+        let mut plan = Vec::new();
+        // `git ls-files` listings by canonical directory, so many files from one
+        // directory cost one subprocess.
+        let mut tracked_cache: HashMap<PathBuf, HashSet<PathBuf>> = HashMap::new();
+        for path_str in paths {
+            let path = Path::new(path_str);
+            // One stat per input, reused for every check below.
+            let Ok(metadata) = fs::metadata(path) else {
+                return Err(anyhow::anyhow!("Path does not exist: {}", path_str));
             };
-            if is_binary_content(&content) {
-                eprintln!("Warning: skipping binary file '{}'", path.display());
-                self.skipped_binary += 1;
-                return Ok(());
-            }
-            self.formatter.write_file_header(path, writer)?;
-            let text = String::from_utf8_lossy(&content);
-            self.token_count += self.token_counter.count(&text);
-            if let Err(e) = writer.write_all(&content) {
-                eprintln!("Warning: Failed to write file '{}': {e}", path.display());
+            if self.is_ignored(path, metadata.is_dir()) {
+                continue;
             }
-        } else {
-            let mut file = match fs::File::open(path) {
-                Ok(f) => f,
-                Err(e) => {
-                    eprintln!("Warning: Failed to read file '{}': {e}", path.display());
-                    return Ok(());
+            if metadata.is_file() {
+                if self.only_tracked && !self.is_force_included(path) {
+                    let parent = match path.parent() {
+                        Some(p) if !p.as_os_str().is_empty() => p,
+                        _ => Path::new("."),
+                    };
+                    let tracked =
+                        cached_tracked_files(&mut tracked_cache, parent, self.include_submodules)?;
+                    // Join the name unresolved: git tracks a symlink, not its target.
+                    let canonical = parent
+                        .canonicalize()?
+                        .join(path.file_name().unwrap_or_default());
+                    if !tracked.contains(&canonical) {
+                        continue;
+                    }
                 }
-            };
-            let mut header = [0u8; 8192];
-            let n = file.read(&mut header).unwrap_or(0);
-            if is_binary_content(&header[..n]) {
-                eprintln!("Warning: skipping binary file '{}'", path.display());
-                self.skipped_binary += 1;
-                return Ok(());
+                plan.push(PlannedInput::File(
+                    path.to_path_buf(),
+                    FileMeta::of(&metadata),
+                ));
+            } else if metadata.is_dir() {
+                if !self.include_hidden_in_dirs
+                    && self.is_hidden_file(path)
+                    && !self.is_explicit_path(path, paths)
+                {
+                    continue;
+                }
+                if self.include_submodules {
+                    for submodule in uninitialized_submodules(path) {
+                        eprintln!(
+                            "Note: submodule '{}' is not initialized; skipped \
+                             (run `git submodule update --init`)",
+                            submodule.display()
+                        );
+                    }
+                }
+                let mut files = self.walk_directory(path)?;
+                if self.only_tracked {
+                    let tracked =
+                        cached_tracked_files(&mut tracked_cache, path, self.include_submodules)?;
+                    files.retain(|(f, _)| tracked.contains(f) || self.is_force_included(f));
+                }
+                plan.push(PlannedInput::Directory(files));
             }
-            file.seek(SeekFrom::Start(0))?;
-            self.formatter.write_file_header(path, writer)?;
-            self.token_count += crate::token_counter::estimate_from_bytes(file_size);
-            if let Err(e) = std::io::copy(&mut file, writer) {
-                eprintln!("Warning: Failed to copy file '{}': {e}", path.display());
+        }
+        if self.drop_outliers {
+            self.outlier_limit = outlier_limit(self.plan_sizes(&plan));
+        }
+        Ok(plan)
+    }
+
+    fn emit_plan(&mut self, plan: Vec<PlannedInput>, writer: &mut dyn Write) -> Result<()> {
+        if let Some((name, content)) = self.virtual_file.take() {
+            let content = self.transform(&name, content);
+            self.aggregate_virtual_file(&name, &content, writer)?;
+        }
+        for input in plan {
+            match input {
+                PlannedInput::File(path, stat) => self.aggregate_file(&path, stat, writer)?,
+                PlannedInput::Directory(files) => self.aggregate_walked_files(files, writer)?,
             }
         }
-        writer.write_all(self.formatter.file_footer().as_bytes())?;
-        self.file_count += 1;
+        self.write_blank_summary(writer)?;
+        self.write_skipped_note(writer)?;
+        if let Some(section) = self.diff_section.take() {
+            if self.file_count > 0 {
+                writer.write_all(b"\n")?;
+            }
+            self.token_count += self.token_counter.count(&section);
+            writer.write_all(section.as_bytes())?;
+        }
         Ok(())
     }
 
-    /// Walk `dir_path` in parallel, read file contents in parallel, sort for
-    /// determinism, then write each file sequentially to the output stream.
-    fn aggregate_directory(&mut self, dir_path: &Path, writer: &mut dyn Write) -> Result<()> {
-        use ignore::WalkBuilder;
-
-        // Canonicalise once here; all paths returned by the walker are prefixed with
-        // this canonical root, so per-file canonicalize() calls are unnecessary.
-        let canon_dir = dir_path
-            .canonicalize()
-            .unwrap_or_else(|_| dir_path.to_path_buf());
+    /// Sizes of every file the plan would emit, from the planning stats.
+    fn plan_sizes(&self, plan: &[PlannedInput]) -> Vec<u64> {
+        plan.iter()
+            .flat_map(|input| match input {
+                PlannedInput::File(path, stat) if self.passes_file_filters(path, stat) => {
+                    vec![stat.size]
+                }
+                PlannedInput::File(..) => Vec::new(),
+                PlannedInput::Directory(files) => files.iter().map(|(_, stat)| stat.size).collect(),
+            })
+            .collect()
+    }
 
-        // Compile ignore patterns once, relative to the directory being walked.
+    /// With `--drop-outliers`, skip a file over the run's size cutoff.
+    /// Returns true if dropped. Force-included files are always kept.
+    fn drop_if_outlier(&mut self, path: &Path, size: u64) -> bool {
+        let Some(limit) = self.outlier_limit else {
+            return false;
+        };
+        if size <= limit || self.is_force_included(path) {
+            return false;
+        }
+        let detail = format!(
+            "{}, over {OUTLIER_FACTOR}x the median ({})",
+            format_size(size),
+            format_size(limit / OUTLIER_FACTOR)
+        );
+        self.skip(path, SkipReason::Outlier, Some(detail));
+        true
+    }
+
+    /// Skip a file whose name looks like it holds secrets, unless it is
+    /// force-included. Returns true if skipped.
+    fn skip_if_sensitive(&mut self, path: &Path) -> bool {
+        let sensitive = match (&self.sensitive, path.file_name()) {
+            (Some(matcher), Some(name)) => matcher.matched(Path::new(name), false).is_ignore(),
+            _ => false,
+        };
+        if !sensitive || self.is_force_included(path) {
+            return false;
+        }
+        self.skip(path, SkipReason::Sensitive, None);
+        true
+    }
+
+    /// With `--dedup-headers-in-append`, skip a file whose header is already in
+    /// the output being appended to. Returns true if skipped.
+    fn skip_if_already_written(&mut self, path: &Path) -> bool {
+        let present = match (&self.existing_headers, self.formatter.header_marker(path)) {
+            (Some(existing), Some(marker)) => existing.contains(&marker),
+            _ => false,
+        };
+        if present {
+            self.skip(path, SkipReason::AlreadyPresent, None);
+        }
+        present
+    }
+
+    /// With `--dedup-blank-files`, set aside an empty or whitespace-only file for
+    /// the summary line instead of giving it its own block. Returns true if taken.
+    fn collect_blank(&mut self, path: &Path, content: &[u8]) -> bool {
+        if self.formatter.metadata_only() {
+            return false;
+        }
+        let Some(blank) = self.blank_files.as_mut() else {
+            return false;
+        };
+        if !content.iter().all(u8::is_ascii_whitespace) {
+            return false;
+        }
+        blank.push(path.to_path_buf());
+        // Chunk plans are built from file_tokens; a blank file still belongs in one.
+        if let Some(tokens) = self.file_tokens.as_mut() {
+            tokens.push((path.to_path_buf(), 0));
+        }
+        true
+    }
+
+    /// One line listing every file set aside by `collect_blank`, paths relative
+    /// to the cwd where possible.
+    fn write_blank_summary(&mut self, writer: &mut dyn Write) -> Result<()> {
+        let Some(blank) = self.blank_files.as_mut() else {
+            return Ok(());
+        };
+        if blank.is_empty() {
+            return Ok(());
+        }
+        let blank = std::mem::take(blank);
+        let names: Vec<String> = blank.iter().map(|p| cwd_relative(p)).collect();
+        if self.file_count > 0 {
+            writer.write_all(b"\n")?;
+        }
+        writeln!(
+            writer,
+            "--- Empty files ({}): {} ---",
+            names.len(),
+            names.join(", ")
+        )?;
+        self.file_count += blank.len();
+        Ok(())
+    }
+
+    /// One line per reason naming the binary and outlier files left out, with
+    /// their sizes, so the reader still knows they exist.
+    fn write_skipped_note(&mut self, writer: &mut dyn Write) -> Result<()> {
+        if !self.note_skipped {
+            return Ok(());
+        }
+        let mut wrote_any = false;
+        for (reason, label) in [
+            (SkipReason::Binary, "binary"),
+            (SkipReason::Outlier, "large"),
+        ] {
+            let names: Vec<String> = self
+                .skipped
+                .iter()
+                .filter(|s| s.reason == reason)
+                .map(|s| {
+                    let size = s.path.metadata().map(|m| m.len()).unwrap_or(0);
+                    format!("{} ({})", cwd_relative(&s.path), format_size(size))
+                })
+                .collect();
+            if names.is_empty() {
+                continue;
+            }
+            if !wrote_any && self.file_count > 0 {
+                writer.write_all(b"\n")?;
+            }
+            wrote_any = true;
+            writeln!(writer, "--- Skipped ({label}): {} ---", names.join(", "))?;
+        }
+        Ok(())
+    }
+
+    fn is_explicit_path(&self, path: &Path, input_paths: &[String]) -> bool {
+        input_paths.iter().any(|p| Path::new(p) == path)
+    }
+
+    /// Try to handle `read_path` as a Jupyter notebook.
+    /// Returns Ok(true) if it was handled (written or deliberately skipped),
+    /// Ok(false) if the caller should fall back to normal raw-text handling.
+    fn try_write_notebook(
+        &mut self,
+        read_path: &Path,
+        display_path: &Path,
+        stat: FileMeta,
+        writer: &mut dyn Write,
+    ) -> Result<bool> {
+        if stat.size > crate::notebook::MAX_NOTEBOOK_BYTES {
+            eprintln!(
+                "Warning: notebook '{}' exceeds parse limit; using raw text.",
+                read_path.display()
+            );
+            return Ok(false);
+        }
+        let bytes = match fs::read(read_path) {
+            Ok(b) => b,
+            Err(e) => {
+                self.skip_io(read_path, &e);
+                return Ok(true); // skip; raw path would also fail
+            }
+        };
+        match crate::notebook::extract_notebook_code(&bytes, self.notebook_markdown) {
+            Ok(code) => {
+                self.write_header(display_path, stat, Some(code.as_bytes()), writer)?;
+                self.token_count += self.token_counter.count(&code);
+                writer.write_all(code.as_bytes())?;
+                self.write_footer(ends_with_newline(code.as_bytes()), writer)?;
+                Ok(true)
+            }
+            Err(e) => {
+                eprintln!(
+                    "Warning: failed to parse notebook '{}': {e}. Using raw text.",
+                    read_path.display()
+                );
+                Ok(false)
+            }
+        }
+    }
+
+    /// Write in-memory content under `name`; the name is used verbatim in the header.
+    fn aggregate_virtual_file(
+        &mut self,
+        name: &Path,
+        content: &[u8],
+        writer: &mut dyn Write,
+    ) -> Result<()> {
+        if self.binary_detection.is_binary(content) {
+            self.skip(name, SkipReason::Binary, None);
+            return Ok(());
+        }
+        let stat = FileMeta {
+            size: content.len() as u64,
+            modified: None,
+        };
+        self.write_header(name, stat, Some(content), writer)?;
+        let text = String::from_utf8_lossy(content);
+        self.token_count += self.token_counter.count(&text);
+        writer.write_all(content)?;
+        self.write_footer(ends_with_newline(content), writer)
+    }
+
+    /// Aggregate a single file; canonicalises path before passing to formatter.
+    fn aggregate_file(
+        &mut self,
+        path: &Path,
+        stat: FileMeta,
+        writer: &mut dyn Write,
+    ) -> Result<()> {
+        if !self.passes_file_filters(path, &stat) || self.skip_if_sensitive(path) {
+            return Ok(());
+        }
+        let file_size = stat.size;
+        if self.drop_if_outlier(path, file_size) {
+            return Ok(());
+        }
+        // Keep the link itself as the display path so its header can name the target.
+        let display_path = if self.symlink_targets && is_symlink(path) {
+            std::env::current_dir()
+                .map(|cwd| cwd.join(path))
+                .unwrap_or_else(|_| path.to_path_buf())
+        } else {
+            path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+        };
+        if self.skip_if_already_written(&display_path) {
+            return Ok(());
+        }
+        if is_notebook(path) && self.try_write_notebook(path, &display_path, stat, writer)? {
+            return Ok(());
+        }
+        let ends_with_newline = if file_size <= MAX_EXACT_BYTES {
+            let content = match fs::read(path) {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    self.skip_io(path, &e);
+                    return Ok(());
+                }
+            };
+            if self.binary_detection.is_binary(&content) {
+                self.skip(path, SkipReason::Binary, None);
+                return Ok(());
+            }
+            let content = self.transform(path, content);
+            if self.collect_blank(&display_path, &content) {
+                return Ok(());
+            }
+            self.write_header(&display_path, stat, Some(&content), writer)?;
+            let text = String::from_utf8_lossy(&content);
+            self.token_count += self.token_counter.count(&text);
+            if let Err(e) = writer.write_all(&content) {
+                eprintln!("Warning: Failed to write file '{}': {e}", path.display());
+            }
+            ends_with_newline(&content)
+        } else {
+            let mut file = match fs::File::open(path) {
+                Ok(f) => f,
+                Err(e) => {
+                    self.skip_io(path, &e);
+                    return Ok(());
+                }
+            };
+            let mut header = [0u8; 8192];
+            let n = file.read(&mut header).unwrap_or(0);
+            if self.binary_detection.is_binary(&header[..n]) {
+                self.skip(path, SkipReason::Binary, None);
+                return Ok(());
+            }
+            file.seek(SeekFrom::Start(0))?;
+            self.write_header(&display_path, stat, None, writer)?;
+            self.stream_large_file(&mut file, path, file_size, writer)?
+        };
+        self.write_footer(ends_with_newline, writer)?;
+        Ok(())
+    }
+
+    /// Extension and `--modified-within` checks for an explicitly listed file.
+    fn passes_file_filters(&self, path: &Path, stat: &FileMeta) -> bool {
+        if !self.extension_allowed(path) {
+            return false;
+        }
+        match self.modified_cutoff {
+            Some(cutoff) => stat.modified_since(cutoff) || self.is_force_included(path),
+            None => true,
+        }
+    }
+
+    /// Like `aggregate_file` but skips `canonicalize()` — path is already canonical.
+    /// Called from `aggregate_walked_files`; `walk_directory` pre-canonicalises the base directory once.
+    fn aggregate_file_precanon(
+        &mut self,
+        path: &Path,
+        stat: FileMeta,
+        writer: &mut dyn Write,
+    ) -> Result<()> {
+        if is_notebook(path) && self.try_write_notebook(path, path, stat, writer)? {
+            return Ok(());
+        }
+        let file_size = stat.size;
+        let ends_with_newline = if file_size <= MAX_EXACT_BYTES {
+            let content = match fs::read(path) {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    self.skip_io(path, &e);
+                    return Ok(());
+                }
+            };
+            if self.binary_detection.is_binary(&content) {
+                self.skip(path, SkipReason::Binary, None);
+                return Ok(());
+            }
+            let content = self.transform(path, content);
+            if self.collect_blank(path, &content) {
+                return Ok(());
+            }
+            self.write_header(path, stat, Some(&content), writer)?;
+            let text = String::from_utf8_lossy(&content);
+            self.token_count += self.token_counter.count(&text);
+            if let Err(e) = writer.write_all(&content) {
+                eprintln!("Warning: Failed to write file '{}': {e}", path.display());
+            }
+            ends_with_newline(&content)
+        } else {
+            let mut file = match fs::File::open(path) {
+                Ok(f) => f,
+                Err(e) => {
+                    self.skip_io(path, &e);
+                    return Ok(());
+                }
+            };
+            let mut header = [0u8; 8192];
+            let n = file.read(&mut header).unwrap_or(0);
+            if self.binary_detection.is_binary(&header[..n]) {
+                self.skip(path, SkipReason::Binary, None);
+                return Ok(());
+            }
+            file.seek(SeekFrom::Start(0))?;
+            self.write_header(path, stat, None, writer)?;
+            self.stream_large_file(&mut file, path, file_size, writer)?
+        };
+        self.write_footer(ends_with_newline, writer)?;
+        Ok(())
+    }
+
+    /// Walk `dir_path` in parallel and return the files to emit with the
+    /// walker's stat of each, sorted for determinism unless `--no-sort`.
+    fn walk_directory(&self, dir_path: &Path) -> Result<Vec<(PathBuf, FileMeta)>> {
+        use ignore::WalkBuilder;
+
+        // Canonicalise once here; all paths returned by the walker are prefixed with
+        // this canonical root, so per-file canonicalize() calls are unnecessary.
+        let canon_dir = dir_path
+            .canonicalize()
+            .unwrap_or_else(|_| dir_path.to_path_buf());
+
+        // Compile ignore patterns once, relative to the directory being walked.
         // Using gitignore semantics: "target" matches any component named "target",
         // "*.o" matches by filename, "/build" matches only at the root of canon_dir.
         let gitignore = build_gitignore(&self.ignore_patterns, &canon_dir);
         let allowed_ext = self.allowed_extensions.clone();
-        let (tx, rx) = mpsc::channel::<PathBuf>();
+        let modified_cutoff = self.modified_cutoff;
+        let max_entries = self.max_entries;
+        let (tx, rx) = mpsc::channel::<(PathBuf, FileMeta)>();
 
+        // With --hidden-root, a hidden directory named on the command line is
+        // walked with its dotfiles (the user asked for that tree), but not `.git`.
+        let hidden_root =
+            self.hidden_root && !self.include_hidden_in_dirs && has_hidden_name(dir_path);
         let walker = WalkBuilder::new(&canon_dir)
-            .hidden(!self.include_hidden_in_dirs) // hidden(true) = skip dotfiles
+            .hidden(!(self.include_hidden_in_dirs || hidden_root)) // hidden(true) = skip dotfiles
+            .filter_entry(move |e| !(hidden_root && e.file_name() == ".git"))
             .git_ignore(true)
             .follow_links(true)
+            .threads(self.walk_threads)
             .build_parallel();
 
         walker.run(|| {
             let tx = tx.clone();
             let gitignore = Arc::clone(&gitignore);
+            let resolved_ignores = Arc::clone(&self.resolved_ignores);
+            let ignore_files = Arc::clone(&self.ignore_files);
             let allowed_ext = allowed_ext.clone();
+            let entries_seen = Arc::clone(&self.entries_seen);
             Box::new(move |result| {
                 use ignore::WalkState;
+                if max_entries > 0 && entries_seen.fetch_add(1, Ordering::Relaxed) >= max_entries {
+                    return WalkState::Quit;
+                }
+                if let Err(ref err) = result {
+                    if is_loop_error(err) {
+                        eprintln!("Warning: skipping symlink cycle: {err}");
+                    }
+                }
                 if let Ok(entry) = result {
                     let path = entry.path();
                     let is_dir = entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false);
 
                     // matched() strips the canon_dir prefix internally, so relative
                     // patterns like "target" correctly match absolute walker paths.
-                    if gitignore.matched(path, is_dir).is_ignore() {
+                    if gitignore.matched(path, is_dir).is_ignore()
+                        || ignored_by_files(&ignore_files, path, is_dir)
+                        || resolves_into(&resolved_ignores, path)
+                    {
+                        log::debug!("excluded by an ignore pattern: {}", path.display());
                         return if is_dir {
                             WalkState::Skip // prune the entire subtree
                         } else {
@@ -385,6 +2131,13 @@ impl ContentAggregator {
                     }
 
                     if is_dir {
+                        if entry.path_is_symlink() && is_symlink_cycle(path) {
+                            eprintln!(
+                                "Warning: skipping '{}': symlink cycle back to an ancestor directory",
+                                path.display()
+                            );
+                            return WalkState::Skip;
+                        }
                         return WalkState::Continue;
                     }
 
@@ -396,168 +2149,967 @@ impl ContentAggregator {
                             .map(|ext| allowed_ext.contains(&ext.to_lowercase()))
                             .unwrap_or(false)
                     };
-                    if allowed {
-                        let _ = tx.send(path.to_path_buf());
+                    if !allowed {
+                        log::trace!("filtered out by extension: {}", path.display());
+                        return WalkState::Continue;
+                    }
+                    let stat = entry.metadata().map(|m| FileMeta::of(&m)).unwrap_or_default();
+                    if modified_cutoff.is_none_or(|c| stat.modified_since(c)) {
+                        let _ = tx.send((path.to_path_buf(), stat));
+                    } else {
+                        log::trace!("filtered out by --modified-within: {}", path.display());
                     }
                 }
                 WalkState::Continue
             })
         });
         drop(tx); // close the last sender so rx drains cleanly
+        if self.max_entries > 0 && self.entries_seen.load(Ordering::Relaxed) > self.max_entries {
+            return Err(self.entry_limit_error(dir_path));
+        }
+
+        let mut file_paths: Vec<(PathBuf, FileMeta)> = rx.into_iter().collect();
+
+        // Force-included files may sit behind hidden, gitignore or exclude rules
+        // that pruned them above, so find them with an unfiltered walk. It is held
+        // to --max-entries on its own count, and never descends into `.git`.
+        if !self.force_include.is_empty() {
+            let force = build_gitignore(&self.force_include, &canon_dir);
+            let mut seen: std::collections::HashSet<PathBuf> =
+                file_paths.iter().map(|(p, _)| p.clone()).collect();
+            let walker = WalkBuilder::new(&canon_dir)
+                .standard_filters(false)
+                .follow_links(true)
+                .filter_entry(|e| e.file_name() != ".git")
+                .build();
+            for (visited, entry) in walker.filter_map(|e| e.ok()).enumerate() {
+                if self.max_entries > 0 && visited >= self.max_entries {
+                    return Err(self.entry_limit_error(dir_path));
+                }
+                let is_file = entry.file_type().is_some_and(|ft| ft.is_file());
+                if is_file
+                    && force
+                        .matched_path_or_any_parents(entry.path(), false)
+                        .is_ignore()
+                    && seen.insert(entry.path().to_path_buf())
+                {
+                    let stat = entry
+                        .metadata()
+                        .map(|m| FileMeta::of(&m))
+                        .unwrap_or_default();
+                    file_paths.push((entry.into_path(), stat));
+                }
+            }
+        }
+
+        if !self.output_files.is_empty() {
+            file_paths.retain(|(p, _)| !self.output_files.contains(p));
+        }
+        if let Some(grouping) = self.dir_grouping {
+            file_paths.par_sort_unstable_by(|(a, _), (b, _)| grouping.compare(a, b));
+        } else if self.sort {
+            // Parallel sort for deterministic output ordering
+            file_paths.par_sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+        }
+        Ok(file_paths)
+    }
+
+    fn entry_limit_error(&self, dir_path: &Path) -> anyhow::Error {
+        anyhow::anyhow!(
+            "Stopped after visiting {} entries under '{}' (the --max-entries limit). \
+             Narrow the input paths or raise the limit",
+            crate::token_counter::format_count(self.max_entries),
+            dir_path.display()
+        )
+    }
+
+    /// Read the files from `walk_directory` in parallel, then write each one
+    /// sequentially to the output stream.
+    fn aggregate_walked_files(
+        &mut self,
+        mut file_paths: Vec<(PathBuf, FileMeta)>,
+        writer: &mut dyn Write,
+    ) -> Result<()> {
+        if self.outlier_limit.is_some() {
+            file_paths.retain(|(path, stat)| !self.drop_if_outlier(path, stat.size));
+        }
+        if self.sensitive.is_some() {
+            file_paths.retain(|(path, _)| !self.skip_if_sensitive(path));
+        }
+        if self.existing_headers.is_some() {
+            file_paths.retain(|(path, _)| !self.skip_if_already_written(path));
+        }
+
+        // Read file contents in parallel across all CPU cores, then write sequentially.
+        // This separates I/O (parallelisable) from the clipboard write stream (must be serial).
+        let read_results: Vec<(PathBuf, FileMeta, FileReadResult)> = file_paths
+            .into_par_iter()
+            .map(|(path, stat)| {
+                let result =
+                    match read_file_for_aggregation(&path, stat.size, self.binary_detection) {
+                        FileReadResult::Content(bytes) => {
+                            FileReadResult::Content(self.transform(&path, bytes))
+                        }
+                        other => other,
+                    };
+                (path, stat, result)
+            })
+            .collect();
+
+        for (path, stat, result) in read_results {
+            match result {
+                FileReadResult::Content(bytes) if self.collect_blank(&path, &bytes) => {}
+                FileReadResult::Content(bytes) => {
+                    self.write_header(&path, stat, Some(&bytes), writer)?;
+                    let text = String::from_utf8_lossy(&bytes);
+                    self.token_count += self.token_counter.count(&text);
+                    if let Err(e) = writer.write_all(&bytes) {
+                        eprintln!("Warning: Failed to write file '{}': {e}", path.display());
+                    }
+                    self.write_footer(ends_with_newline(&bytes), writer)?;
+                }
+                FileReadResult::Binary => {
+                    self.skip(&path, SkipReason::Binary, None);
+                }
+                FileReadResult::LargeFile(file_size) => match fs::File::open(&path) {
+                    Ok(mut file) => {
+                        self.write_header(&path, stat, None, writer)?;
+                        let ends_nl =
+                            self.stream_large_file(&mut file, &path, file_size, writer)?;
+                        self.write_footer(ends_nl, writer)?;
+                    }
+                    Err(e) => self.skip_io(&path, &e),
+                },
+                FileReadResult::Notebook => {
+                    self.aggregate_file_precanon(&path, stat, writer)?;
+                }
+                FileReadResult::ReadError(e) => self.skip_io(&path, &e),
+            }
+        }
+        Ok(())
+    }
+
+    fn skip(&mut self, path: &Path, reason: SkipReason, detail: Option<String>) {
+        log::debug!("skipping {}: {}", path.display(), reason.label());
+        self.skipped.push(SkippedFile {
+            path: path.to_path_buf(),
+            reason,
+            detail,
+        });
+    }
+
+    fn skip_io(&mut self, path: &Path, e: &std::io::Error) {
+        self.skip(path, SkipReason::from_io(e), Some(e.to_string()));
+    }
+
+    fn is_hidden_file(&self, path: &Path) -> bool {
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .map(|name| name.starts_with('.'))
+            .unwrap_or(false)
+    }
+
+    pub fn file_count(&self) -> usize {
+        self.file_count
+    }
+
+    pub fn token_count(&self) -> usize {
+        self.token_count
+    }
+
+    pub fn compact_saved(&self) -> u64 {
+        self.compact_saved.load(Ordering::Relaxed)
+    }
+
+    pub fn skipped_files(&self) -> &[SkippedFile] {
+        &self.skipped
+    }
+
+    /// Emitted file paths in output order (`--exec`).
+    pub fn file_list(&self) -> &[PathBuf] {
+        self.file_list.as_deref().unwrap_or_default()
+    }
+
+    /// Per-file (path, tokens) in output order (`--chunk-by-tokens`).
+    pub fn file_tokens(&self) -> &[(PathBuf, usize)] {
+        self.file_tokens.as_deref().unwrap_or_default()
+    }
+
+    /// Per-file (path, line count), longest first.
+    pub fn line_counts(&self) -> Vec<(PathBuf, usize)> {
+        let mut rows = self.line_counts.clone().unwrap_or_default();
+        rows.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        rows
+    }
+
+    /// Per-extension (extension, file count, bytes), largest first.
+    /// Files without an extension are reported under an empty string.
+    pub fn ext_summary(&self) -> Vec<(String, usize, u64)> {
+        let mut rows: Vec<(String, usize, u64)> = self
+            .ext_stats
+            .iter()
+            .flatten()
+            .map(|(ext, &(count, bytes))| (ext.clone(), count, bytes))
+            .collect();
+        rows.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::PathHeader;
+    use crate::formatter::{build_formatter, FenceLanguages, FormatChoice};
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn xml_aggregator(no_path: bool) -> ContentAggregator {
+        let header = if no_path {
+            PathHeader::None
+        } else {
+            PathHeader::Absolute
+        };
+        ContentAggregator::new(
+            build_formatter(FormatChoice::Xml, header, FenceLanguages::default()),
+            false,
+            vec![],
+            true,
+            std::collections::HashSet::new(),
+        )
+    }
+
+    #[test]
+    fn test_aggregate_single_file() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.txt");
+        fs::write(&file_path, "Hello, World!").unwrap();
+
+        let mut aggregator = xml_aggregator(false);
+        let mut buffer = Vec::new();
+        aggregator
+            .aggregate_paths(&[file_path.to_str().unwrap().to_string()], &mut buffer)
+            .unwrap();
+        let content = String::from_utf8(buffer).unwrap();
+
+        assert!(content.contains("Hello, World!"));
+        assert!(content.contains("<file path="));
+        assert_eq!(aggregator.file_count(), 1);
+    }
+
+    #[test]
+    fn test_aggregate_file_without_headers() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.txt");
+        fs::write(&file_path, "Hello, World!").unwrap();
+
+        let mut aggregator = xml_aggregator(true);
+        let mut buffer = Vec::new();
+        aggregator
+            .aggregate_paths(&[file_path.to_str().unwrap().to_string()], &mut buffer)
+            .unwrap();
+        let content = String::from_utf8(buffer).unwrap();
+
+        assert!(content.contains("Hello, World!"));
+        assert!(content.contains("<file>"));
+        assert!(!content.contains("path="));
+        assert_eq!(aggregator.file_count(), 1);
+    }
+
+    #[test]
+    fn test_aggregate_directory() {
+        let dir = tempdir().unwrap();
+        let subdir = dir.path().join("subdir");
+        fs::create_dir(&subdir).unwrap();
+
+        let file1 = dir.path().join("file1.txt");
+        let file2 = subdir.join("file2.txt");
+
+        fs::write(&file1, "File 1 content").unwrap();
+        fs::write(&file2, "File 2 content").unwrap();
+
+        let mut aggregator = xml_aggregator(false);
+        let mut buffer = Vec::new();
+        aggregator
+            .aggregate_paths(&[dir.path().to_str().unwrap().to_string()], &mut buffer)
+            .unwrap();
+        let content = String::from_utf8(buffer).unwrap();
+
+        assert!(content.contains("File 1 content"));
+        assert!(content.contains("File 2 content"));
+        assert_eq!(aggregator.file_count(), 2);
+    }
+
+    #[test]
+    fn test_aggregate_nonexistent_path() {
+        let mut aggregator = xml_aggregator(false);
+        let mut buffer = Vec::new();
+        let result = aggregator.aggregate_paths(&["nonexistent_file.txt".to_string()], &mut buffer);
+
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Path does not exist"));
+    }
+
+    #[test]
+    fn test_skip_hidden_files_in_directory() {
+        let dir = tempdir().unwrap();
+        let visible_file = dir.path().join("visible.txt");
+        let hidden_file = dir.path().join(".hidden.txt");
+
+        fs::write(&visible_file, "Visible content").unwrap();
+        fs::write(&hidden_file, "Hidden content").unwrap();
+
+        let mut aggregator = xml_aggregator(false);
+        let mut buffer = Vec::new();
+        aggregator
+            .aggregate_paths(&[dir.path().to_str().unwrap().to_string()], &mut buffer)
+            .unwrap();
+        let content = String::from_utf8(buffer).unwrap();
+
+        assert!(content.contains("Visible content"));
+        assert!(!content.contains("Hidden content"));
+        assert_eq!(aggregator.file_count(), 1);
+    }
+
+    #[test]
+    fn test_hidden_root_recurses_fully_except_git() {
+        let dir = tempdir().unwrap();
+        let config = dir.path().join(".config");
+        let nvim = config.join("nvim");
+        fs::create_dir_all(nvim.join(".lua")).unwrap();
+        fs::create_dir_all(config.join(".git")).unwrap();
+        fs::write(nvim.join("init.lua"), "Init content").unwrap();
+        fs::write(nvim.join(".luarc.json"), "Luarc content").unwrap();
+        fs::write(nvim.join(".lua").join("plugins.lua"), "Plugin content").unwrap();
+        fs::write(config.join(".git").join("config"), "Git internals").unwrap();
+
+        let run = |path: &Path| {
+            let mut aggregator = xml_aggregator(false).with_hidden_root(true);
+            let mut buffer = Vec::new();
+            aggregator
+                .aggregate_paths(&[path.to_str().unwrap().to_string()], &mut buffer)
+                .unwrap();
+            String::from_utf8(buffer).unwrap()
+        };
+        let content = run(&config);
+        assert!(content.contains("Init content"));
+        assert!(content.contains("Luarc content"));
+        assert!(content.contains("Plugin content"));
+        assert!(!content.contains("Git internals"));
+
+        // Only the directory's own name counts, not a hidden ancestor.
+        let content = run(&nvim);
+        assert!(content.contains("Init content"));
+        assert!(!content.contains("Luarc content"));
+    }
+
+    #[test]
+    fn test_include_hidden_files_in_directory_with_flag() {
+        let dir = tempdir().unwrap();
+        let visible_file = dir.path().join("visible.txt");
+        let hidden_file = dir.path().join(".hidden.txt");
+
+        fs::write(&visible_file, "Visible content").unwrap();
+        fs::write(&hidden_file, "Hidden content").unwrap();
+
+        let mut aggregator = ContentAggregator::new(
+            build_formatter(
+                FormatChoice::Xml,
+                PathHeader::Absolute,
+                FenceLanguages::default(),
+            ),
+            true,
+            vec![],
+            true,
+            std::collections::HashSet::new(),
+        );
+        let mut buffer = Vec::new();
+        aggregator
+            .aggregate_paths(&[dir.path().to_str().unwrap().to_string()], &mut buffer)
+            .unwrap();
+        let content = String::from_utf8(buffer).unwrap();
+
+        assert!(content.contains("Visible content"));
+        assert!(content.contains("Hidden content"));
+        assert_eq!(aggregator.file_count(), 2);
+    }
+
+    #[test]
+    fn test_one_blank_line_between_files() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), "no newline").unwrap();
+        fs::write(dir.path().join("b.txt"), "with newline\n").unwrap();
+        fs::write(dir.path().join("c.txt"), "last").unwrap();
+
+        let mut aggregator = xml_aggregator(true);
+        let mut buffer = Vec::new();
+        aggregator
+            .aggregate_paths(&[dir.path().to_str().unwrap().to_string()], &mut buffer)
+            .unwrap();
+        let content = String::from_utf8(buffer).unwrap();
+
+        assert_eq!(
+            content,
+            "<context>\n<file>\nno newline\n</file>\n\n<file>\nwith newline\n</file>\n\n<file>\nlast\n</file>\n</context>\n"
+        );
+    }
+
+    #[test]
+    fn test_header_meta() {
+        use crate::cli::MetaField;
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.txt");
+        fs::write(&file_path, "one\ntwo\nthree").unwrap();
+
+        let mut aggregator =
+            xml_aggregator(false).with_header_meta(vec![MetaField::Size, MetaField::Lines]);
+        let mut buffer = Vec::new();
+        aggregator
+            .aggregate_paths(&[file_path.to_str().unwrap().to_string()], &mut buffer)
+            .unwrap();
+        let content = String::from_utf8(buffer).unwrap();
+
+        assert!(content.contains("meta=\"13B, 3 lines\""));
+    }
+
+    #[test]
+    fn test_dir_grouping_orders_files_around_subdirectories() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("a/sub")).unwrap();
+        fs::create_dir(dir.path().join("z")).unwrap();
+        for name in ["m.txt", "a/x.txt", "a/sub/y.txt", "z/w.txt"] {
+            fs::write(dir.path().join(name), name).unwrap();
+        }
+        let order = |grouping| {
+            let aggregator = xml_aggregator(false).with_dir_grouping(grouping);
+            let files = aggregator.walk_directory(dir.path()).unwrap();
+            let root = dir.path().canonicalize().unwrap();
+            files
+                .iter()
+                .map(|(p, _)| {
+                    p.strip_prefix(&root)
+                        .unwrap()
+                        .to_string_lossy()
+                        .into_owned()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(order(None), ["a/sub/y.txt", "a/x.txt", "m.txt", "z/w.txt"]);
+        assert_eq!(
+            order(Some(DirGrouping::FilesFirst)),
+            ["m.txt", "a/x.txt", "a/sub/y.txt", "z/w.txt"]
+        );
+        assert_eq!(
+            order(Some(DirGrouping::DirsFirst)),
+            ["a/sub/y.txt", "a/x.txt", "z/w.txt", "m.txt"]
+        );
+    }
+
+    #[test]
+    fn test_walked_files_carry_their_size() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), "12345").unwrap();
+        fs::write(dir.path().join("b.txt"), "").unwrap();
+        let files = xml_aggregator(false).walk_directory(dir.path()).unwrap();
+        let sizes: Vec<u64> = files.iter().map(|(_, stat)| stat.size).collect();
+        assert_eq!(sizes, [5, 0]);
+        assert!(files.iter().all(|(_, stat)| stat.modified.is_some()));
+    }
+
+    #[test]
+    fn test_line_counts_sorted_descending() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("short.txt"), "one\n").unwrap();
+        fs::write(dir.path().join("long.txt"), "a\nb\nc").unwrap();
+        let mut aggregator = xml_aggregator(false).with_line_counts(true);
+        aggregator
+            .aggregate_paths(&[dir.path().to_string_lossy().to_string()], &mut Vec::new())
+            .unwrap();
+        let counts: Vec<_> = aggregator
+            .line_counts()
+            .into_iter()
+            .map(|(p, n)| (p.file_name().unwrap().to_string_lossy().to_string(), n))
+            .collect();
+        assert_eq!(
+            counts,
+            vec![("long.txt".to_string(), 3), ("short.txt".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_toc_numbers_emitted_files_in_order() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), "one\n").unwrap();
+        fs::write(dir.path().join("b.bin"), b"\0\x01\x02").unwrap();
+        fs::write(dir.path().join("c.txt"), "a\nb\nc\n").unwrap();
+        let mut aggregator = xml_aggregator(false).with_toc(true);
+        let mut out = Vec::new();
+        aggregator
+            .aggregate_paths(&[dir.path().to_string_lossy().to_string()], &mut out)
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let root = dir.path().display();
+        let toc = format!("<toc>\n1. {root}/a.txt (1 line)\n2. {root}/c.txt (3 lines)\n</toc>\n\n");
+        assert!(
+            out.starts_with(&format!("<context>\n{toc}<file path=")),
+            "{out}"
+        );
+        assert!(out.contains(&format!("<file path=\"{root}/c.txt\" index=\"2\">")));
+    }
+
+    #[test]
+    fn test_existing_headers_are_indexed_with_their_numbers_dropped() {
+        let dir = tempdir().unwrap();
+        for name in ["a.txt", "ab.txt", "b.txt"] {
+            fs::write(dir.path().join(name), name).unwrap();
+        }
+        let root = dir.path().canonicalize().unwrap();
+        let root = root.display();
+        let existing =
+            format!("## File 1: {root}/a.txt\n\n```\nx\n```\n\n## File: {root}/ab.txt.bak -> y\n");
+        let mut aggregator = ContentAggregator::new(
+            build_formatter(
+                FormatChoice::Markdown,
+                PathHeader::Absolute,
+                FenceLanguages::default(),
+            ),
+            false,
+            vec![],
+            true,
+            HashSet::new(),
+        )
+        .with_existing_output(Some(existing));
+        let mut out = Vec::new();
+        aggregator
+            .aggregate_paths(&[dir.path().to_string_lossy().to_string()], &mut out)
+            .unwrap();
+        let skipped: Vec<_> = aggregator.skipped_files().iter().map(|s| &s.path).collect();
+        assert_eq!(skipped, [&dir.path().canonicalize().unwrap().join("a.txt")]);
+        assert_eq!(aggregator.file_count(), 2);
+    }
+
+    #[test]
+    fn test_diff_section_counts_toward_tokens() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), "one\n").unwrap();
+        let inputs = [dir.path().to_string_lossy().to_string()];
+        let mut plain = xml_aggregator(true);
+        plain.aggregate_paths(&inputs, &mut Vec::new()).unwrap();
+        let section = "=== Diff vs HEAD ===\n+added a line with several words\n";
+        let mut with_diff = xml_aggregator(true).with_diff_section(Some(section.to_string()));
+        with_diff.aggregate_paths(&inputs, &mut Vec::new()).unwrap();
+        assert_eq!(
+            with_diff.token_count(),
+            plain.token_count() + crate::token_counter::TokenCounter::new().count(section)
+        );
+    }
+
+    #[test]
+    fn test_single_block_fence_outgrows_content_backticks() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.md"), "```rust\nfn a() {}\n````\n").unwrap();
+        fs::write(dir.path().join("b.txt"), "plain").unwrap();
+        let mut aggregator = ContentAggregator::new(
+            build_formatter(
+                FormatChoice::SingleBlock,
+                PathHeader::Absolute,
+                FenceLanguages::default(),
+            ),
+            false,
+            vec![],
+            true,
+            std::collections::HashSet::new(),
+        );
+        let mut out = Vec::new();
+        aggregator
+            .aggregate_paths(&[dir.path().to_string_lossy().to_string()], &mut out)
+            .unwrap();
+        let root = dir.path().display();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!(
+                "`````\n--- File: {root}/a.md ---\n```rust\nfn a() {{}}\n````\n\n\
+                 --- File: {root}/b.txt ---\nplain\n`````\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_chunk_by_tokens_breaks_at_file_boundaries() {
+        let files: Vec<(PathBuf, usize)> = [("a", 400), ("b", 500), ("c", 200), ("d", 1500)]
+            .iter()
+            .map(|(p, n)| (PathBuf::from(p), *n))
+            .collect();
+        let names = |chunks: Vec<Vec<PathBuf>>| {
+            chunks
+                .iter()
+                .map(|c| c.iter().map(|p| p.to_string_lossy()).collect::<String>())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(chunk_by_tokens(&files, 1000)), ["ab", "c", "d"]);
+        assert_eq!(names(chunk_by_tokens(&files, 5000)), ["abcd"]);
+        assert!(chunk_by_tokens(&[], 1000).is_empty());
+    }
+
+    #[test]
+    fn test_dedup_blank_files_summarised() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("pkg")).unwrap();
+        fs::write(dir.path().join("pkg/__init__.py"), "").unwrap();
+        fs::write(dir.path().join("pkg/blank.py"), " \n\t\n").unwrap();
+        fs::write(dir.path().join("pkg/mod.py"), "x = 1\n").unwrap();
+
+        let mut aggregator = xml_aggregator(false).with_dedup_blank_files(true);
+        let mut output = Vec::new();
+        aggregator
+            .aggregate_paths(&[dir.path().to_string_lossy().to_string()], &mut output)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches("<file ").count(), 1);
+        assert!(output.contains("--- Empty files (2): "));
+        assert!(output.contains("__init__.py"));
+        assert!(output.contains("blank.py ---"));
+        assert_eq!(aggregator.file_count(), 3);
+    }
+
+    #[test]
+    fn test_dedup_blank_files_stay_in_chunk_plan() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.py"), "").unwrap();
+        fs::write(dir.path().join("b.py"), "x = 1\n").unwrap();
+        let mut aggregator = xml_aggregator(true)
+            .with_dedup_blank_files(true)
+            .with_file_tokens(true);
+        aggregator
+            .aggregate_paths(&[dir.path().to_string_lossy().to_string()], &mut Vec::new())
+            .unwrap();
+        let names: Vec<_> = aggregator
+            .file_tokens()
+            .iter()
+            .map(|(p, _)| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, ["a.py", "b.py"]);
+        assert_eq!(aggregator.file_tokens()[0].1, 0);
+    }
+
+    #[test]
+    fn test_note_skipped_lists_binaries_with_sizes() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+        fs::write(dir.path().join("data.bin"), vec![0u8; 2048]).unwrap();
 
-        let mut file_paths: Vec<PathBuf> = rx.into_iter().collect();
+        let mut aggregator = xml_aggregator(false).with_note_skipped(true);
+        let mut output = Vec::new();
+        aggregator
+            .aggregate_paths(&[dir.path().to_string_lossy().to_string()], &mut output)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("--- Skipped (binary): "));
+        assert!(output.contains("data.bin (2.0K) ---"));
+        assert!(!output.contains("Skipped (large)"));
+    }
 
-        if self.sort {
-            // Parallel sort for deterministic output ordering
-            file_paths.par_sort_unstable();
-        }
+    #[test]
+    fn test_sensitive_files_are_skipped_unless_allowed() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+        fs::write(dir.path().join("server.pem"), "-----BEGIN-----\n").unwrap();
+        fs::write(dir.path().join(".env"), "TOKEN=secret\n").unwrap();
+        fs::write(dir.path().join(".env.example"), "TOKEN=\n").unwrap();
+        let paths = [
+            dir.path(),
+            &dir.path().join(".env"),
+            &dir.path().join(".env.example"),
+        ]
+        .map(|p| p.to_string_lossy().to_string());
+        let patterns: Vec<String> = SENSITIVE_PATTERNS.iter().map(|p| p.to_string()).collect();
 
-        // Read file contents in parallel across all CPU cores, then write sequentially.
-        // This separates I/O (parallelisable) from the clipboard write stream (must be serial).
-        let read_results: Vec<(PathBuf, FileReadResult)> = file_paths
-            .into_par_iter()
-            .map(|path| {
-                let result = read_file_for_aggregation(&path);
-                (path, result)
-            })
+        let mut aggregator = xml_aggregator(false).with_sensitive_patterns(&patterns);
+        let mut output = Vec::new();
+        aggregator.aggregate_paths(&paths, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("fn main()") && output.contains("TOKEN=\n"));
+        assert!(!output.contains("secret") && !output.contains("BEGIN"));
+        let skipped: Vec<_> = aggregator
+            .skipped_files()
+            .iter()
+            .filter(|s| s.reason == SkipReason::Sensitive)
             .collect();
+        assert_eq!(skipped.len(), 2);
 
-        for (path, result) in read_results {
-            match result {
-                FileReadResult::Content(bytes) => {
-                    self.formatter.write_file_header(&path, writer)?;
-                    let text = String::from_utf8_lossy(&bytes);
-                    self.token_count += self.token_counter.count(&text);
-                    if let Err(e) = writer.write_all(&bytes) {
-                        eprintln!("Warning: Failed to write file '{}': {e}", path.display());
-                    }
-                    writer.write_all(self.formatter.file_footer().as_bytes())?;
-                    self.file_count += 1;
-                }
-                FileReadResult::Binary => {
-                    eprintln!("Warning: skipping binary file '{}'", path.display());
-                    self.skipped_binary += 1;
-                }
-                FileReadResult::LargeFile => {
-                    let file_size = path.metadata().map(|m| m.len()).unwrap_or(0);
-                    match fs::File::open(&path) {
-                        Ok(mut file) => {
-                            self.formatter.write_file_header(&path, writer)?;
-                            self.token_count +=
-                                crate::token_counter::estimate_from_bytes(file_size);
-                            if let Err(e) = std::io::copy(&mut file, writer) {
-                                eprintln!("Warning: Failed to copy file '{}': {e}", path.display());
-                            }
-                            writer.write_all(self.formatter.file_footer().as_bytes())?;
-                            self.file_count += 1;
-                        }
-                        Err(e) => {
-                            eprintln!("Warning: Failed to open file '{}': {e}", path.display());
-                        }
-                    }
-                }
-                FileReadResult::Notebook => {
-                    self.aggregate_file_precanon(&path, writer)?;
-                }
-                FileReadResult::ReadError(msg) => {
-                    eprintln!("{msg}");
-                }
-            }
+        let mut aggregator = xml_aggregator(false).with_sensitive_patterns(&[]);
+        let mut output = Vec::new();
+        aggregator.aggregate_paths(&paths, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("secret") && output.contains("BEGIN"));
+    }
+
+    #[test]
+    fn test_utf8_bom_is_stripped_unless_kept() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("win.txt");
+        fs::write(&file, b"\xEF\xBB\xBFhello\n").unwrap();
+        let paths = [file.to_string_lossy().to_string()];
+
+        let mut output = Vec::new();
+        xml_aggregator(true)
+            .aggregate_paths(&paths, &mut output)
+            .unwrap();
+        assert_eq!(output, b"<context>\n<file>\nhello\n</file>\n</context>\n");
+
+        let mut output = Vec::new();
+        xml_aggregator(true)
+            .with_keep_bom(true)
+            .aggregate_paths(&paths, &mut output)
+            .unwrap();
+        assert!(output.windows(8).any(|w| w == b"\xEF\xBB\xBFhello"));
+    }
+
+    #[test]
+    fn test_inline_filename_comments_start_each_file() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+        fs::write(dir.path().join("run.py"), "print()\n").unwrap();
+
+        let mut aggregator = xml_aggregator(true).with_inline_filename(true);
+        let mut output = Vec::new();
+        aggregator
+            .aggregate_paths(&[dir.path().to_string_lossy().to_string()], &mut output)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("<file>\n// main.rs\nfn main() {}\n</file>"));
+        assert!(output.contains("<file>\n# run.py\nprint()\n</file>"));
+    }
+
+    #[test]
+    fn test_separators_go_between_files_without_paths() {
+        let dir = tempdir().unwrap();
+        for name in ["a.rs", "b.rs", "c.rs"] {
+            fs::write(dir.path().join(name), name).unwrap();
         }
-        Ok(())
+
+        let mut aggregator = xml_aggregator(true).with_separator(Some("---".to_string()));
+        let mut output = Vec::new();
+        aggregator
+            .aggregate_paths(&[dir.path().to_string_lossy().to_string()], &mut output)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(
+            output,
+            "<context>\n<file>\na.rs\n</file>\n\n---\n\n<file>\nb.rs\n</file>\n\n---\n\n\
+             <file>\nc.rs\n</file>\n</context>\n"
+        );
+        assert!(!output.contains(&*dir.path().to_string_lossy()));
     }
 
-    fn is_hidden_file(&self, path: &Path) -> bool {
-        path.file_name()
-            .and_then(|name| name.to_str())
-            .map(|name| name.starts_with('.'))
-            .unwrap_or(false)
+    #[test]
+    fn test_compact_trims_whitespace_and_blank_runs() {
+        let text = "\n  \nfn main() {  \r\n\tlet x = 1;\t\n\n\n   \n    x\n}\n\n\n";
+        assert_eq!(
+            String::from_utf8(compact_content(text.as_bytes().to_vec())).unwrap(),
+            "fn main() {\r\n\tlet x = 1;\n\n    x\n}\n"
+        );
+        assert_eq!(compact_content(b"no newline  ".to_vec()), b"no newline");
+        assert_eq!(compact_content(b"\n\n".to_vec()), b"");
     }
 
-    pub fn file_count(&self) -> usize {
-        self.file_count
+    #[test]
+    fn test_wrap_breaks_at_spaces_and_keeps_indentation() {
+        let text = "short\n    alpha beta gamma delta\r\nabcdefghijkl\n";
+        let wrapped = wrap_content(Some(12), text.as_bytes().to_vec());
+        assert_eq!(
+            String::from_utf8(wrapped).unwrap(),
+            "short\n    alpha\n    beta\n    gamma\n    delta\r\nabcdefghijkl\n"
+        );
+        let wrapped = wrap_content(Some(10), b"abcdefghijklmnopqrstuvwxyz".to_vec());
+        assert_eq!(wrapped, b"abcdefghij\nklmnopqrst\nuvwxyz");
+        assert_eq!(wrap_content(None, b"unchanged".to_vec()), b"unchanged");
     }
 
-    pub fn token_count(&self) -> usize {
-        self.token_count
+    #[test]
+    fn test_mime_binary_detection() {
+        // gzip magic without any NUL byte, and valid UTF-8 containing one.
+        let gzip = [0x1f, 0x8b, 0x08, 0x08, 0x41, 0x42, 0x43];
+        let text_with_nul = b"key\x00value\n";
+        assert!(!BinaryDetection::Heuristic.is_binary(&gzip));
+        assert!(BinaryDetection::Mime.is_binary(&gzip));
+        assert!(BinaryDetection::Heuristic.is_binary(text_with_nul));
+        assert!(!BinaryDetection::Mime.is_binary(text_with_nul));
     }
 
-    pub fn skipped_binary_count(&self) -> usize {
-        self.skipped_binary
+    #[test]
+    fn test_dir_markers_precede_each_directory() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("utils")).unwrap();
+        fs::write(dir.path().join("a.rs"), "a").unwrap();
+        fs::write(dir.path().join("utils/b.rs"), "b").unwrap();
+
+        let mut aggregator = xml_aggregator(false).with_dir_markers(true);
+        let mut output = Vec::new();
+        aggregator
+            .aggregate_paths(&[dir.path().to_string_lossy().to_string()], &mut output)
+            .unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let sep = std::path::MAIN_SEPARATOR;
+        let root_marker = format!("=== Directory: {}{sep} ===\n<file", root.display());
+        let utils_marker = format!(
+            "=== Directory: {}{sep} ===\n<file",
+            root.join("utils").display()
+        );
+        let root_at = output.find(&root_marker).unwrap();
+        let utils_at = output.find(&utils_marker).unwrap();
+        assert!(root_at < utils_at);
+        assert_eq!(output.matches("=== Directory:").count(), 2);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::cli::PathHeader;
-    use crate::formatter::{build_formatter, FormatChoice};
-    use std::fs;
-    use tempfile::tempdir;
+    #[test]
+    fn test_dir_markers_skip_directories_without_included_files() {
+        let dir = tempdir().unwrap();
+        for sub in ["assets", "pkg", "vendor"] {
+            fs::create_dir(dir.path().join(sub)).unwrap();
+        }
+        fs::write(dir.path().join("a.rs"), "a").unwrap();
+        fs::write(dir.path().join("assets/logo.bin"), [0u8, 1, 2, 0]).unwrap();
+        fs::write(dir.path().join("pkg/__init__.py"), "").unwrap();
+        fs::write(dir.path().join("vendor/lib.rs"), "v").unwrap();
 
-    fn xml_aggregator(no_path: bool) -> ContentAggregator {
-        let header = if no_path {
-            PathHeader::None
-        } else {
-            PathHeader::Absolute
-        };
-        ContentAggregator::new(
-            build_formatter(FormatChoice::Xml, header),
+        let mut aggregator = ContentAggregator::new(
+            build_formatter(
+                FormatChoice::Xml,
+                PathHeader::Absolute,
+                FenceLanguages::default(),
+            ),
             false,
-            vec![],
+            vec!["vendor".to_string()],
             true,
             std::collections::HashSet::new(),
         )
+        .with_dir_markers(true)
+        .with_dedup_blank_files(true);
+        let mut output = Vec::new();
+        aggregator
+            .aggregate_paths(&[dir.path().to_string_lossy().to_string()], &mut output)
+            .unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches("=== Directory:").count(), 1);
+        for sub in ["assets", "pkg", "vendor"] {
+            assert!(!output.contains(&format!("{sub}{} ===", std::path::MAIN_SEPARATOR)));
+        }
     }
 
     #[test]
-    fn test_aggregate_single_file() {
+    fn test_max_entries_aborts_walk() {
         let dir = tempdir().unwrap();
-        let file_path = dir.path().join("test.txt");
-        fs::write(&file_path, "Hello, World!").unwrap();
+        for i in 0..5 {
+            fs::write(dir.path().join(format!("{i}.txt")), "x").unwrap();
+        }
+        let input = [dir.path().to_string_lossy().to_string()];
+        let mut aggregator = xml_aggregator(false).with_max_entries(3);
+        let err = aggregator
+            .aggregate_paths(&input, &mut Vec::new())
+            .unwrap_err();
+        assert!(err.to_string().contains("--max-entries"));
 
-        let mut aggregator = xml_aggregator(false);
-        let mut buffer = Vec::new();
+        let mut aggregator = xml_aggregator(false).with_max_entries(6);
+        aggregator.aggregate_paths(&input, &mut Vec::new()).unwrap();
+        assert_eq!(aggregator.file_count(), 5);
+    }
+
+    #[test]
+    fn test_drop_outliers() {
+        let dir = tempdir().unwrap();
+        for name in ["a.rs", "b.rs", "c.rs"] {
+            fs::write(dir.path().join(name), "x".repeat(100)).unwrap();
+        }
+        fs::write(dir.path().join("blob.json"), "y".repeat(600)).unwrap();
+        fs::write(dir.path().join("big.rs"), "z".repeat(400)).unwrap();
+
+        let mut aggregator = xml_aggregator(false).with_drop_outliers(true);
+        let mut output = Vec::new();
         aggregator
-            .aggregate_paths(&[file_path.to_str().unwrap().to_string()], &mut buffer)
+            .aggregate_paths(&[dir.path().to_string_lossy().to_string()], &mut output)
             .unwrap();
-        let content = String::from_utf8(buffer).unwrap();
 
-        assert!(content.contains("Hello, World!"));
-        assert!(content.contains("<file path="));
-        assert_eq!(aggregator.file_count(), 1);
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("big.rs"));
+        assert!(!output.contains("blob.json"));
+        let skipped = aggregator.skipped_files();
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].reason, SkipReason::Outlier);
+        assert_eq!(outlier_limit([0, 0, 10, 20, 30]), Some(100));
     }
 
     #[test]
-    fn test_aggregate_file_without_headers() {
+    fn test_modified_within_filters_old_files() {
         let dir = tempdir().unwrap();
-        let file_path = dir.path().join("test.txt");
-        fs::write(&file_path, "Hello, World!").unwrap();
+        let old = dir.path().join("old.txt");
+        fs::write(&old, "stale").unwrap();
+        fs::write(dir.path().join("new.txt"), "fresh").unwrap();
+        let week_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(7 * 86_400);
+        fs::File::options()
+            .write(true)
+            .open(&old)
+            .unwrap()
+            .set_modified(week_ago)
+            .unwrap();
 
-        let mut aggregator = xml_aggregator(true);
+        let mut aggregator = xml_aggregator(false)
+            .with_modified_within(Some(std::time::Duration::from_secs(86_400)));
+        let mut output = Vec::new();
+        aggregator
+            .aggregate_paths(&[dir.path().to_string_lossy().to_string()], &mut output)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("fresh"));
+        assert!(!output.contains("stale"));
+    }
+
+    #[test]
+    fn test_format_size_and_date() {
+        assert_eq!(format_size(512), "512B");
+        assert_eq!(format_size(1229), "1.2K");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0M");
+        let t = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_709_251_200);
+        assert_eq!(format_date(t), "2024-03-01");
+        let t = t + std::time::Duration::from_secs(13 * 3_600 + 5 * 60 + 9);
+        assert_eq!(format_timestamp(t), "2024-03-01 13:05:09 UTC");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_target_annotation() {
+        let dir = tempdir().unwrap();
+        let real = dir.path().join("real.txt");
+        let link = dir.path().join("link.txt");
+        fs::write(&real, "Real content").unwrap();
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        let mut aggregator = xml_aggregator(false).with_symlink_targets(true);
         let mut buffer = Vec::new();
         aggregator
-            .aggregate_paths(&[file_path.to_str().unwrap().to_string()], &mut buffer)
+            .aggregate_paths(&[link.to_str().unwrap().to_string()], &mut buffer)
             .unwrap();
         let content = String::from_utf8(buffer).unwrap();
 
-        assert!(content.contains("Hello, World!"));
-        assert!(content.contains("<file>"));
-        assert!(!content.contains("path="));
-        assert_eq!(aggregator.file_count(), 1);
+        assert!(content.contains("Real content"));
+        assert!(content.contains("link.txt\" target=\""));
+        assert!(content.contains("real.txt\">"));
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_aggregate_directory() {
+    fn test_symlink_cycle_terminates() {
         let dir = tempdir().unwrap();
-        let subdir = dir.path().join("subdir");
+        let subdir = dir.path().join("sub");
         fs::create_dir(&subdir).unwrap();
-
-        let file1 = dir.path().join("file1.txt");
-        let file2 = subdir.join("file2.txt");
-
-        fs::write(&file1, "File 1 content").unwrap();
-        fs::write(&file2, "File 2 content").unwrap();
+        fs::write(subdir.join("file.txt"), "Looped content").unwrap();
+        std::os::unix::fs::symlink(dir.path(), subdir.join("back")).unwrap();
 
         let mut aggregator = xml_aggregator(false);
         let mut buffer = Vec::new();
@@ -566,70 +3118,306 @@ mod tests {
             .unwrap();
         let content = String::from_utf8(buffer).unwrap();
 
-        assert!(content.contains("File 1 content"));
-        assert!(content.contains("File 2 content"));
-        assert_eq!(aggregator.file_count(), 2);
+        assert_eq!(content.matches("Looped content").count(), 1);
+        assert_eq!(aggregator.file_count(), 1);
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_aggregate_nonexistent_path() {
-        let mut aggregator = xml_aggregator(false);
-        let mut buffer = Vec::new();
-        let result = aggregator.aggregate_paths(&["nonexistent_file.txt".to_string()], &mut buffer);
+    fn test_resolve_ignores_follows_symlinks() {
+        let dir = tempdir().unwrap();
+        let real = dir.path().join("real");
+        fs::create_dir(&real).unwrap();
+        fs::write(real.join("secret.txt"), "Secret content").unwrap();
+        fs::write(dir.path().join("keep.txt"), "Kept content").unwrap();
+        std::os::unix::fs::symlink(&real, dir.path().join("link")).unwrap();
+        let ignore = vec![real.to_str().unwrap().to_string()];
+        let run = |resolve: bool| {
+            let mut aggregator = ContentAggregator::new(
+                build_formatter(
+                    FormatChoice::Xml,
+                    PathHeader::None,
+                    FenceLanguages::default(),
+                ),
+                false,
+                ignore.clone(),
+                true,
+                std::collections::HashSet::new(),
+            )
+            .with_resolve_ignores(resolve);
+            let mut buffer = Vec::new();
+            aggregator
+                .aggregate_paths(&[dir.path().to_str().unwrap().to_string()], &mut buffer)
+                .unwrap();
+            String::from_utf8(buffer).unwrap()
+        };
 
-        assert!(result.is_err());
-        assert!(result
-            .unwrap_err()
-            .to_string()
-            .contains("Path does not exist"));
+        assert!(run(false).contains("Secret content"));
+        let content = run(true);
+        assert!(!content.contains("Secret content"));
+        assert!(content.contains("Kept content"));
     }
 
     #[test]
-    fn test_skip_hidden_files_in_directory() {
+    fn test_ignore_patterns_apply_to_inputs_outside_the_cwd() {
+        // Tests run from the crate root, so a tempdir lies outside the cwd.
         let dir = tempdir().unwrap();
-        let visible_file = dir.path().join("visible.txt");
-        let hidden_file = dir.path().join(".hidden.txt");
+        let log = dir.path().join("run.log");
+        fs::write(&log, "log line").unwrap();
+        assert!(!log.starts_with(std::env::current_dir().unwrap()));
+        assert!(matches_patterns(&["*.log".to_string()], &log, false));
 
-        fs::write(&visible_file, "Visible content").unwrap();
-        fs::write(&hidden_file, "Hidden content").unwrap();
+        let mut aggregator = ContentAggregator::new(
+            build_formatter(
+                FormatChoice::Xml,
+                PathHeader::None,
+                FenceLanguages::default(),
+            ),
+            false,
+            vec!["*.log".to_string()],
+            true,
+            std::collections::HashSet::new(),
+        );
+        let mut output = Vec::new();
+        aggregator
+            .aggregate_paths(&[log.to_string_lossy().to_string()], &mut output)
+            .unwrap();
+        assert_eq!(aggregator.file_count(), 0);
+    }
 
-        let mut aggregator = xml_aggregator(false);
-        let mut buffer = Vec::new();
+    #[test]
+    fn test_ignore_output_dir_skips_earlier_dumps() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        fs::create_dir(root.join("dumps")).unwrap();
+        fs::write(root.join("main.rs"), "fn main() {}\n").unwrap();
+        fs::write(root.join("dumps").join("old.txt"), "Old dump").unwrap();
+        let run = |enabled: bool| {
+            let mut aggregator = xml_aggregator(false)
+                .with_output_files(vec![root.join("dumps").join("new.txt")])
+                .with_ignore_output_dir(enabled);
+            let mut buffer = Vec::new();
+            aggregator
+                .aggregate_paths(&[root.to_str().unwrap().to_string()], &mut buffer)
+                .unwrap();
+            String::from_utf8(buffer).unwrap()
+        };
+
+        assert!(run(false).contains("Old dump"));
+        let content = run(true);
+        assert!(!content.contains("Old dump"));
+        assert!(content.contains("fn main()"));
+    }
+
+    #[test]
+    fn test_max_lines_caps_only_long_files() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("long.txt"), "1\n2\n3\n4").unwrap();
+        fs::write(dir.path().join("short.txt"), "a\nb\n").unwrap();
+        let mut aggregator = xml_aggregator(true).with_max_lines(Some(2));
+        let mut output = Vec::new();
         aggregator
-            .aggregate_paths(&[dir.path().to_str().unwrap().to_string()], &mut buffer)
+            .aggregate_paths(&[dir.path().to_string_lossy().to_string()], &mut output)
             .unwrap();
-        let content = String::from_utf8(buffer).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "<context>\n<file>\n1\n2\n... (4 lines total, truncated) ...\n</file>\n\n\
+             <file>\na\nb\n</file>\n</context>\n"
+        );
+    }
 
-        assert!(content.contains("Visible content"));
-        assert!(!content.contains("Hidden content"));
-        assert_eq!(aggregator.file_count(), 1);
+    #[test]
+    fn test_sample_lines_thins_only_files_over_the_threshold() {
+        let dir = tempdir().unwrap();
+        let log: String = (1..=7).map(|i| format!("{i}\n")).collect();
+        fs::write(dir.path().join("a.log"), &log).unwrap();
+        fs::write(dir.path().join("b.log"), "x\ny\n").unwrap();
+        let mut aggregator = xml_aggregator(true).with_sample_lines(Some(3), 4);
+        let mut output = Vec::new();
+        aggregator
+            .aggregate_paths(&[dir.path().to_string_lossy().to_string()], &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "<context>\n<file>\n... (sampled: 1 line in 3 of 7) ...\n1\n4\n7\n</file>\n\n\
+             <file>\nx\ny\n</file>\n</context>\n"
+        );
     }
 
     #[test]
-    fn test_include_hidden_files_in_directory_with_flag() {
+    fn test_streamed_sample_reads_once_and_marks_the_end() {
         let dir = tempdir().unwrap();
-        let visible_file = dir.path().join("visible.txt");
-        let hidden_file = dir.path().join(".hidden.txt");
+        let path = dir.path().join("a.log");
+        let log: String = (1..=7).map(|i| format!("{i}\n")).collect();
+        fs::write(&path, &log).unwrap();
+        let stream = |above: usize| {
+            let mut aggregator = xml_aggregator(true).with_sample_lines(Some(3), above);
+            let mut file = fs::File::open(&path).unwrap();
+            let mut out = Vec::new();
+            aggregator
+                .stream_large_file(&mut file, &path, log.len() as u64, &mut out)
+                .unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        assert_eq!(stream(4), "1\n4\n7\n... (sampled: 1 line in 3 of 7) ...\n");
+        assert_eq!(stream(7), log);
+    }
 
-        fs::write(&visible_file, "Visible content").unwrap();
-        fs::write(&hidden_file, "Hidden content").unwrap();
+    #[test]
+    fn test_plan_sizes_cover_files_and_directories() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), "abc").unwrap();
+        fs::write(dir.path().join("b.txt"), "abcdefgh").unwrap();
+        let single = dir.path().join("a.txt").to_string_lossy().to_string();
+        let mut aggregator = xml_aggregator(true);
+        let plan = aggregator
+            .plan_inputs(&[single, dir.path().to_string_lossy().to_string()])
+            .unwrap();
+        assert_eq!(aggregator.plan_sizes(&plan), [3, 3, 8]);
+    }
+
+    #[test]
+    fn test_large_file_truncated_at_stream_cap() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("huge.log");
+        fs::write(&file_path, "line\n".repeat(2 * 1024 * 1024)).unwrap(); // 10 MB
+        let mut aggregator = xml_aggregator(true);
+        aggregator.max_stream_bytes = 8;
+        let mut output = Vec::new();
+        aggregator
+            .aggregate_paths(&[file_path.to_string_lossy().to_string()], &mut output)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "<context>\n<file>\nline\nlin\n[truncated: 10485752 of 10485760 bytes omitted]\n</file>\n</context>\n"
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_virtual_file_gets_every_transform() {
+        let mut aggregator = xml_aggregator(true)
+            .with_content_filter(Some("tr a-z A-Z".to_string()))
+            .with_compact(true)
+            .with_virtual_file("stdin", b"abc  \n\n\n".to_vec());
+        let mut output = Vec::new();
+        aggregator.aggregate_paths(&[], &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "<context>\n<file>\nABC\n</file>\n</context>\n"
+        );
+        assert_eq!(aggregator.compact_saved(), 4);
+    }
+
+    #[test]
+    fn test_streamed_files_are_compacted() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("a.log");
+        let log = "\n\none  \n\n\n\ttwo\t\n  \nthree\n\n\n";
+        fs::write(&path, log).unwrap();
+        let mut aggregator = xml_aggregator(true).with_compact(true);
+        let mut file = fs::File::open(&path).unwrap();
+        let mut out = Vec::new();
+        aggregator
+            .stream_large_file(&mut file, &path, log.len() as u64, &mut out)
+            .unwrap();
+        let compacted = "one\n\n\ttwo\n\nthree\n";
+        assert_eq!(String::from_utf8(out).unwrap(), compacted);
+        assert_eq!(
+            compact_content(log.as_bytes().to_vec()),
+            compacted.as_bytes()
+        );
+        assert_eq!(
+            aggregator.compact_saved(),
+            (log.len() - compacted.len()) as u64
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_content_filter_applies_to_streamed_files() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("huge.log");
+        let content = "secret line\n".repeat(512 * 1024); // 6 MB, streamed
+        assert!(content.len() as u64 > MAX_EXACT_BYTES);
+        fs::write(&file_path, &content).unwrap();
+        let mut aggregator =
+            xml_aggregator(true).with_content_filter(Some("tr a-z A-Z".to_string()));
+        let mut output = Vec::new();
+        aggregator
+            .aggregate_paths(&[file_path.to_string_lossy().to_string()], &mut output)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(!output.contains("secret"));
+        assert_eq!(
+            output,
+            format!(
+                "<context>\n<file>\n{}</file>\n</context>\n",
+                content.to_uppercase()
+            )
+        );
+    }
+
+    #[test]
+    fn test_force_include_overrides_exclusions() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join(".env.example"), "KEY=value").unwrap();
+        fs::write(dir.path().join("Cargo.lock"), "# lock").unwrap();
+        fs::write(dir.path().join("other.lock"), "# other").unwrap();
+        fs::write(dir.path().join("main.rs"), "fn main() {}").unwrap();
+        fs::create_dir(dir.path().join(".git")).unwrap();
+        fs::write(dir.path().join(".git/Cargo.lock"), "# git internals").unwrap();
 
         let mut aggregator = ContentAggregator::new(
-            build_formatter(FormatChoice::Xml, PathHeader::Absolute),
-            true,
-            vec![],
+            build_formatter(
+                FormatChoice::Xml,
+                PathHeader::Absolute,
+                FenceLanguages::default(),
+            ),
+            false,
+            vec!["*.lock".to_string()],
             true,
-            std::collections::HashSet::new(),
-        );
-        let mut buffer = Vec::new();
+            ["rs".to_string()].into_iter().collect(),
+        )
+        .with_force_include(vec![".env.example".to_string(), "Cargo.lock".to_string()]);
+        let mut output = Vec::new();
         aggregator
-            .aggregate_paths(&[dir.path().to_str().unwrap().to_string()], &mut buffer)
+            .aggregate_paths(&[dir.path().to_string_lossy().to_string()], &mut output)
             .unwrap();
-        let content = String::from_utf8(buffer).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("KEY=value"));
+        assert!(output.contains("# lock"));
+        assert!(output.contains("fn main() {}"));
+        assert!(!output.contains("# other"));
+        assert!(!output.contains("# git internals"));
+        assert_eq!(aggregator.file_count(), 3);
+    }
 
-        assert!(content.contains("Visible content"));
-        assert!(content.contains("Hidden content"));
-        assert_eq!(aggregator.file_count(), 2);
+    #[test]
+    fn test_force_include_walk_respects_max_entries() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("node_modules")).unwrap();
+        for i in 0..10 {
+            fs::write(dir.path().join(format!("node_modules/{i}.js")), "x").unwrap();
+        }
+        fs::write(dir.path().join("main.rs"), "fn main() {}").unwrap();
+        let mut aggregator = ContentAggregator::new(
+            build_formatter(
+                FormatChoice::Xml,
+                PathHeader::None,
+                FenceLanguages::default(),
+            ),
+            false,
+            vec!["node_modules/".to_string()],
+            true,
+            std::collections::HashSet::new(),
+        )
+        .with_force_include(vec!["*.lock".to_string()])
+        .with_max_entries(5);
+        let err = aggregator
+            .aggregate_paths(&[dir.path().to_string_lossy().to_string()], &mut Vec::new())
+            .unwrap_err();
+        assert!(err.to_string().contains("--max-entries"), "{err}");
     }
 
     #[test]
@@ -0,0 +1,99 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Lines added and removed in one file.
+pub type Change = (u64, u64);
+
+fn git(args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .context("Failed to run git (is it installed?)")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Files changed between `base` and the working tree, deleted ones left out
+/// (`--since-ref`). Paths are relative to the current directory.
+pub fn changed_files(base: &str) -> Result<Vec<String>> {
+    let text = git(&["diff", "--name-only", "--relative", "--diff-filter=d", base])?;
+    Ok(text
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// Per-file `(added, removed)` line counts between `base` and the working
+/// tree, keyed by absolute path (`--with-diffstat`).
+pub fn numstat(base: &str) -> Result<HashMap<PathBuf, Change>> {
+    let root = git(&["rev-parse", "--show-toplevel"])?;
+    let text = git(&["diff", "--numstat", base])?;
+    Ok(parse_numstat(&text, Path::new(root.trim())))
+}
+
+/// The unified diff between `base` and the working tree, limited to `paths`
+/// (`--with-diff`).
+pub fn patch(base: &str, paths: &[String]) -> Result<String> {
+    let mut args = vec!["diff", base, "--"];
+    args.extend(paths.iter().map(String::as_str));
+    git(&args)
+}
+
+/// `git diff --numstat` lines are `added<TAB>removed<TAB>path`, with `-` for
+/// the counts of binary files (which are left out). Renames show up as
+/// `old => new` or `dir/{old => new}`; the new name is kept.
+fn parse_numstat(text: &str, root: &Path) -> HashMap<PathBuf, Change> {
+    text.lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let added = fields.next()?.parse().ok()?;
+            let removed = fields.next()?.parse().ok()?;
+            let path = renamed_to(fields.next()?);
+            Some((root.join(path), (added, removed)))
+        })
+        .collect()
+}
+
+fn renamed_to(path: &str) -> String {
+    let Some((before, after)) = path.split_once(" => ") else {
+        return path.to_string();
+    };
+    match (before.rsplit_once('{'), after.split_once('}')) {
+        (Some((prefix, _)), Some((new, suffix))) => {
+            format!("{prefix}{new}{suffix}").replace("//", "/")
+        }
+        _ => after.to_string(),
+    }
+}
+
+/// `+12 -3`, as shown in headers.
+pub fn describe((added, removed): Change) -> String {
+    format!("+{added} -{removed}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_counts_renames_and_skips_binaries() {
+        let text = "12\t3\tsrc/main.rs\n-\t-\tlogo.png\n1\t0\tsrc/{old => new}/lib.rs\n\
+                    0\t2\ta.txt => b.txt\n4\t4\tsrc/{ => sub}/x.rs\n";
+        let stats = parse_numstat(text, Path::new("/repo"));
+        assert_eq!(stats.len(), 4);
+        assert_eq!(stats[Path::new("/repo/src/main.rs")], (12, 3));
+        assert_eq!(stats[Path::new("/repo/src/new/lib.rs")], (1, 0));
+        assert_eq!(stats[Path::new("/repo/b.txt")], (0, 2));
+        assert_eq!(stats[Path::new("/repo/src/sub/x.rs")], (4, 4));
+        assert_eq!(describe((12, 3)), "+12 -3");
+    }
+}
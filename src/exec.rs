@@ -0,0 +1,248 @@
+use anyhow::Result;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::thread::JoinHandle;
+
+/// Run `template` through the shell for the given files, like `find -exec`:
+/// once per file with `{}` replaced by its quoted path, or once for all files
+/// when the template ends in `{} +`. A template without `{}` gets the path
+/// appended. Returns how many invocations exited unsuccessfully.
+pub fn run(template: &str, files: &[PathBuf]) -> Result<usize> {
+    let mut failed = 0;
+    for command in build_commands(template, files) {
+        if !shell(&command).status()?.success() {
+            failed += 1;
+        }
+    }
+    Ok(failed)
+}
+
+/// Pipe `content` through `template` with `{}` replaced by the quoted path
+/// (`--content-filter`) and return what it prints. Errors if the command
+/// can't be started or exits unsuccessfully.
+pub fn filter(template: &str, path: &Path, content: &[u8]) -> Result<Vec<u8>> {
+    let mut stream = filter_stream(template, path, std::io::Cursor::new(content.to_vec()))?;
+    let mut output = Vec::new();
+    stream.read_to_end(&mut output)?;
+    stream.finish()?;
+    Ok(output)
+}
+
+/// `filter` for content too large to hold in memory: `input` is fed to the
+/// command while the caller reads its output from the returned stream, then
+/// calls `FilterStream::finish` to learn whether it succeeded.
+pub fn filter_stream(
+    template: &str,
+    path: &Path,
+    mut input: impl Read + Send + 'static,
+) -> Result<FilterStream> {
+    let command = template.replace("{}", &shell_quote(&path.to_string_lossy()));
+    let mut child = shell(&command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // Feed stdin (and drain stderr) from other threads so a command that
+    // answers before reading all of its input can't deadlock against us.
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let feeder = std::thread::spawn(move || {
+        let _ = std::io::copy(&mut input, &mut stdin);
+    });
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    let errors = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = stderr.read_to_end(&mut buf);
+        buf
+    });
+    let stdout = child.stdout.take().expect("stdout is piped");
+    Ok(FilterStream {
+        child,
+        stdout,
+        feeder,
+        errors,
+    })
+}
+
+/// A running `--content-filter` command; reading it yields the command's output.
+pub struct FilterStream {
+    child: Child,
+    stdout: ChildStdout,
+    feeder: JoinHandle<()>,
+    errors: JoinHandle<Vec<u8>>,
+}
+
+impl Read for FilterStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.stdout.read(buf)
+    }
+}
+
+impl FilterStream {
+    /// Wait for the command, discarding output left unread. Errors if it
+    /// exited unsuccessfully.
+    pub fn finish(mut self) -> Result<()> {
+        std::io::copy(&mut self.stdout, &mut std::io::sink())?;
+        let status = self.child.wait()?;
+        let _ = self.feeder.join();
+        let stderr = self.errors.join().unwrap_or_default();
+        if !status.success() {
+            let stderr = String::from_utf8_lossy(&stderr);
+            anyhow::bail!(
+                "{status} ({})",
+                stderr.lines().next().unwrap_or("no output").trim()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Run `template` for one file (`{}` or an appended path, as in `run`) and
+/// return what it prints, for `--preview-command`. Errors if the command
+/// can't be started or exits unsuccessfully.
+pub fn capture(template: &str, path: &Path) -> Result<Vec<u8>> {
+    let mut output = Vec::new();
+    for command in build_commands(template, &[path.to_path_buf()]) {
+        let out = shell(&command).stdin(Stdio::null()).output()?;
+        if !out.status.success() {
+            anyhow::bail!("{}", out.status);
+        }
+        output.extend(out.stdout);
+    }
+    Ok(output)
+}
+
+fn build_commands(template: &str, files: &[PathBuf]) -> Vec<String> {
+    let quoted: Vec<String> = files
+        .iter()
+        .map(|f| shell_quote(&f.to_string_lossy()))
+        .collect();
+    if let Some(prefix) = template.trim_end().strip_suffix("{} +") {
+        if quoted.is_empty() {
+            return Vec::new();
+        }
+        return vec![format!("{prefix}{}", quoted.join(" "))];
+    }
+    quoted
+        .iter()
+        .map(|path| {
+            if template.contains("{}") {
+                template.replace("{}", path)
+            } else {
+                format!("{template} {path}")
+            }
+        })
+        .collect()
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.args(["/C", command]);
+    cmd
+}
+
+#[cfg(not(windows))]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", command]);
+    cmd
+}
+
+/// Quote `s` as one shell word; plain paths are left as they are.
+fn shell_quote(s: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "/._-+:@%,".contains(c);
+    if !s.is_empty() && s.chars().all(plain) {
+        s.to_string()
+    } else if cfg!(windows) {
+        format!("\"{s}\"")
+    } else {
+        format!("'{}'", s.replace('\'', r"'\''"))
+    }
+}
+
+/// `argv` as one line that can be pasted back into a shell (`--embed-command`).
+/// Control characters are escaped so the line can't be split or restyled.
+pub fn command_line(argv: &[String]) -> String {
+    argv.iter()
+        .map(|arg| {
+            let arg: String = arg
+                .chars()
+                .map(|c| {
+                    if c.is_control() {
+                        c.escape_default().to_string()
+                    } else {
+                        c.to_string()
+                    }
+                })
+                .collect();
+            match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") && !flag.contains(' ') => {
+                    format!("{flag}={}", shell_quote(value))
+                }
+                _ => shell_quote(&arg),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(windows))]
+    #[test]
+    fn builds_per_file_and_batch_commands() {
+        let files = [PathBuf::from("/src/a.rs"), PathBuf::from("/src/it's b.rs")];
+        assert_eq!(
+            build_commands("wc -l {}", &files),
+            ["wc -l /src/a.rs", r"wc -l '/src/it'\''s b.rs'"]
+        );
+        assert_eq!(
+            build_commands("wc -l {} +", &files),
+            [r"wc -l /src/a.rs '/src/it'\''s b.rs'"]
+        );
+        assert_eq!(build_commands("cat", &files[..1]), ["cat /src/a.rs"]);
+        assert!(build_commands("wc {} +", &[]).is_empty());
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn command_line_quotes_and_escapes_arguments() {
+        let argv = [
+            "cxt",
+            "--relative",
+            "--format=xml",
+            "--ignore=*.o",
+            "my dir",
+            "a\nb",
+        ];
+        let argv: Vec<String> = argv.iter().map(|s| s.to_string()).collect();
+        assert_eq!(
+            command_line(&argv),
+            r"cxt --relative --format=xml --ignore='*.o' 'my dir' 'a\nb'"
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn filter_pipes_content_and_reports_failure() {
+        let path = Path::new("/src/a.rs");
+        assert_eq!(filter("tr a-z A-Z", path, b"abc").unwrap(), b"ABC");
+        assert_eq!(filter("echo {}", path, b"").unwrap(), b"/src/a.rs\n");
+        assert!(filter("exit 3", path, b"abc").is_err());
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn capture_returns_stdout_of_one_invocation() {
+        let path = Path::new("/src/a b.rs");
+        assert_eq!(capture("echo", path).unwrap(), b"/src/a b.rs\n");
+        assert_eq!(
+            capture("echo {} {}", path).unwrap(),
+            b"/src/a b.rs /src/a b.rs\n"
+        );
+        assert!(capture("false", path).is_err());
+    }
+}
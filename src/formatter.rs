@@ -1,6 +1,7 @@
+use std::collections::HashMap;
 use std::path::Path;
 
-use crate::cli::PathHeader;
+use crate::cli::{PathHeader, RelativeFallback};
 
 pub trait Formatter: Send + Sync {
     fn document_start(&self) -> &'static str {
@@ -11,32 +12,330 @@ pub trait Formatter: Send + Sync {
     }
     fn write_file_header(
         &self,
-        path: &Path,
+        header: &FileHeader,
         writer: &mut dyn std::io::Write,
     ) -> std::io::Result<()>;
+    /// Written after the file content, which always ends in a newline by then.
     fn file_footer(&self) -> &'static str;
+    /// True for formats that describe files instead of including them; the
+    /// aggregator then drops file bodies and fills in `FileHeader::stats`.
+    fn metadata_only(&self) -> bool {
+        false
+    }
+    /// Start of the header line this format writes for `path`, used to spot
+    /// files already present in an output being appended to. None when
+    /// headers don't name the file. Markers are unnumbered; compare them with
+    /// existing lines through `without_toc_index`.
+    fn header_marker(&self, _path: &Path) -> Option<String> {
+        None
+    }
+    /// How every `header_marker` of this format starts, to pick header lines
+    /// out of existing output.
+    fn header_lead(&self) -> Option<&'static str> {
+        None
+    }
+    /// `--dir-markers` line for output entering `dir`, with the path shown the
+    /// way headers show paths. None when headers show none.
+    fn dir_marker(&self, _dir: &Path) -> Option<String> {
+        None
+    }
+    /// Path as this format's headers show it, for the `--toc` index.
+    fn display_path(&self, path: &Path) -> String {
+        path.display().to_string()
+    }
+    /// The `--toc` index of `(path, lines)` entries, numbered from 1.
+    fn write_toc(
+        &self,
+        _entries: &[(String, usize)],
+        _writer: &mut dyn std::io::Write,
+    ) -> std::io::Result<()> {
+        Ok(())
+    }
+    /// True when the whole output goes inside one fenced code block; the
+    /// aggregator picks the fence once the content is known.
+    fn single_block(&self) -> bool {
+        false
+    }
+}
+
+/// A backtick fence longer than any backtick run in `parts` (at least three),
+/// so nothing inside can close it early.
+pub fn block_fence(parts: &[&[u8]]) -> String {
+    let longest = parts
+        .iter()
+        .flat_map(|part| part.split(|&b| b != b'`'))
+        .map(<[u8]>::len)
+        .max()
+        .unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+/// `line` with a `--toc` header number dropped ("## File 3: a.rs" becomes
+/// "## File: a.rs"), so it lines up with `Formatter::header_marker`.
+pub fn without_toc_index(line: &str) -> std::borrow::Cow<'_, str> {
+    for lead in ["## File ", "--- File "] {
+        let Some(rest) = line.strip_prefix(lead) else {
+            continue;
+        };
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if digits > 0 && rest[digits..].starts_with(':') {
+            return format!("{}{}", lead.trim_end(), &rest[digits..]).into();
+        }
+    }
+    line.into()
+}
+
+fn toc_lines(entries: &[(String, usize)]) -> String {
+    entries
+        .iter()
+        .enumerate()
+        .map(|(i, (path, lines))| {
+            let unit = if *lines == 1 { "line" } else { "lines" };
+            format!("{}. {path} ({lines} {unit})\n", i + 1)
+        })
+        .collect()
+}
+
+/// Everything a formatter needs to render one file's header.
+pub struct FileHeader<'a> {
+    pub path: &'a Path,
+    /// Resolved real path when `path` is a symlink and `--symlink-targets` is on.
+    pub target: Option<&'a Path>,
+    /// `--header-meta` summary, e.g. "1.2K, 48 lines, modified 2024-03-01".
+    pub meta: Option<&'a str>,
+    /// Position in the `--toc` index.
+    pub index: Option<usize>,
+    /// Size, line count and summary, filled in only for `metadata_only` formatters.
+    pub stats: Option<FileStats>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct FileStats {
+    pub size: u64,
+    pub lines: usize,
+    /// One-line description from `summary::summarize`, if one was found.
+    pub summary: Option<String>,
+}
+
+/// `file://` URI for an absolute path, percent-encoding everything outside
+/// the unreserved set (and `/`), as `text/uri-list` consumers expect.
+pub fn file_uri(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-._~:".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    uri
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
 pub enum FormatChoice {
     Xml,
     Markdown,
+    /// One `path,size,lines,extension` row per file, without content.
+    Csv,
+    /// One `path: summary` line per file, without content.
+    Summary,
+    /// Everything in one markdown code block, files split by `--- File: path ---` lines.
+    SingleBlock,
 }
 
-fn resolve_display(path: &Path, header: PathHeader, cwd: Option<&Path>) -> String {
-    match header {
-        PathHeader::Relative => {
-            if let Some(cwd) = cwd {
-                if let Some(rel) = pathdiff::diff_paths(path, cwd) {
-                    return rel.display().to_string();
-                }
-            }
-            path.display().to_string()
+impl FormatChoice {
+    /// Clipboard MIME hint for backends that accept one; `None` means plain text.
+    pub fn mime_type(self) -> Option<&'static str> {
+        match self {
+            FormatChoice::Xml => None,
+            FormatChoice::Markdown => Some("text/markdown"),
+            FormatChoice::Csv => Some("text/csv"),
+            FormatChoice::Summary => None,
+            FormatChoice::SingleBlock => Some("text/markdown"),
+        }
+    }
+}
+
+/// How file paths are rendered in headers.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PathStyle {
+    pub header: PathHeader,
+    /// Use `/` as the separator regardless of platform.
+    pub posix: bool,
+    /// Prefix relative paths with `./` (paths starting with `..` are left alone).
+    pub dot_slash: bool,
+    /// Relative style only: what to show for a file outside the current directory.
+    pub relative_fallback: RelativeFallback,
+    /// Show only the file name; repeated names get a ` (2)`, ` (3)`... suffix.
+    pub flat: bool,
+    /// Markdown only: put the path on the opening fence line instead of a heading.
+    pub inline: bool,
+}
+
+impl From<PathHeader> for PathStyle {
+    fn from(header: PathHeader) -> Self {
+        Self {
+            header,
+            posix: false,
+            dot_slash: false,
+            relative_fallback: RelativeFallback::default(),
+            flat: false,
+            inline: false,
+        }
+    }
+}
+
+/// Renders header paths for one run; `--flat-headers` needs to remember which
+/// file names it has already emitted.
+struct PathResolver {
+    style: PathStyle,
+    cwd: Option<std::path::PathBuf>,
+    flat_names: std::sync::Mutex<FlatNames>,
+}
+
+/// `--flat-headers` bookkeeping: how often each name was used, and the label
+/// each path got so the `--toc` index and the header agree.
+#[derive(Default)]
+struct FlatNames {
+    counts: HashMap<String, usize>,
+    labels: HashMap<std::path::PathBuf, String>,
+}
+
+impl PathResolver {
+    fn new(style: PathStyle) -> Self {
+        let cwd = if style.header == PathHeader::Relative {
+            std::env::current_dir().ok()
+        } else {
+            None
+        };
+        Self {
+            style,
+            cwd,
+            flat_names: Default::default(),
+        }
+    }
+
+    /// Header path for a file's own header; asking again for the same path
+    /// gives the same label.
+    fn file(&self, path: &Path) -> String {
+        if !self.style.flat {
+            return resolve_display(path, self.style, self.cwd.as_deref());
+        }
+        let mut names = self.flat_names.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(label) = names.labels.get(path) {
+            return label.clone();
+        }
+        let name = base_name(path);
+        let count = names.counts.entry(name.clone()).or_insert(0);
+        *count += 1;
+        let label = if *count == 1 {
+            name
+        } else {
+            format!("{name} ({count})")
+        };
+        names.labels.insert(path.to_path_buf(), label.clone());
+        label
+    }
+
+    /// `Formatter::dir_marker` for `dir`, e.g. "=== Directory: src/utils/ ===".
+    fn dir_marker(&self, dir: &Path) -> Option<String> {
+        if self.style.header == PathHeader::None {
+            return None;
+        }
+        let separator = if self.style.posix {
+            '/'
+        } else {
+            std::path::MAIN_SEPARATOR
+        };
+        let shown = self.target(dir);
+        let shown = shown.trim_end_matches(['/', std::path::MAIN_SEPARATOR]);
+        let shown = if shown.is_empty() { "." } else { shown };
+        Some(format!("=== Directory: {shown}{separator} ==="))
+    }
+
+    /// Path for a symlink target or a header lookup; never counted as a name
+    /// collision.
+    fn target(&self, path: &Path) -> String {
+        if self.style.flat {
+            base_name(path)
+        } else {
+            resolve_display(path, self.style, self.cwd.as_deref())
         }
+    }
+}
+
+fn base_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+fn resolve_display(path: &Path, style: PathStyle, cwd: Option<&Path>) -> String {
+    let display = match style.header {
+        PathHeader::Relative => cwd
+            .and_then(|cwd| pathdiff::diff_paths(path, cwd))
+            // A `..` chain (or, on Windows, an absolute path on another drive)
+            // is clearer as the absolute path unless dotdot was asked for.
+            .filter(|rel| {
+                style.relative_fallback == RelativeFallback::Dotdot
+                    || !(rel.starts_with("..") || rel.is_absolute())
+            })
+            .map(|rel| {
+                if style.dot_slash && !rel.starts_with("..") {
+                    format!(".{}{}", std::path::MAIN_SEPARATOR, rel.display())
+                } else {
+                    rel.display().to_string()
+                }
+            })
+            .unwrap_or_else(|| path.display().to_string()),
         _ => path.display().to_string(),
+    };
+    if style.posix && std::path::MAIN_SEPARATOR != '/' {
+        display.replace(std::path::MAIN_SEPARATOR, "/")
+    } else {
+        display
     }
 }
 
+/// Extension → fence language overrides from `--lang-map` and the config file,
+/// consulted before the built-in table.
+#[derive(Clone, Default, Debug)]
+pub struct FenceLanguages(HashMap<String, String>);
+
+impl FenceLanguages {
+    /// Add or replace a mapping; later calls win.
+    pub fn insert(&mut self, ext: String, lang: String) {
+        self.0.insert(ext, lang);
+    }
+
+    fn language_for(&self, path: &Path) -> &str {
+        path.extension()
+            .and_then(|e| self.0.get(&e.to_string_lossy().to_lowercase()))
+            .map_or_else(|| language_for_extension(path), String::as_str)
+    }
+}
+
+/// Parse an `EXT=LANG` mapping such as `.tsx=typescript` into a lowercase
+/// extension without its dot and the language name.
+pub fn parse_lang_mapping(s: &str) -> Result<(String, String), String> {
+    let (ext, lang) = s
+        .split_once('=')
+        .ok_or_else(|| format!("'{s}' is not EXT=LANG (e.g. .tsx=typescript)"))?;
+    let ext = ext.trim().trim_start_matches('.').to_lowercase();
+    let lang = lang.trim();
+    if ext.is_empty() || ext.contains(['.', '/', '\\']) {
+        return Err(format!("'{s}': invalid extension"));
+    }
+    if lang.is_empty() || lang.contains(|c: char| c.is_whitespace() || c == '`') {
+        return Err(format!("'{s}': language must be a single word"));
+    }
+    Ok((ext, lang.to_string()))
+}
+
 pub fn language_for_extension(path: &Path) -> &'static str {
     match path
         .extension()
@@ -88,18 +387,16 @@ pub fn language_for_extension(path: &Path) -> &'static str {
 }
 
 pub struct XmlFormatter {
-    header: PathHeader,
-    cwd: Option<std::path::PathBuf>,
+    style: PathStyle,
+    paths: PathResolver,
 }
 
 impl XmlFormatter {
-    pub fn new(header: PathHeader) -> Self {
-        let cwd = if header == PathHeader::Relative {
-            std::env::current_dir().ok()
-        } else {
-            None
-        };
-        Self { header, cwd }
+    pub fn new(style: PathStyle) -> Self {
+        Self {
+            style,
+            paths: PathResolver::new(style),
+        }
     }
 }
 
@@ -114,61 +411,529 @@ impl Formatter for XmlFormatter {
 
     fn write_file_header(
         &self,
-        path: &Path,
+        header: &FileHeader,
         writer: &mut dyn std::io::Write,
     ) -> std::io::Result<()> {
-        if self.header == PathHeader::None {
-            writer.write_all(b"<file>\n")
-        } else {
-            let resolved = resolve_display(path, self.header, self.cwd.as_deref());
-            writeln!(writer, "<file path=\"{resolved}\">")
+        let mut tag = String::from("<file");
+        if self.style.header != PathHeader::None {
+            let resolved = self.paths.file(header.path);
+            tag.push_str(&format!(" path=\"{resolved}\""));
+            if let Some(target) = header.target {
+                let target = self.paths.target(target);
+                tag.push_str(&format!(" target=\"{target}\""));
+            }
+        }
+        if let Some(index) = header.index {
+            tag.push_str(&format!(" index=\"{index}\""));
+        }
+        if let Some(meta) = header.meta {
+            tag.push_str(&format!(" meta=\"{meta}\""));
         }
+        writeln!(writer, "{tag}>")
     }
 
     fn file_footer(&self) -> &'static str {
-        "\n</file>\n"
+        "</file>\n"
+    }
+
+    fn header_marker(&self, path: &Path) -> Option<String> {
+        (self.style.header != PathHeader::None)
+            .then(|| format!("<file path=\"{}\"", self.paths.target(path)))
+    }
+
+    fn header_lead(&self) -> Option<&'static str> {
+        (self.style.header != PathHeader::None).then_some("<file path=\"")
+    }
+
+    fn display_path(&self, path: &Path) -> String {
+        self.paths.file(path)
+    }
+
+    fn dir_marker(&self, dir: &Path) -> Option<String> {
+        self.paths.dir_marker(dir)
+    }
+
+    fn write_toc(
+        &self,
+        entries: &[(String, usize)],
+        writer: &mut dyn std::io::Write,
+    ) -> std::io::Result<()> {
+        write!(writer, "<toc>\n{}</toc>\n\n", toc_lines(entries))
     }
 }
 
 pub struct MarkdownFormatter {
-    header: PathHeader,
-    cwd: Option<std::path::PathBuf>,
+    style: PathStyle,
+    paths: PathResolver,
+    languages: FenceLanguages,
 }
 
 impl MarkdownFormatter {
-    pub fn new(header: PathHeader) -> Self {
-        let cwd = if header == PathHeader::Relative {
-            std::env::current_dir().ok()
-        } else {
-            None
-        };
-        Self { header, cwd }
+    pub fn new(style: PathStyle, languages: FenceLanguages) -> Self {
+        Self {
+            style,
+            paths: PathResolver::new(style),
+            languages,
+        }
     }
 }
 
 impl Formatter for MarkdownFormatter {
     fn write_file_header(
         &self,
-        path: &Path,
+        header: &FileHeader,
         writer: &mut dyn std::io::Write,
     ) -> std::io::Result<()> {
-        let lang = language_for_extension(path);
-        if self.header == PathHeader::None {
-            writeln!(writer, "```{lang}")
+        let lang = self.languages.language_for(header.path);
+        if self.style.inline {
+            let mut info = lang.to_string();
+            if self.style.header != PathHeader::None {
+                info.push(' ');
+                info.push_str(&self.paths.file(header.path));
+                if let Some(target) = header.target {
+                    info.push_str(&format!(" -> {}", self.paths.target(target)));
+                }
+            }
+            if let Some(meta) = header.meta {
+                info.push_str(&format!(" ({meta})"));
+            }
+            return writeln!(writer, "```{info}");
+        }
+        if self.style.header != PathHeader::None {
+            let resolved = self.paths.file(header.path);
+            let label = match header.index {
+                Some(index) => format!("File {index}"),
+                None => "File".to_string(),
+            };
+            match header.target {
+                Some(target) => {
+                    let target = self.paths.target(target);
+                    write!(writer, "## {label}: {resolved} -> {target}\n\n")?;
+                }
+                None => write!(writer, "## {label}: {resolved}\n\n")?,
+            }
+        }
+        if let Some(meta) = header.meta {
+            write!(writer, "({meta})\n\n")?;
+        }
+        writeln!(writer, "```{lang}")
+    }
+
+    fn file_footer(&self) -> &'static str {
+        "```\n"
+    }
+
+    fn header_marker(&self, path: &Path) -> Option<String> {
+        if self.style.header == PathHeader::None {
+            return None;
+        }
+        let resolved = self.paths.target(path);
+        Some(if self.style.inline {
+            format!("```{} {resolved}", self.languages.language_for(path))
+        } else {
+            format!("## File: {resolved}")
+        })
+    }
+
+    fn header_lead(&self) -> Option<&'static str> {
+        if self.style.header == PathHeader::None {
+            None
+        } else if self.style.inline {
+            Some("```")
         } else {
-            let resolved = resolve_display(path, self.header, self.cwd.as_deref());
-            write!(writer, "## File: {resolved}\n\n```{lang}\n")
+            Some("## File: ")
+        }
+    }
+
+    fn display_path(&self, path: &Path) -> String {
+        self.paths.file(path)
+    }
+
+    fn dir_marker(&self, dir: &Path) -> Option<String> {
+        self.paths.dir_marker(dir)
+    }
+
+    fn write_toc(
+        &self,
+        entries: &[(String, usize)],
+        writer: &mut dyn std::io::Write,
+    ) -> std::io::Result<()> {
+        write!(writer, "## Contents\n\n{}\n", toc_lines(entries))
+    }
+}
+
+pub struct CsvFormatter {
+    paths: PathResolver,
+}
+
+impl CsvFormatter {
+    pub fn new(style: PathStyle) -> Self {
+        Self {
+            paths: PathResolver::new(style),
+        }
+    }
+}
+
+/// Quote a CSV field if it contains a comma, quote or line break (RFC 4180).
+fn csv_field(s: &str) -> std::borrow::Cow<'_, str> {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\"")).into()
+    } else {
+        s.into()
+    }
+}
+
+impl Formatter for CsvFormatter {
+    fn document_start(&self) -> &'static str {
+        "path,size,lines,extension\n"
+    }
+
+    /// The path column is always filled: without it the rows mean nothing.
+    fn write_file_header(
+        &self,
+        header: &FileHeader,
+        writer: &mut dyn std::io::Write,
+    ) -> std::io::Result<()> {
+        let path = self.paths.file(header.path);
+        let ext = header
+            .path
+            .extension()
+            .map(|e| e.to_string_lossy())
+            .unwrap_or_default();
+        let stats = header.stats.clone().unwrap_or_default();
+        writeln!(
+            writer,
+            "{},{},{},{}",
+            csv_field(&path),
+            stats.size,
+            stats.lines,
+            csv_field(&ext)
+        )
+    }
+
+    fn file_footer(&self) -> &'static str {
+        ""
+    }
+
+    fn metadata_only(&self) -> bool {
+        true
+    }
+}
+
+pub struct SummaryFormatter {
+    paths: PathResolver,
+}
+
+impl SummaryFormatter {
+    pub fn new(style: PathStyle) -> Self {
+        Self {
+            paths: PathResolver::new(style),
+        }
+    }
+}
+
+impl Formatter for SummaryFormatter {
+    /// `path: summary`, or the bare path when nothing describes the file.
+    fn write_file_header(
+        &self,
+        header: &FileHeader,
+        writer: &mut dyn std::io::Write,
+    ) -> std::io::Result<()> {
+        let path = self.paths.file(header.path);
+        match header.stats.as_ref().and_then(|s| s.summary.as_deref()) {
+            Some(summary) => writeln!(writer, "{path}: {summary}"),
+            None => writeln!(writer, "{path}"),
+        }
+    }
+
+    fn file_footer(&self) -> &'static str {
+        ""
+    }
+
+    fn metadata_only(&self) -> bool {
+        true
+    }
+}
+
+pub struct SingleBlockFormatter {
+    style: PathStyle,
+    paths: PathResolver,
+}
+
+impl SingleBlockFormatter {
+    pub fn new(style: PathStyle) -> Self {
+        Self {
+            style,
+            paths: PathResolver::new(style),
+        }
+    }
+}
+
+impl Formatter for SingleBlockFormatter {
+    fn write_file_header(
+        &self,
+        header: &FileHeader,
+        writer: &mut dyn std::io::Write,
+    ) -> std::io::Result<()> {
+        let mut line = String::from("---");
+        if self.style.header != PathHeader::None {
+            let resolved = self.paths.file(header.path);
+            match header.index {
+                Some(index) => line.push_str(&format!(" File {index}: {resolved}")),
+                None => line.push_str(&format!(" File: {resolved}")),
+            }
+            if let Some(target) = header.target {
+                line.push_str(&format!(" -> {}", self.paths.target(target)));
+            }
+        }
+        if let Some(meta) = header.meta {
+            line.push_str(&format!(" ({meta})"));
         }
+        if line.len() > 3 {
+            line.push_str(" ---");
+        }
+        writeln!(writer, "{line}")
     }
 
     fn file_footer(&self) -> &'static str {
-        "\n```\n\n"
+        ""
+    }
+
+    fn header_marker(&self, path: &Path) -> Option<String> {
+        (self.style.header != PathHeader::None)
+            .then(|| format!("--- File: {}", self.paths.target(path)))
+    }
+
+    fn header_lead(&self) -> Option<&'static str> {
+        (self.style.header != PathHeader::None).then_some("--- File: ")
+    }
+
+    fn display_path(&self, path: &Path) -> String {
+        self.paths.file(path)
+    }
+
+    fn dir_marker(&self, dir: &Path) -> Option<String> {
+        self.paths.dir_marker(dir)
+    }
+
+    fn write_toc(
+        &self,
+        entries: &[(String, usize)],
+        writer: &mut dyn std::io::Write,
+    ) -> std::io::Result<()> {
+        writeln!(writer, "{}", toc_lines(entries))
+    }
+
+    fn single_block(&self) -> bool {
+        true
     }
 }
 
-pub fn build_formatter(choice: FormatChoice, header: PathHeader) -> Box<dyn Formatter> {
+pub fn build_formatter(
+    choice: FormatChoice,
+    style: impl Into<PathStyle>,
+    languages: FenceLanguages,
+) -> Box<dyn Formatter> {
+    let style = style.into();
     match choice {
-        FormatChoice::Xml => Box::new(XmlFormatter::new(header)),
-        FormatChoice::Markdown => Box::new(MarkdownFormatter::new(header)),
+        FormatChoice::Xml => Box::new(XmlFormatter::new(style)),
+        FormatChoice::Markdown => Box::new(MarkdownFormatter::new(style, languages)),
+        FormatChoice::Csv => Box::new(CsvFormatter::new(style)),
+        FormatChoice::Summary => Box::new(SummaryFormatter::new(style)),
+        FormatChoice::SingleBlock => Box::new(SingleBlockFormatter::new(style)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flat_headers_disambiguate_repeated_names() {
+        let style = PathStyle {
+            flat: true,
+            ..PathStyle::from(PathHeader::Absolute)
+        };
+        let fmt = build_formatter(FormatChoice::Xml, style, FenceLanguages::default());
+        let mut out = Vec::new();
+        for path in ["/a/mod.rs", "/b/mod.rs", "/b/lib.rs"] {
+            let header = FileHeader {
+                path: Path::new(path),
+                target: None,
+                meta: None,
+                index: None,
+                stats: None,
+            };
+            fmt.write_file_header(&header, &mut out).unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "<file path=\"mod.rs\">\n<file path=\"mod.rs (2)\">\n<file path=\"lib.rs\">\n"
+        );
+    }
+
+    #[test]
+    fn toc_and_headers_agree_under_flat_headers() {
+        let style = PathStyle {
+            flat: true,
+            ..PathStyle::from(PathHeader::Absolute)
+        };
+        let fmt = build_formatter(FormatChoice::Markdown, style, FenceLanguages::default());
+        let mut out = Vec::new();
+        for (index, path) in ["/a/mod.rs", "/b/mod.rs"].into_iter().enumerate() {
+            let path = Path::new(path);
+            let listed = fmt.display_path(path);
+            let header = FileHeader {
+                path,
+                target: None,
+                meta: None,
+                index: Some(index + 1),
+                stats: None,
+            };
+            fmt.write_file_header(&header, &mut out).unwrap();
+            assert!(String::from_utf8_lossy(&out).contains(&format!(": {listed}\n")));
+        }
+        assert_eq!(fmt.display_path(Path::new("/b/mod.rs")), "mod.rs (2)");
+    }
+
+    #[test]
+    fn dir_markers_follow_the_header_style() {
+        let marker = |style: PathStyle| {
+            build_formatter(FormatChoice::Markdown, style, FenceLanguages::default())
+                .dir_marker(Path::new("/work/src/utils"))
+        };
+        assert_eq!(marker(PathStyle::from(PathHeader::None)), None);
+        let posix = PathStyle {
+            posix: true,
+            ..PathStyle::from(PathHeader::Absolute)
+        };
+        assert_eq!(
+            marker(posix).as_deref(),
+            Some("=== Directory: /work/src/utils/ ===")
+        );
+        let flat = PathStyle {
+            flat: true,
+            posix: true,
+            ..PathStyle::from(PathHeader::Absolute)
+        };
+        assert_eq!(marker(flat).as_deref(), Some("=== Directory: utils/ ==="));
+    }
+
+    #[test]
+    fn numbered_headers_match_their_marker() {
+        assert_eq!(
+            without_toc_index("## File 12: src/a.rs"),
+            "## File: src/a.rs"
+        );
+        assert_eq!(
+            without_toc_index("--- File 3: a.rs ---"),
+            "--- File: a.rs ---"
+        );
+        assert_eq!(without_toc_index("## File: 12: odd"), "## File: 12: odd");
+        assert_eq!(without_toc_index("## File 1x: a"), "## File 1x: a");
+    }
+
+    #[test]
+    fn file_uris_percent_encode_reserved_bytes() {
+        assert_eq!(
+            file_uri(Path::new("/src/my file#1.rs")),
+            "file:///src/my%20file%231.rs"
+        );
+        assert_eq!(file_uri(Path::new("/caf\u{e9}")), "file:///caf%C3%A9");
+    }
+
+    #[test]
+    fn csv_rows_quote_paths_with_commas() {
+        let fmt = build_formatter(
+            FormatChoice::Csv,
+            PathHeader::Absolute,
+            FenceLanguages::default(),
+        );
+        let mut out = Vec::new();
+        for path in ["/src/main.rs", "/data/a,b \"x\".csv", "/Makefile"] {
+            let header = FileHeader {
+                path: Path::new(path),
+                target: None,
+                meta: None,
+                index: None,
+                stats: Some(FileStats {
+                    size: 12,
+                    lines: 3,
+                    summary: None,
+                }),
+            };
+            fmt.write_file_header(&header, &mut out).unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "/src/main.rs,12,3,rs\n\"/data/a,b \"\"x\"\".csv\",12,3,csv\n/Makefile,12,3,\n"
+        );
+    }
+
+    #[test]
+    fn lang_map_overrides_fence_language() {
+        assert_eq!(
+            parse_lang_mapping(".TSX=typescript"),
+            Ok(("tsx".to_string(), "typescript".to_string()))
+        );
+        assert!(parse_lang_mapping("tsx").is_err());
+        assert!(parse_lang_mapping("=rust").is_err());
+        assert!(parse_lang_mapping("tsx=type script").is_err());
+
+        let mut languages = FenceLanguages::default();
+        languages.insert("tsx".into(), "typescript".into());
+        languages.insert("tpl".into(), "jinja".into());
+        let fmt = build_formatter(FormatChoice::Markdown, PathHeader::None, languages);
+        let mut out = Vec::new();
+        for path in ["/a.tsx", "/b.tpl", "/c.rs"] {
+            let header = FileHeader {
+                path: Path::new(path),
+                target: None,
+                meta: None,
+                index: None,
+                stats: None,
+            };
+            fmt.write_file_header(&header, &mut out).unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "```typescript\n```jinja\n```rust\n"
+        );
+    }
+
+    #[test]
+    fn relative_headers_outside_cwd_follow_the_fallback() {
+        let cwd = Path::new("/work/proj");
+        let style = PathStyle::from(PathHeader::Relative);
+        let display = |path: &str, style| resolve_display(Path::new(path), style, Some(cwd));
+        assert_eq!(display("/work/proj/src/a.rs", style), "src/a.rs");
+        assert_eq!(display("/work/other/a.rs", style), "/work/other/a.rs");
+        let dotdot = PathStyle {
+            relative_fallback: RelativeFallback::Dotdot,
+            ..style
+        };
+        assert_eq!(display("/work/other/a.rs", dotdot), "../other/a.rs");
+    }
+
+    #[test]
+    fn collapsed_markdown_headers_use_the_fence_line() {
+        let style = PathStyle {
+            inline: true,
+            ..PathStyle::from(PathHeader::Absolute)
+        };
+        let fmt = build_formatter(FormatChoice::Markdown, style, FenceLanguages::default());
+        let mut out = Vec::new();
+        let header = FileHeader {
+            path: Path::new("/src/main.rs"),
+            target: None,
+            meta: Some("48 lines"),
+            index: None,
+            stats: None,
+        };
+        fmt.write_file_header(&header, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "```rust /src/main.rs (48 lines)\n"
+        );
     }
 }
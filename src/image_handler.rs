@@ -79,7 +79,7 @@ pub fn copy_image_to_clipboard(path: &std::path::Path) -> anyhow::Result<()> {
         // The 500 ms sleep gives clipboard managers time to fetch before we exit.
         let rgba = img.into_rgba8();
         let bytes = rgba.into_raw();
-        let mut clipboard = arboard::Clipboard::new()
+        let mut clipboard = crate::clipboard::open_arboard()
             .map_err(|e| anyhow::anyhow!("Failed to open clipboard: {e}"))?;
         clipboard
             .set_image(arboard::ImageData {
@@ -0,0 +1,446 @@
+use anyhow::Result;
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+/// Which import syntax `--follow-includes` looks for.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum IncludeLang {
+    /// Pick by each file's extension.
+    Auto,
+    /// `mod name;` and `use crate::` / `self::` / `super::` paths.
+    Rust,
+    /// `#include "file"` (angle-bracket system headers are never followed).
+    C,
+    /// Relative imports and modules found under the project root.
+    Python,
+    /// Relative `import` / `export ... from` / `require()` specifiers (JS and TS).
+    Js,
+}
+
+impl IncludeLang {
+    fn for_file(self, path: &Path) -> Option<IncludeLang> {
+        if self != IncludeLang::Auto {
+            return Some(self);
+        }
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "rs" => Some(IncludeLang::Rust),
+            "c" | "h" | "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" => Some(IncludeLang::C),
+            "py" => Some(IncludeLang::Python),
+            "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "mts" | "cts" => Some(IncludeLang::Js),
+            _ => None,
+        }
+    }
+}
+
+/// Extensions tried, in order, for an extensionless JS/TS specifier.
+const JS_EXTENSIONS: &[&str] = &["ts", "tsx", "js", "jsx", "mjs", "cjs", "mts", "cts"];
+
+/// `entries` followed by every project file they reach through local imports,
+/// breadth first, at most `max_depth` hops away. Only files under `root` are
+/// followed; the patterns are textual, so imports hidden in strings or
+/// comments may be picked up and macro-generated ones missed.
+pub fn follow(
+    entries: &[PathBuf],
+    lang: IncludeLang,
+    max_depth: usize,
+    root: &Path,
+) -> Result<Vec<PathBuf>> {
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let mut seen = HashSet::new();
+    let mut order = Vec::new();
+    let mut queue = VecDeque::new();
+    for entry in entries {
+        if !entry.is_file() {
+            anyhow::bail!(
+                "--follow-includes starts from files; '{}' is not one",
+                entry.display()
+            );
+        }
+        let canonical = entry.canonicalize()?;
+        if seen.insert(canonical.clone()) {
+            order.push(canonical.clone());
+            queue.push_back((canonical, 0));
+        }
+    }
+    while let Some((file, depth)) = queue.pop_front() {
+        if depth == max_depth {
+            continue;
+        }
+        let Some(file_lang) = lang.for_file(&file) else {
+            continue;
+        };
+        let Ok(content) = std::fs::read_to_string(&file) else {
+            continue;
+        };
+        for found in references(file_lang, &file, &content, &root) {
+            let Ok(found) = found.canonicalize() else {
+                continue;
+            };
+            if found.starts_with(&root) && found.is_file() && seen.insert(found.clone()) {
+                log::debug!("{} includes {}", file.display(), found.display());
+                order.push(found.clone());
+                queue.push_back((found, depth + 1));
+            }
+        }
+    }
+    Ok(order)
+}
+
+/// Existing files that `file` refers to.
+fn references(lang: IncludeLang, file: &Path, content: &str, root: &Path) -> Vec<PathBuf> {
+    let dir = file.parent().unwrap_or(Path::new("."));
+    match lang {
+        IncludeLang::Rust => rust_references(file, content),
+        IncludeLang::C => content
+            .lines()
+            .filter_map(|line| {
+                let rest = line.trim_start().strip_prefix('#')?.trim_start();
+                let rest = rest.strip_prefix("include")?.trim_start();
+                let name = rest.strip_prefix('"')?.split('"').next()?;
+                [dir.join(name), root.join(name)]
+                    .into_iter()
+                    .find(|p| p.is_file())
+            })
+            .collect(),
+        IncludeLang::Python => python_references(dir, content, root),
+        IncludeLang::Js => js_specifiers(content)
+            .into_iter()
+            .filter(|spec| spec.starts_with('.'))
+            .filter_map(|spec| resolve_js(&dir.join(spec)))
+            .collect(),
+        IncludeLang::Auto => Vec::new(),
+    }
+}
+
+/// Directory holding the submodules of the module defined in `file`.
+fn rust_module_dir(file: &Path) -> PathBuf {
+    let dir = file.parent().unwrap_or(Path::new("."));
+    match file.file_stem().and_then(|s| s.to_str()) {
+        Some("main" | "lib" | "mod") | None => dir.to_path_buf(),
+        Some(stem) => dir.join(stem),
+    }
+}
+
+/// The crate's `src/` directory: next to the nearest `Cargo.toml`, else the
+/// entry file's own directory.
+fn rust_crate_root(file: &Path) -> PathBuf {
+    file.ancestors()
+        .skip(1)
+        .find(|dir| dir.join("Cargo.toml").is_file())
+        .map(|dir| dir.join("src"))
+        .unwrap_or_else(|| file.parent().unwrap_or(Path::new(".")).to_path_buf())
+}
+
+fn rust_references(file: &Path, content: &str) -> Vec<PathBuf> {
+    let module_dir = rust_module_dir(file);
+    let mut found = Vec::new();
+    let mut path_attr: Option<String> = None;
+    let mut statement = String::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(value) = line
+            .strip_prefix("#[path")
+            .and_then(|rest| rest.split('"').nth(1))
+        {
+            path_attr = Some(value.to_string());
+            continue;
+        }
+        let decl = strip_visibility(line);
+        if let Some(name) = decl
+            .strip_prefix("mod ")
+            .and_then(|rest| rest.trim().strip_suffix(';'))
+        {
+            let candidates = match path_attr.take() {
+                Some(path) => vec![file.parent().unwrap_or(Path::new(".")).join(path)],
+                None => vec![
+                    module_dir.join(format!("{name}.rs")),
+                    module_dir.join(name).join("mod.rs"),
+                ],
+            };
+            found.extend(candidates.into_iter().find(|p| p.is_file()));
+            continue;
+        }
+        path_attr = None;
+        // `use` statements can span lines; gather one up to its semicolon.
+        if statement.is_empty() {
+            match decl.strip_prefix("use ") {
+                Some(rest) => statement.push_str(rest),
+                None => continue,
+            }
+        } else {
+            statement.push_str(line);
+        }
+        if let Some(tree) = statement
+            .split(';')
+            .next()
+            .filter(|_| statement.contains(';'))
+        {
+            for path in use_paths(tree) {
+                found.extend(resolve_rust_path(file, &module_dir, &path));
+            }
+            statement.clear();
+        }
+    }
+    found
+}
+
+fn strip_visibility(line: &str) -> &str {
+    if let Some(rest) = line.strip_prefix("pub(") {
+        return rest.split_once(')').map_or(line, |(_, r)| r.trim_start());
+    }
+    line.strip_prefix("pub ").unwrap_or(line)
+}
+
+/// Flatten a use tree (`crate::a::{b, c::{d, e}}`) into its paths.
+fn use_paths(tree: &str) -> Vec<String> {
+    let tree = tree.trim();
+    let (Some(open), Some(close)) = (tree.find('{'), tree.rfind('}')) else {
+        return vec![tree.split(" as ").next().unwrap_or(tree).trim().to_string()];
+    };
+    let prefix = &tree[..open];
+    let inner = &tree[open + 1..close];
+    let mut depth = 0;
+    let mut start = 0;
+    let mut parts = Vec::new();
+    for (i, c) in inner.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&inner[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&inner[start..]);
+    parts
+        .into_iter()
+        .filter(|part| !part.trim().is_empty())
+        .flat_map(use_paths)
+        .map(|path| format!("{prefix}{path}"))
+        .collect()
+}
+
+/// The deepest module file named by a `crate::`, `self::` or `super::` path.
+fn resolve_rust_path(file: &Path, module_dir: &Path, path: &str) -> Option<PathBuf> {
+    let mut segments: Vec<&str> = path.split("::").map(str::trim).collect();
+    let mut base = match *segments.first()? {
+        "crate" => rust_crate_root(file),
+        "self" => module_dir.to_path_buf(),
+        "super" => module_dir.parent()?.to_path_buf(),
+        _ => return None,
+    };
+    segments.remove(0);
+    while segments.first() == Some(&"super") {
+        base = base.parent()?.to_path_buf();
+        segments.remove(0);
+    }
+    let mut deepest = None;
+    let mut dir = base;
+    for segment in segments {
+        if segment == "self" || segment == "*" {
+            break;
+        }
+        let candidates = [
+            dir.join(format!("{segment}.rs")),
+            dir.join(segment).join("mod.rs"),
+        ];
+        match candidates.into_iter().find(|p| p.is_file()) {
+            Some(found) => deepest = Some(found),
+            None => break,
+        }
+        dir = dir.join(segment);
+    }
+    deepest
+}
+
+fn python_references(dir: &Path, content: &str, root: &Path) -> Vec<PathBuf> {
+    let module_file = |base: &Path, dotted: &str| {
+        let rel: PathBuf = dotted.split('.').filter(|s| !s.is_empty()).collect();
+        [
+            base.join(&rel).with_extension("py"),
+            base.join(&rel).join("__init__.py"),
+        ]
+        .into_iter()
+        .find(|p| p.is_file() && !rel.as_os_str().is_empty())
+    };
+    let mut found = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("from ") {
+            let Some((module, names)) = rest.split_once(" import ") else {
+                continue;
+            };
+            let module = module.trim();
+            let dots = module.chars().take_while(|&c| c == '.').count();
+            let bases: Vec<PathBuf> = if dots > 0 {
+                dir.ancestors()
+                    .nth(dots - 1)
+                    .map(Path::to_path_buf)
+                    .into_iter()
+                    .collect()
+            } else {
+                vec![dir.to_path_buf(), root.to_path_buf()]
+            };
+            let module = &module[dots..];
+            for base in &bases {
+                found.extend(module_file(base, module));
+                // `from pkg import sub` may name a submodule rather than a symbol.
+                for name in names.trim_matches(|c| c == '(' || c == ')').split(',') {
+                    let name = name.split(" as ").next().unwrap_or("").trim();
+                    if !name.is_empty() && name != "*" {
+                        found.extend(module_file(base, &format!("{module}.{name}")));
+                    }
+                }
+            }
+        } else if let Some(rest) = line.strip_prefix("import ") {
+            for module in rest.split(',') {
+                let module = module.split(" as ").next().unwrap_or("").trim();
+                for base in [dir, root] {
+                    found.extend(module_file(base, module));
+                }
+            }
+        }
+    }
+    found
+}
+
+/// Quoted specifiers of `import`/`export ... from`, bare `import`, `import()`
+/// and `require()`.
+fn js_specifiers(content: &str) -> Vec<String> {
+    let mut specs = Vec::new();
+    for marker in ["from ", "import ", "import(", "require("] {
+        let mut rest = content;
+        while let Some(at) = rest.find(marker) {
+            rest = &rest[at + marker.len()..];
+            let trimmed = rest.trim_start();
+            let Some(quote) = trimmed.chars().next().filter(|c| "'\"`".contains(*c)) else {
+                continue;
+            };
+            if let Some(spec) = trimmed[1..].split(quote).next() {
+                specs.push(spec.to_string());
+            }
+        }
+    }
+    specs
+}
+
+/// `path` as written, with a JS/TS extension added, as a directory index, or
+/// with a `.js` specifier mapped back to its TypeScript source.
+fn resolve_js(path: &Path) -> Option<PathBuf> {
+    if path.is_file() {
+        return Some(path.to_path_buf());
+    }
+    let with_ext = |base: &Path| {
+        JS_EXTENSIONS.iter().find_map(|ext| {
+            let mut name = base.as_os_str().to_owned();
+            name.push(format!(".{ext}"));
+            let candidate = PathBuf::from(name);
+            candidate.is_file().then_some(candidate)
+        })
+    };
+    let stripped = match path.extension().and_then(|e| e.to_str()) {
+        Some("js" | "jsx" | "mjs" | "cjs") => Some(path.with_extension("")),
+        _ => None,
+    };
+    with_ext(path)
+        .or_else(|| with_ext(&path.join("index")))
+        .or_else(|| stripped.and_then(|p| with_ext(&p)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn names(files: &[PathBuf], root: &Path) -> Vec<String> {
+        let root = root.canonicalize().unwrap();
+        files
+            .iter()
+            .map(|f| {
+                f.strip_prefix(&root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn follows_rust_mods_and_crate_paths() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src/net")).unwrap();
+        fs::write(root.join("Cargo.toml"), "[package]\n").unwrap();
+        fs::write(
+            root.join("src/main.rs"),
+            "mod cli;\nmod net;\nfn main() {}\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("src/cli.rs"),
+            "use crate::net::{http::Client, Error};\n",
+        )
+        .unwrap();
+        fs::write(root.join("src/net/mod.rs"), "pub mod http;\n").unwrap();
+        fs::write(root.join("src/net/http.rs"), "use super::super::cli;\n").unwrap();
+        fs::write(root.join("src/unused.rs"), "").unwrap();
+
+        let all = follow(&[root.join("src/main.rs")], IncludeLang::Auto, 5, root).unwrap();
+        assert_eq!(
+            names(&all, root),
+            [
+                "src/main.rs",
+                "src/cli.rs",
+                "src/net/mod.rs",
+                "src/net/http.rs"
+            ]
+        );
+        let near = follow(&[root.join("src/cli.rs")], IncludeLang::Rust, 1, root).unwrap();
+        assert_eq!(
+            names(&near, root),
+            ["src/cli.rs", "src/net/http.rs", "src/net/mod.rs"]
+        );
+    }
+
+    #[test]
+    fn follows_c_python_and_js_imports_within_the_root() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("pkg")).unwrap();
+        fs::create_dir_all(root.join("web/lib")).unwrap();
+        fs::write(
+            root.join("main.c"),
+            "#include <stdio.h>\n#include \"util.h\"\n",
+        )
+        .unwrap();
+        fs::write(root.join("util.h"), "#include \"../outside.h\"\n").unwrap();
+        fs::write(root.join("app.py"), "import os\nfrom pkg import helpers\n").unwrap();
+        fs::write(root.join("pkg/__init__.py"), "").unwrap();
+        fs::write(root.join("pkg/helpers.py"), "from .base import Thing\n").unwrap();
+        fs::write(root.join("pkg/base.py"), "").unwrap();
+        fs::write(
+            root.join("web/app.ts"),
+            "import { a } from './lib';\nimport React from 'react';\nconst b = require(\"./b.js\");\n",
+        )
+        .unwrap();
+        fs::write(root.join("web/lib/index.ts"), "").unwrap();
+        fs::write(root.join("web/b.ts"), "").unwrap();
+
+        let c = follow(&[root.join("main.c")], IncludeLang::Auto, 5, root).unwrap();
+        assert_eq!(names(&c, root), ["main.c", "util.h"]);
+        let py = follow(&[root.join("app.py")], IncludeLang::Auto, 5, root).unwrap();
+        assert_eq!(
+            names(&py, root),
+            ["app.py", "pkg/__init__.py", "pkg/helpers.py", "pkg/base.py"]
+        );
+        let js = follow(&[root.join("web/app.ts")], IncludeLang::Auto, 5, root).unwrap();
+        assert_eq!(
+            names(&js, root),
+            ["web/app.ts", "web/lib/index.ts", "web/b.ts"]
+        );
+    }
+}
@@ -1,36 +1,193 @@
 use anyhow::Result;
 use clap::Parser;
 
+mod cargo_deps;
 mod cli;
 mod clipboard;
+mod config;
 mod content_aggregator;
+mod diffstat;
+mod exec;
 mod formatter;
 mod image_handler;
+mod includes;
 mod lang;
 mod notebook;
 mod output_handler;
+mod paste;
+mod summary;
 mod token_counter;
 mod tui;
 
 use cli::{Args, Destination, Mode};
-use content_aggregator::ContentAggregator;
+use content_aggregator::{ContentAggregator, SkipReason, SkippedFile};
 
 #[cfg(feature = "dhat-heap")]
 #[global_allocator]
 static ALLOC: dhat::Alloc = dhat::Alloc;
 
 /// Read newline-delimited paths from stdin, stripping CR and skipping blank lines.
-fn read_stdin_paths() -> anyhow::Result<Vec<String>> {
-    use std::io::BufRead;
-    let stdin = std::io::stdin();
-    let paths = stdin
-        .lock()
+fn read_stdin_paths(format: cli::InputFormat) -> anyhow::Result<Vec<String>> {
+    use std::io::Read;
+    let mut input = Vec::new();
+    std::io::stdin().lock().read_to_end(&mut input)?;
+    Ok(format.parse(&String::from_utf8_lossy(&input)))
+}
+
+/// Absolute form of each `--write` target, resolved through its (existing)
+/// parent so it compares equal to paths found by the directory walk.
+fn output_file_paths(output: &cli::OutputArgs) -> Vec<std::path::PathBuf> {
+    let Destination::File { paths, .. } = output.destination() else {
+        return Vec::new();
+    };
+    paths
+        .iter()
+        .filter_map(|path| {
+            let parent = match path.parent() {
+                Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+                _ => std::path::PathBuf::from("."),
+            };
+            let name = path.file_name()?;
+            Some(std::fs::canonicalize(parent).ok()?.join(name))
+        })
+        .collect()
+}
+
+/// Measure every file once, then group them into chunks of at most `budget`
+/// tokens, each to be aggregated as its own document. `flag` names the option
+/// in warnings.
+fn plan_chunks(
+    aggregator: &mut ContentAggregator,
+    paths: &[String],
+    args: &Args,
+    budget: usize,
+    flag: &str,
+) -> Result<Vec<Vec<std::path::PathBuf>>> {
+    aggregate_sanitized(aggregator, paths, &args.render, &mut std::io::sink())?;
+    print_skip_summary(aggregator, args.output.verbose);
+    for (file, tokens) in aggregator.file_tokens() {
+        if *tokens > budget {
+            eprintln!(
+                "Warning: '{}' alone is {} tokens, over the {flag} budget",
+                file.display(),
+                token_counter::format_count(*tokens)
+            );
+        }
+    }
+    Ok(content_aggregator::chunk_by_tokens(
+        aggregator.file_tokens(),
+        budget,
+    ))
+}
+
+/// `a.txt, b.txt` for the status line after writing to `--write` targets.
+fn display_paths(paths: &[std::path::PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The arguments cxt was started with, the program named plainly `cxt`.
+fn cli_argv() -> Vec<String> {
+    std::iter::once("cxt".to_string())
+        .chain(
+            std::env::args_os()
+                .skip(1)
+                .map(|a| a.to_string_lossy().into_owned()),
+        )
+        .collect()
+}
+
+/// Quitting the TUI without confirming is a cancellation, reported through
+/// `--cancel-exit-code` so scripts can tell it apart from an empty match.
+fn exit_cancelled(code: i32) -> Result<()> {
+    println!("No files or directories selected. Exiting.");
+    if code != 0 {
+        std::process::exit(code);
+    }
+    Ok(())
+}
+
+/// Root of the git work tree containing the current directory, if any.
+fn git_toplevel() -> Option<std::path::PathBuf> {
+    let output = std::process::Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| std::path::PathBuf::from(String::from_utf8_lossy(&output.stdout).trim()))
+}
+
+/// Join relative `paths` onto `base` (`--paths-base`); absolute paths are kept.
+fn resolve_against(base: Option<&std::path::Path>, paths: Vec<String>) -> Vec<String> {
+    let Some(base) = base else {
+        return paths;
+    };
+    paths
+        .into_iter()
+        .map(|p| {
+            if std::path::Path::new(&p).is_absolute() {
+                p
+            } else {
+                base.join(&p).to_string_lossy().into_owned()
+            }
+        })
+        .collect()
+}
+
+/// Open `$VISUAL`/`$EDITOR` (falling back to `vi`) on a scratch file and read
+/// back one path per line, skipping blanks and `#` comments.
+fn read_editor_paths() -> anyhow::Result<Vec<String>> {
+    // Created exclusively with a random name, so nothing planted in the temp dir is followed.
+    let mut scratch = tempfile::Builder::new()
+        .prefix("cxt_paths_")
+        .suffix(".txt")
+        .tempfile()?;
+    std::io::Write::write_all(
+        &mut scratch,
+        b"# Enter one path per line, then save and quit.\n\
+          # Lines starting with '#' are ignored.\n",
+    )?;
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    let status = std::process::Command::new(program)
+        .args(parts)
+        .arg(scratch.path())
+        .status();
+    let content = std::fs::read_to_string(scratch.path());
+    let status = status.map_err(|e| anyhow::anyhow!("Failed to launch editor '{program}': {e}"))?;
+    if !status.success() {
+        anyhow::bail!("Editor '{program}' exited with {status}");
+    }
+    Ok(content?
         .lines()
-        .map_while(Result::ok)
-        .map(|l| l.trim_end_matches('\r').to_string())
-        .filter(|l| !l.is_empty())
-        .collect();
-    Ok(paths)
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(String::from)
+        .collect())
+}
+
+/// Files in the cxt output on the clipboard, or in `from` (`-` for stdin),
+/// for `cxt paste` and `cxt diff`.
+fn read_pasted_files(from: Option<&str>) -> Result<Vec<paste::PastedFile>> {
+    let text = match from {
+        None => clipboard::read_text()?,
+        Some("-") => std::io::read_to_string(std::io::stdin())?,
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read '{path}': {e}"))?,
+    };
+    let files = paste::parse(&text);
+    if files.is_empty() {
+        anyhow::bail!("No XML or Markdown cxt file blocks found");
+    }
+    Ok(files)
 }
 
 fn dedup_paths(paths: Vec<String>) -> Vec<String> {
@@ -57,42 +214,390 @@ fn expand_braces(inputs: Vec<String>) -> Vec<String> {
         .collect()
 }
 
-fn destination_from_args(args: &Args) -> Destination {
-    args.output.destination()
+/// CLI destination flags win; otherwise the config's `default_output` applies.
+/// `--manifest-stdout` counts as one: stdout belongs to the manifest, so a
+/// configured `stdout` or `print` default must not write content there too.
+fn destination_from_args(args: &Args, config: &config::Config) -> Destination {
+    let o = &args.output;
+    let explicit = !o.write.is_empty()
+        || o.print
+        || o.ci
+        || o.ext_summary
+        || o.line_counts
+        || o.manifest_stdout
+        || o.exec.is_some();
+    match config.default_output {
+        Some(default) if !explicit => default.destination(),
+        _ => o.destination(),
+    }
 }
 
-fn print_binary_skip_warning(aggregator: &ContentAggregator) {
-    let n = aggregator.skipped_binary_count();
-    if n > 0 {
+/// One-line summary of skipped files, e.g. "3 files skipped: 2 binary, 1 permission
+/// denied"; `--verbose` lists each file too.
+fn print_skip_summary(aggregator: &ContentAggregator, verbose: bool) {
+    let skipped = aggregator.skipped_files();
+    if skipped.is_empty() {
+        return;
+    }
+    let mut by_reason: std::collections::BTreeMap<_, usize> = std::collections::BTreeMap::new();
+    for file in skipped {
+        *by_reason.entry(file.reason).or_default() += 1;
+    }
+    let breakdown: Vec<String> = by_reason
+        .iter()
+        .map(|(reason, n)| format!("{n} {}", reason.label()))
+        .collect();
+    // Outliers and possible secrets are always listed: the user should see both.
+    let listed = |file: &&SkippedFile| {
+        verbose || matches!(file.reason, SkipReason::Outlier | SkipReason::Sensitive)
+    };
+    let n = skipped.len();
+    eprintln!(
+        "({n} file{} skipped: {}{})",
+        if n == 1 { "" } else { "s" },
+        breakdown.join(", "),
+        if skipped.iter().all(|f| listed(&f)) {
+            ""
+        } else {
+            " — use --verbose to list them"
+        }
+    );
+    for file in skipped.iter().filter(listed) {
+        match &file.detail {
+            Some(detail) => eprintln!("  {}: {detail}", file.path.display()),
+            None => eprintln!("  {}: {}", file.path.display(), file.reason.label()),
+        }
+    }
+    if skipped.iter().any(|f| f.reason == SkipReason::Sensitive) {
         eprintln!(
-            "({n} binary file{} skipped — add -i patterns to suppress this warning)",
-            if n == 1 { "" } else { "s" }
+            "Warning: files that may hold secrets were left out; pass --allow-sensitive \
+             to include them"
+        );
+    }
+}
+
+fn print_wc_counts(c: &output_handler::WcCounts) {
+    eprintln!(
+        "{} lines, {} words, {} bytes",
+        token_counter::format_count(c.lines),
+        token_counter::format_count(c.words),
+        token_counter::format_count(c.bytes)
+    );
+}
+
+fn print_line_counts(aggregator: &ContentAggregator) {
+    let cwd = std::env::current_dir().ok();
+    let rows = aggregator.line_counts();
+    let width = rows
+        .first()
+        .map_or(1, |(_, n)| token_counter::format_count(*n).len());
+    for (path, lines) in &rows {
+        let display = cwd
+            .as_ref()
+            .and_then(|c| path.strip_prefix(c).ok())
+            .unwrap_or(path);
+        println!(
+            "  {:>width$}  {}",
+            token_counter::format_count(*lines),
+            display.display()
+        );
+    }
+}
+
+fn print_ext_summary(aggregator: &ContentAggregator) {
+    let rows = aggregator.ext_summary();
+    println!("  {:<12} {:>8} {:>14}", "EXT", "FILES", "BYTES");
+    for (ext, count, bytes) in &rows {
+        let ext = if ext.is_empty() { "(none)" } else { ext };
+        println!(
+            "  {:<12} {:>8} {:>14}",
+            ext,
+            token_counter::format_count(*count),
+            token_counter::format_count(*bytes as usize)
+        );
+    }
+    let total_files: usize = rows.iter().map(|r| r.1).sum();
+    let total_bytes: u64 = rows.iter().map(|r| r.2).sum();
+    println!(
+        "  {:<12} {:>8} {:>14}",
+        "total",
+        token_counter::format_count(total_files),
+        token_counter::format_count(total_bytes as usize)
+    );
+}
+
+/// Run the aggregation through `--ascii-only` and `--no-final-newline` when
+/// requested; returns how many characters were replaced.
+fn aggregate_sanitized(
+    aggregator: &mut ContentAggregator,
+    paths: &[String],
+    render: &cli::RenderArgs,
+    w: &mut dyn std::io::Write,
+) -> Result<usize> {
+    let mut trimmer;
+    let w: &mut dyn std::io::Write = if render.no_final_newline {
+        trimmer = output_handler::TrimFinalNewline::new(w);
+        &mut trimmer
+    } else {
+        w
+    };
+    match render.ascii_only.as_deref() {
+        Some(replacement) => {
+            let mut sanitizer = output_handler::AsciiWriter::new(w, replacement);
+            aggregator.aggregate_paths(paths, &mut sanitizer)?;
+            Ok(sanitizer.replaced())
+        }
+        None => {
+            aggregator.aggregate_paths(paths, w)?;
+            Ok(0)
+        }
+    }
+}
+
+/// Whether a failed clipboard copy has somewhere to go: `--clipboard-fallback-file`,
+/// or on a headless system a temp file. Otherwise the failure is an error.
+fn has_clipboard_fallback(output: &cli::OutputArgs) -> bool {
+    output.clipboard_fallback_file.is_some() || output_handler::is_headless()
+}
+
+/// Write `buf` to `--clipboard-fallback-file`, or else to a new temp file named
+/// `<prefix>XXXXXX.txt` that is kept. The temp file is created exclusively, so a
+/// name planted in a shared temp dir is never followed or overwritten.
+fn write_fallback_file(
+    output: &cli::OutputArgs,
+    prefix: &str,
+    buf: &[u8],
+) -> Result<std::path::PathBuf> {
+    use std::io::Write;
+    if let Some(path) = &output.clipboard_fallback_file {
+        std::fs::write(path, buf)?;
+        return Ok(path.clone());
+    }
+    let mut file = tempfile::Builder::new()
+        .prefix(prefix)
+        .suffix(".txt")
+        .tempfile()?;
+    file.write_all(buf)?;
+    let (_, path) = file.keep()?;
+    Ok(path)
+}
+
+/// Copy buffered output to the clipboard, honouring `--max-clipboard-size`,
+/// `--fallback-stdout` and the fallback file. Returns where the content actually ended up.
+fn deliver_to_clipboard(
+    buf: &[u8],
+    echo: bool,
+    mime: Option<&'static str>,
+    output: &cli::OutputArgs,
+) -> Result<Destination> {
+    use std::io::Write;
+    let limit = output.max_clipboard_size;
+    let delivered = if limit > 0 && buf.len() as u64 > limit {
+        let reason = format!(
+            "output is {} bytes, over the --max-clipboard-size limit of {limit}",
+            token_counter::format_count(buf.len())
         );
+        if output.fallback_stdout {
+            eprintln!("Warning: {reason}; printing to stdout instead.");
+            Destination::Stdout
+        } else {
+            let path = write_fallback_file(output, "cxt_output_", buf)?;
+            eprintln!("Warning: {reason}; wrote it to a file instead.");
+            Destination::File {
+                paths: vec![path],
+                gzip: false,
+                append: false,
+            }
+        }
+    } else {
+        let copied = Destination::Clipboard { echo: false }
+            .write_with_mime(mime, |w| w.write_all(buf).map_err(Into::into));
+        match copied {
+            Ok(()) => Destination::Clipboard { echo },
+            Err(e) if output.fallback_stdout => {
+                eprintln!("Warning: clipboard copy failed ({e}); printing to stdout instead.");
+                Destination::Stdout
+            }
+            Err(e) if has_clipboard_fallback(output) => {
+                let path = write_fallback_file(output, "cxt_clipboard_", buf)?;
+                eprintln!("Warning: clipboard copy failed ({e}); wrote it to a file instead.");
+                Destination::File {
+                    paths: vec![path],
+                    gzip: false,
+                    append: false,
+                }
+            }
+            Err(e) => return Err(e),
+        }
+    };
+    let print = match delivered {
+        Destination::Stdout => true,
+        Destination::Clipboard { .. } | Destination::File { .. } => echo,
+        Destination::Discard => false,
+    };
+    if print {
+        std::io::stdout().write_all(buf)?;
     }
+    Ok(delivered)
 }
 
-fn print_aggregate_summary(aggregator: &ContentAggregator, dest: &Destination) {
+fn clipboard_summary(aggregator: &ContentAggregator) -> String {
     let files = aggregator.file_count();
-    let tokens = token_counter::format_count(aggregator.token_count());
+    format!(
+        "Copied {} tokens from {files} file{} to clipboard.",
+        token_counter::format_count(aggregator.token_count()),
+        if files == 1 { "" } else { "s" }
+    )
+}
+
+/// `--as-uri-list`: one `file://` URI per resolved file, CRLF-terminated as
+/// RFC 2483 specifies for `text/uri-list`.
+fn write_uri_list(files: &[std::path::PathBuf], w: &mut dyn std::io::Write) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    for file in files {
+        let abs = std::fs::canonicalize(file).unwrap_or_else(|_| cwd.join(file));
+        write!(w, "{}\r\n", formatter::file_uri(&abs))?;
+    }
+    Ok(())
+}
+
+/// `out.txt` -> `out.3.txt` for the third `--chunk-by-tokens` chunk.
+fn chunk_path(path: &std::path::Path, n: usize) -> std::path::PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{stem}.{n}.{}", ext.to_string_lossy()),
+        None => format!("{stem}.{n}"),
+    };
+    path.with_file_name(name)
+}
+
+fn print_uri_list_summary(files: usize, dest: &Destination) {
     let plural = if files == 1 { "" } else { "s" };
     match dest {
-        Destination::File { path, .. } => {
+        Destination::File { paths, .. } => {
             println!(
-                "Wrote {tokens} tokens from {files} file{plural} to {}.",
-                path.display()
+                "Wrote {files} file URI{plural} to {}.",
+                display_paths(paths)
             );
         }
-        Destination::Clipboard { .. } => {
-            println!("Copied {tokens} tokens from {files} file{plural} to clipboard.");
-        }
+        Destination::Clipboard { .. } => println!("Copied {files} file URI{plural} to clipboard."),
         Destination::Stdout | Destination::Discard => {}
     }
 }
 
+/// `to_stderr` keeps stdout clean for `--manifest-stdout`.
+fn print_aggregate_summary(aggregator: &ContentAggregator, dest: &Destination, to_stderr: bool) {
+    let files = aggregator.file_count();
+    let tokens = token_counter::format_count(aggregator.token_count());
+    let plural = if files == 1 { "" } else { "s" };
+    let line = match dest {
+        Destination::File { paths, .. } => format!(
+            "Wrote {tokens} tokens from {files} file{plural} to {}.",
+            display_paths(paths)
+        ),
+        Destination::Clipboard { .. } => clipboard_summary(aggregator),
+        Destination::Stdout | Destination::Discard => return,
+    };
+    if to_stderr {
+        eprintln!("{line}");
+    } else {
+        println!("{line}");
+    }
+}
+
+/// `--report-json`: one line saying where the output went, e.g.
+/// `{"destination":"clipboard","files":12,"bytes":34567}`.
+fn print_report_json(dest: &Destination, files: usize, bytes: usize) {
+    let mut report = serde_json::json!({
+        "destination": match dest {
+            Destination::Clipboard { .. } => "clipboard",
+            Destination::File { .. } => "file",
+            Destination::Stdout => "stdout",
+            Destination::Discard => "discard",
+        },
+        "files": files,
+        "bytes": bytes,
+    });
+    if let Destination::File { paths, .. } = dest {
+        report["paths"] = serde_json::json!(paths);
+    }
+    println!("{report}");
+}
+
+/// Walk and read threads when `--jobs` isn't given: one per CPU, but no more
+/// than a quarter of the open-file limit covers. Each thread may hold a
+/// directory (walking) and a file (reading) open, and with `--content-filter`
+/// the three pipes to its command.
+fn default_jobs(content_filter: bool) -> usize {
+    let cpus = std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get);
+    let per_thread = if content_filter { 5 } else { 2 };
+    match open_file_limit() {
+        Some(limit) => (limit / 4 / per_thread).clamp(1, cpus),
+        None => cpus,
+    }
+}
+
+/// The soft `RLIMIT_NOFILE`, or None when unlimited or unknown.
+#[cfg(unix)]
+fn open_file_limit() -> Option<usize> {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: getrlimit only writes the struct it is given.
+    if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) } != 0 {
+        return None;
+    }
+    (limit.rlim_cur != libc::RLIM_INFINITY).then(|| limit.rlim_cur.try_into().unwrap_or(usize::MAX))
+}
+
+#[cfg(not(unix))]
+fn open_file_limit() -> Option<usize> {
+    None
+}
+
+/// `--manifest-stdout`: each included file, relative to the current directory
+/// where possible, and with `preview` its first non-blank line.
+fn print_manifest(files: &[std::path::PathBuf], preview: bool) {
+    let cwd = std::env::current_dir().ok();
+    for file in files {
+        let shown = cwd
+            .as_deref()
+            .and_then(|c| file.strip_prefix(c).ok())
+            .unwrap_or(file);
+        match preview.then(|| first_line(file)).flatten() {
+            Some(line) => println!("{}  {line}", shown.display()),
+            None => println!("{}", shown.display()),
+        }
+    }
+}
+
+/// The first non-blank line in the first few KiB of `path`, trimmed and cut
+/// to `MAX_CHARS` (`--list-preview`).
+fn first_line(path: &std::path::Path) -> Option<String> {
+    use std::io::{BufRead, Read};
+    const MAX_CHARS: usize = 60;
+    let file = std::fs::File::open(path).ok()?;
+    let line = std::io::BufReader::new(file.take(8 * 1024))
+        .lines()
+        .map_while(Result::ok)
+        .map(|l| l.trim().to_string())
+        .find(|l| !l.is_empty())?;
+    Some(if line.chars().count() > MAX_CHARS {
+        let cut: String = line.chars().take(MAX_CHARS - 1).collect();
+        format!("{cut}…")
+    } else {
+        line
+    })
+}
+
 fn main() -> Result<()> {
     #[cfg(feature = "dhat-heap")]
     let _profiler = dhat::Profiler::new_heap();
 
+    // Quiet unless RUST_LOG asks for more, e.g. `RUST_LOG=cxt=debug`.
+    env_logger::init();
+
     let args = Args::parse_from(wild::args());
 
     if let Err(e) = args.validate() {
@@ -101,6 +606,37 @@ fn main() -> Result<()> {
     }
 
     match args.mode() {
+        Mode::InitConfig { force } => {
+            let path = config::init(force)?;
+            println!("Wrote {}", path.display());
+            return Ok(());
+        }
+
+        Mode::ClearClipboard => {
+            Destination::Clipboard { echo: false }.write_with(|_| Ok(()))?;
+            println!("Cleared the clipboard.");
+            return Ok(());
+        }
+
+        Mode::Paste {
+            into,
+            from,
+            conflict,
+        } => {
+            let files = read_pasted_files(from.as_deref())?;
+            let root = into.unwrap_or_else(|| std::path::PathBuf::from("."));
+            let written = paste::write_files(&files, &root, conflict)?;
+            eprintln!("Pasted {written} of {} files.", files.len());
+            return Ok(());
+        }
+
+        Mode::DiffPaste { into, from } => {
+            let files = read_pasted_files(from.as_deref())?;
+            let root = into.unwrap_or_else(|| std::path::PathBuf::from("."));
+            print!("{}", paste::review(&files, &root)?);
+            return Ok(());
+        }
+
         Mode::ListLanguages => {
             println!("Supported languages for --lang:\n");
             for name in lang::all_names() {
@@ -119,6 +655,22 @@ fn main() -> Result<()> {
             return Ok(());
         }
 
+        Mode::ListClipboardBackends => {
+            println!("Clipboard backends, in the order cxt tries them:\n");
+            for (i, (name, available)) in output_handler::OutputHandler::describe_backends()
+                .iter()
+                .enumerate()
+            {
+                let status = if *available {
+                    "available"
+                } else {
+                    "unavailable"
+                };
+                println!("  {}. {name:16} {status}", i + 1);
+            }
+            return Ok(());
+        }
+
         Mode::GitDiff(n) => {
             let diff_output = if n == 0 {
                 std::process::Command::new("git").args(["diff"]).output()?
@@ -160,8 +712,28 @@ fn main() -> Result<()> {
         Mode::Aggregate => {}
     }
 
-    // --st: resolve git-changed files, then fall through to aggregate.
-    let st_paths: Option<Vec<String>> = if let Some(n) = args.source.st {
+    let config = config::load();
+    // With nothing changed there is no output, but --report-json still owes its line.
+    let nothing_changed = |message: String| {
+        eprintln!("{message}");
+        if args.output.report_json {
+            print_report_json(&destination_from_args(&args, &config), 0, 0);
+        }
+    };
+
+    // --st / --since-ref: resolve git-changed files, then fall through to aggregate.
+    // The listing goes to stderr; stdout may be carrying the content or a report.
+    let st_paths: Option<Vec<String>> = if let Some(base) = &args.source.since_ref {
+        let paths = diffstat::changed_files(base)?;
+        if paths.is_empty() {
+            nothing_changed(format!("No changed files since {base}."));
+            return Ok(());
+        }
+        for p in &paths {
+            eprintln!("  {p}");
+        }
+        Some(paths)
+    } else if let Some(n) = args.source.st {
         let output = if n == 0 {
             std::process::Command::new("git")
                 .args(["diff", "--name-only", "HEAD"])
@@ -178,7 +750,7 @@ fn main() -> Result<()> {
         }
         let text = String::from_utf8_lossy(&output.stdout);
         if text.trim().is_empty() {
-            println!("No changed files.");
+            nothing_changed("No changed files.".to_string());
             return Ok(());
         }
         let paths: Vec<String> = text
@@ -187,7 +759,7 @@ fn main() -> Result<()> {
             .map(String::from)
             .collect();
         for p in &paths {
-            println!("  {p}");
+            eprintln!("  {p}");
         }
         Some(paths)
     } else {
@@ -195,46 +767,336 @@ fn main() -> Result<()> {
     };
 
     let stdin_is_piped = !atty::is(atty::Stream::Stdin);
-    let render = args.render;
+    let render = &args.render;
+    let paths_base = args.source.paths_base.as_deref();
+    if let Some(base) = paths_base {
+        if !base.is_dir() {
+            anyhow::bail!("--paths-base '{}' is not a directory", base.display());
+        }
+    }
 
+    let allowed_extensions = args.select.extensions().unwrap_or_else(|e| {
+        eprintln!("Error: {e}");
+        std::process::exit(1);
+    });
+
+    let mut ignore = expand_braces(args.select.ignore.clone());
+    let ignore_files = args.select.ignore_files().unwrap_or_else(|e| {
+        eprintln!("Error: {e}");
+        std::process::exit(1);
+    });
+    if args.source.rust {
+        ignore.extend(
+            cargo_deps::RUST_PRESET_IGNORES
+                .iter()
+                .map(|p| p.to_string()),
+        );
+    }
+    if args.select.exclude_generated {
+        ignore.extend(
+            content_aggregator::GENERATED_PATTERNS
+                .iter()
+                .map(|p| p.to_string()),
+        );
+    }
+
+    let stdin_content = match &args.source.stdin_content {
+        Some(name) => {
+            let mut content = Vec::new();
+            std::io::Read::read_to_end(&mut std::io::stdin(), &mut content)?;
+            Some((name, content))
+        }
+        None => None,
+    };
+    let mut fence_languages = formatter::FenceLanguages::default();
+    for (ext, lang) in config.lang_map.iter().chain(&render.lang_map) {
+        fence_languages.insert(ext.clone(), lang.clone());
+    }
+    let output_files = output_file_paths(&args.output);
+    let existing_output = match output_files.as_slice() {
+        [path] if args.output.dedup_headers_in_append => std::fs::read(path)
+            .ok()
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned()),
+        _ => None,
+    };
+    // Base for --with-diffstat / --with-diff: the ref the inputs were chosen against.
+    let diff_base = match (&args.source.since_ref, args.source.st) {
+        (Some(base), _) => base.clone(),
+        (None, Some(n)) if n > 0 => format!("HEAD~{n}"),
+        _ => "HEAD".to_string(),
+    };
+    let diffstat = if render.with_diffstat {
+        diffstat::numstat(&diff_base)?
+    } else {
+        Default::default()
+    };
+    let preamble = render.embed_command.then(|| {
+        format!(
+            "# Generated by: {}\n# Generated at: {}\n\n",
+            exec::command_line(&cli_argv()),
+            content_aggregator::format_timestamp(std::time::SystemTime::now())
+        )
+    });
+    let sensitive_patterns: Vec<String> = if args.select.allow_sensitive {
+        Vec::new()
+    } else {
+        config.sensitive_patterns.clone().unwrap_or_else(|| {
+            content_aggregator::SENSITIVE_PATTERNS
+                .iter()
+                .map(|p| p.to_string())
+                .collect()
+        })
+    };
+    let jobs = args.select.jobs.map_or_else(
+        || default_jobs(render.content_filter.is_some()),
+        usize::from,
+    );
+    // Each reader holds one file open at a time, so this bounds open files.
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .build_global()?;
+    let build_aggregator = |ignore: Vec<String>, header: cli::PathHeader| {
+        let fmt = formatter::build_formatter(
+            render.format(),
+            formatter::PathStyle {
+                header,
+                posix: render.posix_paths,
+                dot_slash: render.dot_slash,
+                relative_fallback: render.relative_fallback,
+                flat: render.flat_headers,
+                inline: render.collapse_headers,
+            },
+            fence_languages.clone(),
+        );
+        let aggregator = ContentAggregator::new(
+            fmt,
+            args.select.hidden,
+            ignore,
+            !args.select.no_sort,
+            allowed_extensions.clone(),
+        )
+        .with_force_include(args.select.force_include_patterns())
+        .with_ignore_files(ignore_files.clone())
+        .with_modified_within(args.select.modified_within)
+        .with_drop_outliers(args.select.drop_outliers)
+        .with_only_tracked(args.select.only_tracked)
+        .with_include_submodules(args.select.include_submodules)
+        .with_hidden_root(args.select.hidden_root)
+        .with_dir_grouping(args.select.dir_grouping())
+        .with_walk_threads(jobs)
+        .with_resolve_ignores(args.select.resolve_ignores)
+        .with_sensitive_patterns(&sensitive_patterns)
+        .with_max_entries(
+            args.select
+                .max_entries
+                .or(config.max_entries)
+                .unwrap_or(content_aggregator::DEFAULT_MAX_ENTRIES),
+        )
+        .with_binary_detection(args.select.binary_detection)
+        .with_notebook_markdown(render.notebook_markdown)
+        .with_dedup_blank_files(render.dedup_blank_files)
+        .with_note_skipped(render.note_skipped)
+        .with_preamble(preamble.clone())
+        .with_dir_markers(render.dir_markers)
+        .with_symlink_targets(render.symlink_targets)
+        .with_content_filter(render.content_filter.clone())
+        .with_wrap(render.wrap)
+        .with_compact(render.compact)
+        .with_max_lines(render.max_lines)
+        .with_sample_lines(render.sample_lines, render.sample_above)
+        .with_inline_filename(render.inline_filename)
+        .with_separator(render.separators_only.then(|| render.separator.clone()))
+        .with_keep_bom(render.keep_bom)
+        .with_ext_summary(args.output.ext_summary)
+        .with_line_counts(args.output.line_counts)
+        .with_toc(render.toc)
+        .with_file_list(
+            args.output.exec.is_some() || args.output.as_uri_list || args.output.manifest_stdout,
+        )
+        .with_file_tokens(
+            args.output.chunk_by_tokens.is_some() || args.output.clipboard_paginate.is_some(),
+        )
+        .with_output_files(output_files.clone())
+        .with_ignore_output_dir(args.select.ignore_output_dir)
+        .with_existing_output(existing_output.clone())
+        .with_diffstat(diffstat.clone())
+        .with_header_meta(render.header_meta.clone().unwrap_or_default());
+        match &stdin_content {
+            Some((name, content)) => aggregator.with_virtual_file(name, content.clone()),
+            None => aggregator,
+        }
+    };
+    let ascii_only = render.ascii_only.as_deref();
+    let mime = if args.output.as_uri_list {
+        Some("text/uri-list")
+    } else {
+        render.format().mime_type()
+    };
+    // Ctrl-y in the TUI copies the current selection without leaving it.
+    let copy_in_tui = |selection: &[String], header: cli::PathHeader| -> Result<String> {
+        let mut aggregator = build_aggregator(ignore.clone(), header).with_stream_cap(true);
+        let selection = expand_braces(selection.to_vec());
+        Destination::Clipboard { echo: false }.write_with_mime(mime, |w| {
+            aggregate_sanitized(&mut aggregator, &selection, render, w)
+        })?;
+        Ok(clipboard_summary(&aggregator))
+    };
+
+    let initial_header = render.header(config.path_header.unwrap_or(cli::PathHeader::Absolute));
     let mut tui_header: Option<cli::PathHeader> = None;
 
     let paths: Vec<String> = if let Some(p) = st_paths {
         p
-    } else if args.source.tui {
-        let outcome = tui::run_tui(render.relative, render.no_path)?;
+    } else if args.source.edit_paths {
+        let edited = dedup_paths(
+            args.paths
+                .iter()
+                .cloned()
+                .chain(resolve_against(paths_base, read_editor_paths()?))
+                .collect(),
+        );
+        if edited.is_empty() {
+            println!("No paths entered. Exiting.");
+            return Ok(());
+        }
+        edited
+    } else if let Some(source) = &args.source.paths_from_json {
+        let text = if source == "-" {
+            let mut text = String::new();
+            std::io::Read::read_to_string(&mut std::io::stdin(), &mut text)?;
+            text
+        } else {
+            std::fs::read_to_string(source).map_err(|e| {
+                anyhow::anyhow!("Failed to read --paths-from-json file '{source}': {e}")
+            })?
+        };
+        let listed = cli::parse_json_paths(&text)
+            .map_err(|e| anyhow::anyhow!("Invalid --paths-from-json input '{source}': {e}"))?;
+        dedup_paths(
+            args.paths
+                .iter()
+                .cloned()
+                .chain(resolve_against(paths_base, listed))
+                .collect(),
+        )
+    } else if args.source.tui || args.source.tui_start_selected || !args.source.select.is_empty() {
+        // Positional paths are either the pre-selection or the starting directories.
+        let (start_dirs, preselect_paths) = if args.source.tui_start_selected {
+            let selected = args
+                .paths
+                .iter()
+                .map(|p| {
+                    std::fs::canonicalize(p)
+                        .map_err(|_| anyhow::anyhow!("Path does not exist: {p}"))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            (Vec::new(), selected)
+        } else {
+            let start_dirs = args
+                .paths
+                .iter()
+                .map(|p| {
+                    std::fs::canonicalize(p)
+                        .ok()
+                        .filter(|d| d.is_dir())
+                        .ok_or_else(|| {
+                            anyhow::anyhow!(
+                                "--tui starting path '{p}' is not a directory \
+                                 (use --tui-start-selected to pre-select files)"
+                            )
+                        })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            (start_dirs, Vec::new())
+        };
+        let outcome = tui::run_tui(
+            initial_header == cli::PathHeader::Relative,
+            initial_header == cli::PathHeader::None,
+            start_dirs,
+            &args.source.select,
+            &preselect_paths,
+            args.source.preview_command.clone(),
+            &copy_in_tui,
+        )?;
         tui_header = Some(outcome.path_header);
         if outcome.paths.is_empty() {
-            println!("No files or directories selected. Exiting.");
-            return Ok(());
+            return exit_cancelled(args.source.cancel_exit_code);
         }
         outcome.paths
+    } else if args.source.stdin_content.is_some() {
+        // stdin carries content here, so only argument paths are aggregated.
+        args.paths.clone()
     } else if stdin_is_piped {
-        let stdin_paths = read_stdin_paths()?;
+        // `git status` paths are relative to the repository root, not the cwd.
+        let git_root = (paths_base.is_none()
+            && args.source.input_format == cli::InputFormat::Porcelain)
+            .then(git_toplevel)
+            .flatten();
+        let stdin_paths = resolve_against(
+            paths_base.or(git_root.as_deref()),
+            read_stdin_paths(args.source.input_format)?,
+        );
         let combined = dedup_paths(args.paths.iter().cloned().chain(stdin_paths).collect());
-        if combined.is_empty() {
+        if combined.is_empty() && args.source.with_dep.is_empty() && !args.source.rust {
             anyhow::bail!(
                 "No paths provided. Pipe a newline-delimited list of paths or pass them as arguments.\n\
                  Examples:\n  fd -e rs | cxt\n  cat file_list.txt | cxt\n  cxt src/ Cargo.toml"
             );
         }
         combined
-    } else if args.paths.is_empty() {
-        let outcome = tui::run_tui(render.relative, render.no_path)?;
+    } else if args.paths.is_empty() && args.source.with_dep.is_empty() && !args.source.rust {
+        if args.output.ci {
+            anyhow::bail!(
+                "No paths provided. --ci never opens the TUI: pass paths as arguments or pipe a list on stdin."
+            );
+        }
+        let outcome = tui::run_tui(
+            initial_header == cli::PathHeader::Relative,
+            initial_header == cli::PathHeader::None,
+            Vec::new(),
+            &[],
+            &[],
+            args.source.preview_command.clone(),
+            &copy_in_tui,
+        )?;
         tui_header = Some(outcome.path_header);
         if outcome.paths.is_empty() {
-            println!("No files or directories selected. Exiting.");
-            return Ok(());
+            return exit_cancelled(args.source.cancel_exit_code);
         }
         outcome.paths
     } else {
         args.paths.clone()
     };
 
-    let paths = expand_braces(paths);
+    let mut paths = expand_braces(paths);
+    if args.source.rust {
+        // The preset's inputs lead, so the manifest is the first thing read.
+        let mut preset = cargo_deps::rust_preset_paths()?;
+        preset.append(&mut paths);
+        paths = dedup_paths(preset);
+    }
+    if !args.source.with_dep.is_empty() {
+        let dep_dirs = cargo_deps::source_dirs(&args.source.with_dep)?;
+        paths.extend(dep_dirs.iter().map(|d| d.to_string_lossy().into_owned()));
+    }
+    if let Some(lang) = args.source.follow_includes {
+        let root = match git_toplevel() {
+            Some(root) => root,
+            None => std::env::current_dir()?,
+        };
+        let entries: Vec<_> = paths.iter().map(std::path::PathBuf::from).collect();
+        paths = includes::follow(&entries, lang, args.source.include_depth, &root)?
+            .iter()
+            .map(|p| p.to_string_lossy().into_owned())
+            .collect();
+    }
+    if args.select.order == cli::InputOrder::Sorted {
+        paths.sort();
+    }
 
-    if image_handler::check_image_mode(&paths)? {
-        let dest = destination_from_args(&args);
+    if args.source.stdin_content.is_none() && image_handler::check_image_mode(&paths)? {
+        // Images always go to the clipboard, so the config default doesn't apply.
+        let dest = args.output.destination();
         if !dest.requires_clipboard() {
             anyhow::bail!("Image mode requires clipboard access and is incompatible with --ci/--write/--print.");
         }
@@ -243,24 +1105,129 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
-    let allowed_extensions = args.select.extensions().unwrap_or_else(|e| {
-        eprintln!("Error: {e}");
-        std::process::exit(1);
+    // --clipboard-exclude: a second, trimmed pass goes to the clipboard.
+    let clipboard_ignore = (!args.output.clipboard_exclude.is_empty()).then(|| {
+        let mut patterns = ignore.clone();
+        patterns.extend(expand_braces(args.output.clipboard_exclude.clone()));
+        patterns
     });
+    // Writing would truncate an input before it is read.
+    for out in &output_files {
+        if paths
+            .iter()
+            .any(|p| std::fs::canonicalize(p).is_ok_and(|p| &p == out))
+        {
+            anyhow::bail!(
+                "--write target '{}' is also an input file; choose another output path",
+                out.display()
+            );
+        }
+    }
+    let header = tui_header.unwrap_or(initial_header);
+    let diff_section = if render.with_diff {
+        let diff = diffstat::patch(&diff_base, &paths)?;
+        (!diff.is_empty()).then(|| format!("=== Diff vs {diff_base} ===\n{diff}"))
+    } else {
+        None
+    };
+    let dest = destination_from_args(&args, &config);
+    let mut aggregator = build_aggregator(ignore.clone(), header)
+        .with_diff_section(diff_section)
+        .with_stream_cap(dest.requires_clipboard());
 
-    let header = tui_header.unwrap_or_else(|| render.header());
-    let fmt = formatter::build_formatter(render.format, header);
-    let mut aggregator = ContentAggregator::new(
-        fmt,
-        args.select.hidden,
-        expand_braces(args.select.ignore.clone()),
-        !args.select.no_sort,
-        allowed_extensions,
-    );
+    if let (
+        Some(budget),
+        Destination::File {
+            paths: targets,
+            gzip,
+            ..
+        },
+    ) = (args.output.chunk_by_tokens, &dest)
+    {
+        let chunks = plan_chunks(&mut aggregator, &paths, &args, budget, "--chunk-by-tokens")?;
+        println!(
+            "Wrote {} chunk{}:",
+            chunks.len(),
+            if chunks.len() == 1 { "" } else { "s" }
+        );
+        for (i, files) in chunks.iter().enumerate() {
+            let chunk_files: Vec<_> = targets.iter().map(|t| chunk_path(t, i + 1)).collect();
+            let files: Vec<String> = files
+                .iter()
+                .map(|f| f.to_string_lossy().into_owned())
+                .collect();
+            // Outliers were already dropped while measuring.
+            let mut chunk = build_aggregator(ignore.clone(), header).with_drop_outliers(false);
+            let chunk_dest = Destination::File {
+                paths: chunk_files.clone(),
+                gzip: *gzip,
+                append: false,
+            };
+            chunk_dest
+                .write_with_mime(mime, |w| aggregate_sanitized(&mut chunk, &files, render, w))?;
+            println!(
+                "  {}  {} tokens from {} file{}",
+                display_paths(&chunk_files),
+                token_counter::format_count(chunk.token_count()),
+                files.len(),
+                if files.len() == 1 { "" } else { "s" }
+            );
+        }
+        return Ok(());
+    }
 
-    let dest = destination_from_args(&args);
+    if let (Some(budget), Destination::Clipboard { .. }) = (args.output.clipboard_paginate, &dest) {
+        use std::io::IsTerminal;
+        if !std::io::stdin().is_terminal() {
+            anyhow::bail!(
+                "--clipboard-paginate waits for Enter between chunks and needs a terminal"
+            );
+        }
+        let chunks = plan_chunks(
+            &mut aggregator,
+            &paths,
+            &args,
+            budget,
+            "--clipboard-paginate",
+        )?;
+        for (i, files) in chunks.iter().enumerate() {
+            let files: Vec<String> = files
+                .iter()
+                .map(|f| f.to_string_lossy().into_owned())
+                .collect();
+            let mut chunk = build_aggregator(ignore.clone(), header)
+                .with_drop_outliers(false)
+                .with_stream_cap(true);
+            let mut buf = Vec::new();
+            aggregate_sanitized(&mut chunk, &files, render, &mut buf)?;
+            let (verb, place) = match deliver_to_clipboard(&buf, false, mime, &args.output)? {
+                Destination::File { paths, .. } => {
+                    ("Wrote", format!(" to {}", display_paths(&paths)))
+                }
+                Destination::Stdout => ("Printed", String::new()),
+                _ => ("Copied", String::new()),
+            };
+            eprintln!(
+                "{verb} chunk {}/{}{place}: {} tokens from {} file{}.",
+                i + 1,
+                chunks.len(),
+                token_counter::format_count(chunk.token_count()),
+                files.len(),
+                if files.len() == 1 { "" } else { "s" }
+            );
+            if i + 1 < chunks.len() {
+                eprint!("Paste it, then press Enter for the next chunk (q to stop): ");
+                let mut answer = String::new();
+                std::io::stdin().read_line(&mut answer)?;
+                if answer.trim().eq_ignore_ascii_case("q") {
+                    break;
+                }
+            }
+        }
+        return Ok(());
+    }
 
-    if dest.requires_clipboard() {
+    if dest.requires_clipboard() && !args.output.manifest_stdout && !args.output.report_json {
         let cwd = std::env::current_dir().ok();
         for p in &paths {
             let display = cwd
@@ -270,11 +1237,132 @@ fn main() -> Result<()> {
                 .unwrap_or_else(|| p.clone());
             println!("  {display}");
         }
+        if let Some(name) = &args.source.stdin_content {
+            println!("  {name} (stdin)");
+        }
+    }
+
+    let mut wc_counts = None;
+    let mut digest = None;
+    let mut written = 0;
+    let mut run = |w: &mut dyn std::io::Write| {
+        let mut hashing = None;
+        let w: &mut dyn std::io::Write = if args.output.hash {
+            hashing.insert(output_handler::HashingWriter::new(w))
+        } else {
+            w
+        };
+        let mut counting = None;
+        let w: &mut dyn std::io::Write = if args.output.report_json {
+            counting.insert(output_handler::CountingWriter::new(w))
+        } else {
+            w
+        };
+        let result = if args.output.as_uri_list {
+            aggregate_sanitized(&mut aggregator, &paths, render, &mut std::io::sink())?;
+            write_uri_list(aggregator.file_list(), w)?;
+            Ok(0)
+        } else if args.output.wc {
+            let mut counter = output_handler::CountingWriter::new(w);
+            let replaced = aggregate_sanitized(&mut aggregator, &paths, render, &mut counter)?;
+            wc_counts = Some(counter.counts());
+            Ok(replaced)
+        } else {
+            aggregate_sanitized(&mut aggregator, &paths, render, w)
+        };
+        written = counting.map_or(0, |c| c.counts().bytes);
+        digest = hashing.map(|h| h.hex());
+        result
+    };
+    let (ascii_replaced, dest) = match dest {
+        // Buffer first so the size is known and a failed copy can be redirected.
+        Destination::Clipboard { echo }
+            if args.output.fallback_stdout
+                || args.output.max_clipboard_size > 0
+                || has_clipboard_fallback(&args.output) =>
+        {
+            let mut buf = Vec::new();
+            let replaced = run(&mut buf)?;
+            let delivered = deliver_to_clipboard(&buf, echo, mime, &args.output)?;
+            (replaced, delivered)
+        }
+        _ => (dest.write_with_mime(mime, run)?, dest),
+    };
+    print_skip_summary(&aggregator, args.output.verbose);
+    if let Some(replacement) = ascii_only {
+        if ascii_replaced > 0 {
+            eprintln!(
+                "({} non-ASCII character{} {})",
+                token_counter::format_count(ascii_replaced),
+                if ascii_replaced == 1 { "" } else { "s" },
+                if replacement.is_empty() {
+                    "removed"
+                } else {
+                    "replaced"
+                }
+            );
+        }
+    }
+    if render.compact {
+        eprintln!(
+            "(--compact saved {} bytes)",
+            token_counter::format_count(aggregator.compact_saved() as usize)
+        );
+    }
+    if let Some(c) = wc_counts {
+        print_wc_counts(&c);
+    }
+    if let Some(hex) = digest {
+        eprintln!("sha256: {hex}");
+    }
+    if args.output.ext_summary {
+        print_ext_summary(&aggregator);
+    }
+    if args.output.line_counts {
+        print_line_counts(&aggregator);
+    }
+    if let Some(template) = &args.output.exec {
+        let files = aggregator.file_list();
+        let failed = exec::run(template, files)?;
+        if failed > 0 {
+            anyhow::bail!("--exec: {failed} of {} commands failed", files.len());
+        }
+    }
+    if args.output.as_uri_list {
+        if !args.output.report_json {
+            print_uri_list_summary(aggregator.file_list().len(), &dest);
+        }
+    } else {
+        if args.output.manifest_stdout {
+            print_manifest(aggregator.file_list(), args.output.list_preview);
+        }
+        print_aggregate_summary(
+            &aggregator,
+            &dest,
+            args.output.manifest_stdout || args.output.report_json,
+        );
+    }
+    if let Some(model) = &args.output.estimate_cost {
+        let tokens = aggregator.token_count();
+        if let Some(cost) = token_counter::estimate_cost(model, tokens) {
+            eprintln!(
+                "~{} tokens ≈ {cost} ({model} input)",
+                token_counter::format_count(tokens)
+            );
+        }
+    }
+
+    if args.output.report_json {
+        print_report_json(&dest, aggregator.file_count(), written);
     }
 
-    dest.write_with(|w| aggregator.aggregate_paths(&paths, w))?;
-    print_binary_skip_warning(&aggregator);
-    print_aggregate_summary(&aggregator, &dest);
+    if let Some(patterns) = clipboard_ignore {
+        let mut trimmed = build_aggregator(patterns, header).with_stream_cap(true);
+        let mut buf = Vec::new();
+        aggregate_sanitized(&mut trimmed, &paths, render, &mut buf)?;
+        let delivered = deliver_to_clipboard(&buf, false, mime, &args.output)?;
+        print_aggregate_summary(&trimmed, &delivered, args.output.manifest_stdout);
+    }
 
     Ok(())
 }
@@ -6,9 +6,11 @@ use serde_json::Value;
 pub const MAX_NOTEBOOK_BYTES: u64 = 50 * 1024 * 1024;
 
 /// Extract and concatenate the source of every code cell in a notebook,
-/// separated by `# %%` cell markers. Returns Err if the bytes aren't a
-/// valid notebook (no `cells` or `worksheets` array, invalid JSON, etc.).
-pub fn extract_notebook_code(content: &[u8]) -> Result<String> {
+/// separated by `# %%` cell markers. With `include_markdown`, markdown cells
+/// are kept in order as commented `# %% [markdown]` cells (jupytext percent
+/// format). Returns Err if the bytes aren't a valid notebook (no `cells` or
+/// `worksheets` array, invalid JSON, etc.).
+pub fn extract_notebook_code(content: &[u8], include_markdown: bool) -> Result<String> {
     let notebook: Value = serde_json::from_slice(content).context("invalid notebook JSON")?;
 
     // nbformat 4+: top-level `cells`. nbformat 2/3: cells nested under
@@ -27,9 +29,11 @@ pub fn extract_notebook_code(content: &[u8]) -> Result<String> {
 
     let mut out = String::new();
     for cell in cells {
-        if cell.get("cell_type").and_then(|v| v.as_str()) != Some("code") {
-            continue;
-        }
+        let is_markdown = match cell.get("cell_type").and_then(|v| v.as_str()) {
+            Some("code") => false,
+            Some("markdown") if include_markdown => true,
+            _ => continue,
+        };
         // nbformat 4 uses `source`; nbformat 2/3 uses `input`. Either may be a
         // string or an array of line strings (join with "" — lines keep their '\n').
         let raw = cell.get("source").or_else(|| cell.get("input"));
@@ -41,6 +45,20 @@ pub fn extract_notebook_code(content: &[u8]) -> Result<String> {
         if code.trim().is_empty() {
             continue;
         }
+        if is_markdown {
+            out.push_str("# %% [markdown]\n");
+            for line in code.lines() {
+                if line.is_empty() {
+                    out.push_str("#\n");
+                } else {
+                    out.push_str("# ");
+                    out.push_str(line);
+                    out.push('\n');
+                }
+            }
+            out.push('\n');
+            continue;
+        }
         out.push_str("# %%\n");
         out.push_str(&code);
         if !code.ends_with('\n') {
@@ -82,7 +100,7 @@ mod tests {
   ]
 }"##;
 
-        let result = extract_notebook_code(nb.as_bytes()).unwrap();
+        let result = extract_notebook_code(nb.as_bytes(), false).unwrap();
 
         // Both code cells present
         assert!(result.contains("import os"));
@@ -99,22 +117,40 @@ mod tests {
         assert!(!result.contains("raw cell text"));
     }
 
+    #[test]
+    fn test_markdown_cells_kept_in_order() {
+        let nb = r##"{"cells": [
+          {"cell_type": "markdown", "source": ["# Title\n", "\n", "Some prose."]},
+          {"cell_type": "code", "source": "x = 1"},
+          {"cell_type": "markdown", "source": "Done."}
+        ]}"##;
+
+        let result = extract_notebook_code(nb.as_bytes(), true).unwrap();
+
+        assert_eq!(
+            result,
+            "# %% [markdown]\n# # Title\n#\n# Some prose.\n\n\
+             # %%\nx = 1\n\n\
+             # %% [markdown]\n# Done.\n\n"
+        );
+    }
+
     #[test]
     fn test_no_code_cells() {
         let nb = r#"{"cells": [{"cell_type": "markdown", "source": "hello"}]}"#;
-        let result = extract_notebook_code(nb.as_bytes()).unwrap();
+        let result = extract_notebook_code(nb.as_bytes(), false).unwrap();
         assert!(result.contains("no code cells"));
     }
 
     #[test]
     fn test_invalid_json() {
-        let result = extract_notebook_code(b"not json at all");
+        let result = extract_notebook_code(b"not json at all", false);
         assert!(result.is_err());
     }
 
     #[test]
     fn test_missing_cells_array() {
-        let result = extract_notebook_code(br#"{"metadata": {}}"#);
+        let result = extract_notebook_code(br#"{"metadata": {}}"#, false);
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
@@ -141,7 +177,7 @@ mod tests {
   ]
 }"##;
 
-        let result = extract_notebook_code(nb.as_bytes()).unwrap();
+        let result = extract_notebook_code(nb.as_bytes(), false).unwrap();
 
         assert!(result.contains("import sys"));
         assert!(result.contains("import time"));
@@ -159,7 +195,7 @@ mod tests {
           {"cells": [{"cell_type": "code", "input": "y = 2"}]}
         ]}"#;
 
-        let result = extract_notebook_code(nb.as_bytes()).unwrap();
+        let result = extract_notebook_code(nb.as_bytes(), false).unwrap();
 
         assert!(result.contains("x = 1"));
         assert!(result.contains("y = 2"));
@@ -1,4 +1,5 @@
 use anyhow::Result;
+use sha2::{Digest, Sha256};
 use std::env;
 use std::io::{self, Write};
 
@@ -26,11 +27,261 @@ impl<A: Write, B: Write> Write for TeeWriter<'_, A, B> {
     }
 }
 
+/// One `--write` target, gzip-compressed or plain.
+enum FileTarget {
+    Plain(std::fs::File),
+    Gzip(flate2::write::GzEncoder<std::fs::File>),
+}
+
+impl FileTarget {
+    fn open(path: &std::path::Path, gzip: bool, append: bool) -> io::Result<Self> {
+        if gzip {
+            let file = std::fs::File::create(path)?;
+            Ok(FileTarget::Gzip(flate2::write::GzEncoder::new(
+                file,
+                flate2::Compression::default(),
+            )))
+        } else if append {
+            let file = std::fs::OpenOptions::new()
+                .append(true)
+                .create(true)
+                .open(path)?;
+            Ok(FileTarget::Plain(file))
+        } else {
+            Ok(FileTarget::Plain(std::fs::File::create(path)?))
+        }
+    }
+
+    fn finish(self) -> io::Result<()> {
+        match self {
+            FileTarget::Plain(_) => Ok(()),
+            FileTarget::Gzip(enc) => enc.finish().map(drop),
+        }
+    }
+}
+
+impl Write for FileTarget {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            FileTarget::Plain(f) => f.write(buf),
+            FileTarget::Gzip(enc) => enc.write(buf),
+        }
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            FileTarget::Plain(f) => f.flush(),
+            FileTarget::Gzip(enc) => enc.flush(),
+        }
+    }
+}
+
+/// Writes everything to each of several `--write` targets.
+struct FanOut<'a>(&'a mut [FileTarget]);
+
+impl Write for FanOut<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for target in self.0.iter_mut() {
+            target.write_all(buf)?;
+        }
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.iter_mut().try_for_each(Write::flush)
+    }
+}
+
+/// Pass-through writer that feeds everything written into a SHA-256 (for
+/// `--hash`).
+pub struct HashingWriter<'a> {
+    inner: &'a mut dyn Write,
+    hasher: Sha256,
+}
+
+impl<'a> HashingWriter<'a> {
+    pub fn new(inner: &'a mut dyn Write) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// Lowercase hex digest of the bytes written so far.
+    pub fn hex(&self) -> String {
+        self.hasher
+            .clone()
+            .finalize()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect()
+    }
+}
+
+impl Write for HashingWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// `wc`-style totals of everything written through a `CountingWriter`.
+#[derive(Clone, Copy, Default)]
+pub struct WcCounts {
+    pub lines: usize,
+    pub words: usize,
+    pub bytes: usize,
+}
+
+/// Pass-through writer that tallies lines, words and bytes (for `--wc`).
+/// Word state is carried across writes so split buffers don't double-count.
+pub struct CountingWriter<'a> {
+    inner: &'a mut dyn Write,
+    counts: WcCounts,
+    in_word: bool,
+}
+
+impl<'a> CountingWriter<'a> {
+    pub fn new(inner: &'a mut dyn Write) -> Self {
+        Self {
+            inner,
+            counts: WcCounts::default(),
+            in_word: false,
+        }
+    }
+
+    pub fn counts(&self) -> WcCounts {
+        self.counts
+    }
+}
+
+impl Write for CountingWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        for &b in &buf[..n] {
+            if b == b'\n' {
+                self.counts.lines += 1;
+            }
+            if b.is_ascii_whitespace() {
+                self.in_word = false;
+            } else if !self.in_word {
+                self.in_word = true;
+                self.counts.words += 1;
+            }
+        }
+        self.counts.bytes += n;
+        Ok(n)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Pass-through writer for `--ascii-only`: each non-ASCII character is replaced
+/// by `replacement` (empty drops it). Works on raw bytes, so one UTF-8 character
+/// is counted once even when split across writes.
+pub struct AsciiWriter<'a> {
+    inner: &'a mut dyn Write,
+    replacement: &'a [u8],
+    replaced: usize,
+    in_char: bool,
+}
+
+impl<'a> AsciiWriter<'a> {
+    pub fn new(inner: &'a mut dyn Write, replacement: &'a str) -> Self {
+        Self {
+            inner,
+            replacement: replacement.as_bytes(),
+            replaced: 0,
+            in_char: false,
+        }
+    }
+
+    pub fn replaced(&self) -> usize {
+        self.replaced
+    }
+}
+
+impl Write for AsciiWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut start = 0;
+        for (i, &b) in buf.iter().enumerate() {
+            if b.is_ascii() {
+                self.in_char = false;
+                continue;
+            }
+            self.inner.write_all(&buf[start..i])?;
+            start = i + 1;
+            // Continuation bytes (10xxxxxx) belong to the character already replaced.
+            let continuation = b & 0xC0 == 0x80;
+            if !(continuation && self.in_char) {
+                self.inner.write_all(self.replacement)?;
+                self.replaced += 1;
+            }
+            self.in_char = true;
+        }
+        self.inner.write_all(&buf[start..])?;
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Pass-through writer for `--no-final-newline`: a trailing `\n` is held back
+/// until more output follows, so only the very last one is dropped.
+pub struct TrimFinalNewline<'a> {
+    inner: &'a mut dyn Write,
+    pending: bool,
+}
+
+impl<'a> TrimFinalNewline<'a> {
+    pub fn new(inner: &'a mut dyn Write) -> Self {
+        Self {
+            inner,
+            pending: false,
+        }
+    }
+}
+
+impl Write for TrimFinalNewline<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.pending {
+            self.inner.write_all(b"\n")?;
+        }
+        let (body, pending) = match buf.split_last() {
+            Some((b'\n', rest)) => (rest, true),
+            _ => (buf, false),
+        };
+        self.inner.write_all(body)?;
+        self.pending = pending;
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
 impl Destination {
     pub fn write_with<R>(&self, f: impl FnOnce(&mut dyn Write) -> Result<R>) -> Result<R> {
+        self.write_with_mime(None, f)
+    }
+
+    /// Like `write_with`, but hints the clipboard content type (e.g. `text/markdown`)
+    /// to backends that support one. Other destinations ignore `mime`.
+    pub fn write_with_mime<R>(
+        &self,
+        mime: Option<&'static str>,
+        f: impl FnOnce(&mut dyn Write) -> Result<R>,
+    ) -> Result<R> {
         match self {
             Destination::Clipboard { echo } => {
-                let mut handler = OutputHandler::new();
+                let mut handler = OutputHandler::with_mime(mime);
                 let mut cw = handler.get_clipboard_writer()?;
                 let result = if *echo {
                     let stdout = io::stdout();
@@ -48,18 +299,20 @@ impl Destination {
                 cw.finish()?;
                 Ok(result)
             }
-            Destination::File { path, gzip } => {
-                if *gzip {
-                    let file = std::fs::File::create(path)?;
-                    let mut enc =
-                        flate2::write::GzEncoder::new(file, flate2::Compression::default());
-                    let r = f(&mut enc)?;
-                    enc.finish()?;
-                    Ok(r)
-                } else {
-                    let mut file = std::fs::File::create(path)?;
-                    f(&mut file)
+            Destination::File {
+                paths,
+                gzip,
+                append,
+            } => {
+                let mut targets = paths
+                    .iter()
+                    .map(|path| FileTarget::open(path, *gzip, *append))
+                    .collect::<io::Result<Vec<_>>>()?;
+                let r = f(&mut FanOut(&mut targets))?;
+                for target in targets {
+                    target.finish()?;
                 }
+                Ok(r)
             }
             Destination::Stdout => {
                 let stdout = io::stdout();
@@ -78,18 +331,39 @@ impl Destination {
     }
 }
 
+/// True when nothing could own a clipboard: no X11 or Wayland display and not
+/// WSL, on a platform other than macOS or Windows (e.g. a headless CI runner).
+pub fn is_headless() -> bool {
+    if cfg!(any(target_os = "macos", target_os = "windows")) {
+        return false;
+    }
+    let set = |var| env::var_os(var).is_some_and(|v| !v.is_empty());
+    !["DISPLAY", "WAYLAND_DISPLAY", "WSL_DISTRO_NAME", "WSL_ENV"]
+        .into_iter()
+        .any(set)
+}
+
 pub struct OutputHandler {
     backends: Vec<Box<dyn ClipboardBackend>>,
 }
 
 impl OutputHandler {
-    pub fn new() -> Self {
+    pub fn with_mime(mime: Option<&'static str>) -> Self {
         Self {
-            backends: Self::build_backend_chain(),
+            backends: Self::build_backend_chain(mime),
         }
     }
 
-    fn build_backend_chain() -> Vec<Box<dyn ClipboardBackend>> {
+    #[cfg_attr(
+        not(any(
+            target_os = "linux",
+            target_os = "freebsd",
+            target_os = "openbsd",
+            target_os = "netbsd"
+        )),
+        allow(unused_variables)
+    )]
+    fn build_backend_chain(mime: Option<&'static str>) -> Vec<Box<dyn ClipboardBackend>> {
         let mut chain: Vec<Box<dyn ClipboardBackend>> = Vec::new();
 
         #[cfg(target_os = "macos")]
@@ -106,7 +380,9 @@ impl OutputHandler {
         ))]
         {
             if env::var("WSL_DISTRO_NAME").is_ok() || env::var("WSL_ENV").is_ok() {
-                chain.push(Box::new(clipboard::WslBackend));
+                log::debug!("WSL detected; copying through the Windows clipboard");
+                let verify = env::var_os("CXT_VERIFY_CLIPBOARD").is_some_and(|v| v != "0");
+                chain.push(Box::new(clipboard::WslBackend::new(verify)));
                 return chain;
             }
 
@@ -114,15 +390,16 @@ impl OutputHandler {
                 .unwrap_or_default()
                 .to_lowercase();
             let wayland_display = env::var("WAYLAND_DISPLAY").unwrap_or_default();
+            log::debug!("XDG_SESSION_TYPE={session_type:?} WAYLAND_DISPLAY={wayland_display:?}");
 
             if session_type == "wayland" || !wayland_display.is_empty() {
-                chain.push(Box::new(WlCopyBackend));
+                chain.push(Box::new(WlCopyBackend { mime }));
                 push_clipboard_managers(&mut chain);
                 chain.push(Box::new(ArboardBackend::new()));
             } else {
                 chain.push(Box::new(ArboardBackend::new()));
                 push_clipboard_managers(&mut chain);
-                chain.push(Box::new(WlCopyBackend));
+                chain.push(Box::new(WlCopyBackend { mime }));
                 chain.push(Box::new(X11Backend));
             }
         }
@@ -140,14 +417,27 @@ impl OutputHandler {
         chain
     }
 
+    /// The backends this platform would try, in order, with whether each one
+    /// is usable right now (`--list-clipboard-backends`).
+    pub fn describe_backends() -> Vec<(String, bool)> {
+        Self::build_backend_chain(None)
+            .iter()
+            .map(|b| (b.name().to_string(), b.is_available()))
+            .collect()
+    }
+
     pub fn get_clipboard_writer(&mut self) -> Result<ClipboardWriter> {
         for mut backend in self.backends.drain(..) {
             if !backend.is_available() {
+                log::debug!("clipboard backend {}: not available", backend.name());
                 continue;
             }
             match backend.get_writer() {
-                Ok(writer) => return Ok(ClipboardWriter::new(writer, backend)),
-                Err(_) => continue,
+                Ok(writer) => {
+                    log::debug!("clipboard backend {}: selected", backend.name());
+                    return Ok(ClipboardWriter::new(writer, backend));
+                }
+                Err(e) => log::debug!("clipboard backend {}: failed to start: {e}", backend.name()),
             }
         }
         Err(anyhow::anyhow!(
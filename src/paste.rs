@@ -0,0 +1,405 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// What `cxt paste` does with a file that already exists.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Conflict {
+    Overwrite,
+    Append,
+    /// Prompt on a terminal, skip otherwise.
+    Ask,
+}
+
+/// One file recovered from cxt output.
+#[derive(Debug, PartialEq, Eq)]
+pub struct PastedFile {
+    pub path: String,
+    pub content: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Style {
+    Xml,
+    Markdown,
+}
+
+/// The path named by an XML (`<file path="...">`) or Markdown (`## File: ...`)
+/// header line, without any `-> target` suffix.
+fn header(line: &str) -> Option<(String, Style)> {
+    if let Some(rest) = line.strip_prefix("<file path=\"") {
+        let (path, _) = rest.split_once('"')?;
+        return Some((path.to_string(), Style::Xml));
+    }
+    // `--toc` numbers headers: "## File 7: path".
+    let rest = line.strip_prefix("## File")?;
+    let (number, rest) = rest.split_once(": ")?;
+    if !number.trim().chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let path = rest.split_once(" -> ").map_or(rest, |(path, _)| path);
+    Some((path.to_string(), Style::Markdown))
+}
+
+/// Split XML or Markdown cxt output into its files. A block runs to the last
+/// closing tag or fence before the next header, so content that itself holds
+/// fences survives; text outside blocks (preamble, diff section) is ignored.
+pub fn parse(text: &str) -> Vec<PastedFile> {
+    let text = text.replace("\r\n", "\n");
+    let lines: Vec<&str> = text.lines().collect();
+    let starts: Vec<(usize, String, Style)> = lines
+        .iter()
+        .enumerate()
+        .filter_map(|(i, line)| header(line).map(|(path, style)| (i, path, style)))
+        .collect();
+    let mut files = Vec::new();
+    for (k, (start, path, style)) in starts.iter().enumerate() {
+        let end = starts.get(k + 1).map_or(lines.len(), |next| next.0);
+        let mut body = &lines[start + 1..end];
+        let closer = match style {
+            Style::Xml => "</file>",
+            Style::Markdown => {
+                let Some(open) = body.iter().position(|l| l.starts_with("```")) else {
+                    continue;
+                };
+                body = &body[open + 1..];
+                "```"
+            }
+        };
+        let Some(close) = body.iter().rposition(|l| *l == closer) else {
+            continue;
+        };
+        let content = body[..close].iter().map(|l| format!("{l}\n")).collect();
+        files.push(PastedFile {
+            path: path.clone(),
+            content,
+        });
+    }
+    files
+}
+
+/// Where a pasted `path` lands under `root`. Absolute paths are kept only
+/// when they lie inside `cwd` (and are then taken relative to it); paths that
+/// climb out with `..` are refused. This only reads the path's text, so a
+/// symlink already under `root` can still lead out; `leaves_root` checks that.
+fn destination(root: &Path, cwd: &Path, path: &str) -> Option<PathBuf> {
+    let path = Path::new(path);
+    let relative = if path.is_absolute() {
+        path.strip_prefix(cwd).ok()?
+    } else {
+        path
+    };
+    let safe = relative
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    (safe && relative.components().next().is_some()).then(|| root.join(relative))
+}
+
+/// Whether writing `dest` would leave `root` on disk: `dest` is itself a
+/// symlink, or its nearest existing directory resolves outside `root`.
+fn leaves_root(root: &Path, dest: &Path) -> Result<bool> {
+    if fs::symlink_metadata(dest).is_ok_and(|m| m.file_type().is_symlink()) {
+        return Ok(true);
+    }
+    let root = match root.canonicalize() {
+        Ok(root) => root,
+        // Nothing under a missing root exists yet, so nothing can redirect.
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e).with_context(|| format!("Failed to resolve {}", root.display())),
+    };
+    let mut dir = dest.parent();
+    while let Some(current) = dir {
+        match current.canonicalize() {
+            Ok(real) => return Ok(!real.starts_with(&root)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => dir = current.parent(),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to resolve {}", current.display()))
+            }
+        }
+    }
+    Ok(true)
+}
+
+/// Where `file` lands under `root`, or None (after a warning) when its path
+/// or a symlink on the way leads out of `root`.
+fn checked_destination(root: &Path, cwd: &Path, file: &PastedFile) -> Result<Option<PathBuf>> {
+    let Some(dest) = destination(root, cwd, &file.path) else {
+        eprintln!(
+            "Warning: skipping '{}': outside the target directory",
+            file.path
+        );
+        return Ok(None);
+    };
+    if leaves_root(root, &dest)? {
+        eprintln!(
+            "Warning: skipping '{}': a symlink leads outside the target directory",
+            file.path
+        );
+        return Ok(None);
+    }
+    Ok(Some(dest))
+}
+
+/// Unified diffs from the files on disk under `root` to the pasted `files`,
+/// followed by a summary marking each file modified (M), added (A) or
+/// unchanged; this is what `cxt paste --overwrite` would change.
+pub fn review(files: &[PastedFile], root: &Path) -> Result<String> {
+    let cwd = std::env::current_dir()?;
+    let mut diffs = String::new();
+    let mut summary = Vec::new();
+    let (mut modified, mut added, mut unchanged) = (0, 0, 0);
+    for file in files {
+        let Some(dest) = checked_destination(root, &cwd, file)? else {
+            continue;
+        };
+        let on_disk = match fs::read(&dest) {
+            Ok(bytes) => Some(String::from_utf8_lossy(&bytes).into_owned()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", dest.display())),
+        };
+        let old = on_disk.as_deref().unwrap_or("");
+        if on_disk.is_some() && old == file.content {
+            unchanged += 1;
+            continue;
+        }
+        // Lines keep their endings so a missing final newline shows up.
+        let before: Vec<&str> = old.split_inclusive('\n').collect();
+        let after: Vec<&str> = file.content.split_inclusive('\n').collect();
+        let from = match on_disk {
+            Some(_) => format!("a/{}", file.path),
+            None => "/dev/null".to_string(),
+        };
+        let hunks = difflib::unified_diff(
+            &before,
+            &after,
+            &from,
+            &format!("b/{}", file.path),
+            "",
+            "",
+            3,
+        );
+        for (i, line) in hunks.iter().enumerate() {
+            // The ---/+++ lines carry an empty date after a tab.
+            if i < 2 {
+                diffs.push_str(line.trim_end());
+                diffs.push('\n');
+            } else if line.ends_with('\n') {
+                diffs.push_str(line);
+            } else {
+                diffs.push_str(line);
+                diffs.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+        let (mark, count) = match on_disk {
+            Some(_) => ("M", &mut modified),
+            None => ("A", &mut added),
+        };
+        *count += 1;
+        summary.push(format!("  {mark} {}", file.path));
+    }
+    if !diffs.is_empty() {
+        diffs.push('\n');
+    }
+    diffs.push_str(&format!(
+        "{modified} modified, {added} added, {unchanged} unchanged\n"
+    ));
+    for line in summary {
+        diffs.push_str(&line);
+        diffs.push('\n');
+    }
+    Ok(diffs)
+}
+
+fn ask(path: &Path) -> Result<Option<Conflict>> {
+    eprint!("{} exists: [o]verwrite, [a]ppend, [s]kip? ", path.display());
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(match answer.trim().to_ascii_lowercase().as_str() {
+        "o" | "overwrite" => Some(Conflict::Overwrite),
+        "a" | "append" => Some(Conflict::Append),
+        _ => None,
+    })
+}
+
+/// Write `files` under `root`, creating parent directories, and print one
+/// line per file written. Returns how many were written.
+pub fn write_files(files: &[PastedFile], root: &Path, conflict: Conflict) -> Result<usize> {
+    let cwd = std::env::current_dir()?;
+    let interactive = atty::is(atty::Stream::Stdin);
+    let mut written = 0;
+    for file in files {
+        let Some(dest) = checked_destination(root, &cwd, file)? else {
+            continue;
+        };
+        let existed = dest.exists();
+        let action = if !existed {
+            Conflict::Overwrite
+        } else if conflict != Conflict::Ask {
+            conflict
+        } else if !interactive {
+            eprintln!(
+                "Warning: skipping '{}': it exists (pass --overwrite or --append)",
+                dest.display()
+            );
+            continue;
+        } else {
+            match ask(&dest)? {
+                Some(action) => action,
+                None => continue,
+            }
+        };
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+            // Re-check now that the directories exist.
+            let real = parent
+                .canonicalize()
+                .with_context(|| format!("Failed to resolve {}", parent.display()))?;
+            if !real.starts_with(root.canonicalize()?) {
+                eprintln!(
+                    "Warning: skipping '{}': a symlink leads outside the target directory",
+                    file.path
+                );
+                continue;
+            }
+        }
+        let mut options = fs::OpenOptions::new();
+        match action {
+            Conflict::Append => options.append(true),
+            // A file that wasn't there is created fresh, never through a link.
+            _ if !existed => options.write(true).create_new(true),
+            _ => options.write(true).truncate(true),
+        };
+        let result = options
+            .open(&dest)
+            .and_then(|mut f| f.write_all(file.content.as_bytes()));
+        result.with_context(|| format!("Failed to write {}", dest.display()))?;
+        let verb = if action == Conflict::Append {
+            "Appended to"
+        } else {
+            "Wrote"
+        };
+        println!("{verb} {}", dest.display());
+        written += 1;
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_both_formats_and_keeps_inner_fences() {
+        let xml = "<context>\n<file path=\"src/a.rs\">\nfn a() {}\n</file>\n\n\
+                   <file path=\"b.txt\" meta=\"1B\">\n</file>\n</context>\n";
+        assert_eq!(
+            parse(xml),
+            [
+                PastedFile {
+                    path: "src/a.rs".into(),
+                    content: "fn a() {}\n".into()
+                },
+                PastedFile {
+                    path: "b.txt".into(),
+                    content: String::new()
+                },
+            ]
+        );
+        let md = "# Generated by: cxt\n\n## File: README.md -> docs/README.md\n\n```markdown\n\
+                  # T\n```\nx\n```\n```\n\n## File: a.py\n\n(12B)\n\n```python\npass\n```\n";
+        let files = parse(md);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, "README.md");
+        assert_eq!(files[0].content, "# T\n```\nx\n```\n");
+        assert_eq!(files[1].content, "pass\n");
+    }
+
+    #[test]
+    fn review_diffs_against_disk_and_summarizes() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("same.txt"), "keep\n").unwrap();
+        fs::write(dir.path().join("edit.txt"), "one\ntwo\n").unwrap();
+        let pasted = |path: &str, content: &str| PastedFile {
+            path: path.into(),
+            content: content.into(),
+        };
+        let files = [
+            pasted("same.txt", "keep\n"),
+            pasted("edit.txt", "one\n2\n"),
+            pasted("new.txt", "fresh\n"),
+        ];
+        let text = review(&files, dir.path()).unwrap();
+        assert!(text.contains("--- a/edit.txt\n+++ b/edit.txt\n@@ -1,2 +1,2 @@\n one\n-two\n+2\n"));
+        assert!(text.contains("--- /dev/null\n+++ b/new.txt\n"));
+        assert!(!text.contains("same.txt\n@@"));
+        assert!(text.ends_with("1 modified, 1 added, 1 unchanged\n  M edit.txt\n  A new.txt\n"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_under_the_root_cannot_lead_out() {
+        let outside = tempfile::tempdir().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(outside.path().join("victim.txt"), "safe\n").unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("link")).unwrap();
+        std::os::unix::fs::symlink(
+            outside.path().join("victim.txt"),
+            dir.path().join("alias.txt"),
+        )
+        .unwrap();
+        let pasted = |path: &str| PastedFile {
+            path: path.into(),
+            content: "owned\n".into(),
+        };
+        let files = [
+            pasted("link/victim.txt"),
+            pasted("link/new/deep.txt"),
+            pasted("alias.txt"),
+            pasted("inside.txt"),
+        ];
+        let text = review(&files, dir.path()).unwrap();
+        assert!(text.ends_with("0 modified, 1 added, 0 unchanged\n  A inside.txt\n"));
+        assert_eq!(
+            write_files(&files, dir.path(), Conflict::Overwrite).unwrap(),
+            1
+        );
+        assert_eq!(
+            fs::read_to_string(outside.path().join("victim.txt")).unwrap(),
+            "safe\n"
+        );
+        assert!(!outside.path().join("new").exists());
+    }
+
+    #[test]
+    fn review_marks_a_missing_final_newline() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), "one\ntwo").unwrap();
+        let files = [PastedFile {
+            path: "a.txt".into(),
+            content: "one\ntwo\n".into(),
+        }];
+        let text = review(&files, dir.path()).unwrap();
+        assert!(text.contains("@@ -1,2 +1,2 @@\n one\n-two\n\\ No newline at end of file\n+two\n"));
+        assert!(text.ends_with("1 modified, 0 added, 0 unchanged\n  M a.txt\n"));
+    }
+
+    #[test]
+    fn destinations_stay_inside_the_root() {
+        let root = Path::new("/out");
+        let cwd = Path::new("/work");
+        assert_eq!(
+            destination(root, cwd, "src/a.rs"),
+            Some(PathBuf::from("/out/src/a.rs"))
+        );
+        assert_eq!(
+            destination(root, cwd, "/work/src/a.rs"),
+            Some(PathBuf::from("/out/src/a.rs"))
+        );
+        assert_eq!(destination(root, cwd, "/etc/passwd"), None);
+        assert_eq!(destination(root, cwd, "../escape"), None);
+        assert_eq!(destination(root, cwd, "src/../../escape"), None);
+    }
+}
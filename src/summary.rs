@@ -0,0 +1,246 @@
+use std::io::Read;
+use std::path::Path;
+
+/// How much of a file is looked at; summaries come from its top.
+const SCAN_BYTES: usize = 16 * 1024;
+/// Longer summaries are cut here with an ellipsis.
+const MAX_CHARS: usize = 100;
+
+/// Comment syntax families, picked by extension.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Style {
+    /// `//`, `/* */`, `/** */` (plus Rust's `//!` and `///`).
+    CLike,
+    /// `#` comments and `"""` / `'''` docstrings.
+    Python,
+    /// `#` comments only.
+    Hash,
+    /// `--` comments.
+    DoubleDash,
+    /// A `#` heading is the summary.
+    Markdown,
+    /// `<!-- -->` comments.
+    Markup,
+    /// No known comments: first meaningful line.
+    Plain,
+}
+
+fn style_for(path: &Path) -> Style {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "rs" | "c" | "h" | "cc" | "cpp" | "hpp" | "cxx" | "js" | "jsx" | "mjs" | "ts" | "tsx"
+        | "java" | "kt" | "kts" | "go" | "swift" | "cs" | "scala" | "php" | "dart" | "css"
+        | "scss" | "zig" => Style::CLike,
+        "py" | "pyi" => Style::Python,
+        "sh" | "bash" | "zsh" | "fish" | "rb" | "pl" | "r" | "toml" | "yaml" | "yml" | "nix"
+        | "ps1" | "cmake" | "mk" => Style::Hash,
+        "lua" | "sql" | "hs" | "elm" | "ada" => Style::DoubleDash,
+        "md" | "markdown" => Style::Markdown,
+        "html" | "htm" | "xml" | "svg" | "vue" => Style::Markup,
+        _ if path
+            .file_name()
+            .is_some_and(|n| n == "Makefile" || n == "Dockerfile") =>
+        {
+            Style::Hash
+        }
+        _ => Style::Plain,
+    }
+}
+
+/// `text` as a single comment line in `path`'s own syntax (`// main.rs`,
+/// `-- schema.sql`, `<!-- index.html -->`), `# text` when the type is unknown.
+pub fn comment_line(path: &Path, text: &str) -> String {
+    match style_for(path) {
+        Style::CLike => format!("// {text}"),
+        Style::DoubleDash => format!("-- {text}"),
+        Style::Markdown | Style::Markup => format!("<!-- {text} -->"),
+        Style::Python | Style::Hash | Style::Plain => format!("# {text}"),
+    }
+}
+
+/// One-line description of a file for `--summary-only`: its leading doc
+/// comment or docstring, a Markdown title, or else its first meaningful line.
+pub fn summarize(path: &Path, content: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(&content[..content.len().min(SCAN_BYTES)]);
+    let style = style_for(path);
+    let found = match style {
+        Style::Markdown => text
+            .lines()
+            .find_map(|l| l.trim().strip_prefix('#'))
+            .map(|h| h.trim_start_matches('#').trim().to_string()),
+        Style::Plain => None,
+        _ => leading_comment(&text, style),
+    };
+    found
+        .filter(|s| !s.is_empty())
+        .or_else(|| first_meaningful_line(&text))
+        .map(|s| truncate(&s))
+}
+
+/// Like `summarize`, reading the top of the file from disk (for files too
+/// large to have been loaded).
+pub fn summarize_file(path: &Path) -> Option<String> {
+    let mut head = Vec::with_capacity(SCAN_BYTES);
+    std::fs::File::open(path)
+        .ok()?
+        .take(SCAN_BYTES as u64)
+        .read_to_end(&mut head)
+        .ok()?;
+    summarize(path, &head)
+}
+
+/// First line of text of the comment block at the top of the file, skipping
+/// shebangs, encoding lines and blank lines.
+fn leading_comment(text: &str, style: Style) -> Option<String> {
+    let mut lines = text
+        .lines()
+        .map(str::trim)
+        .skip_while(|l| l.is_empty() || l.starts_with("#!") || l.contains("-*- coding"));
+    let first = lines.next()?;
+    match style {
+        Style::CLike => {
+            if first.starts_with("/*") {
+                let open = if first.starts_with("/**") {
+                    "/**"
+                } else {
+                    "/*"
+                };
+                return block_comment(first, lines, open, "*/");
+            }
+            line_comment(first, lines, &["//!", "///", "//"])
+        }
+        Style::Python => {
+            for quote in ["\"\"\"", "'''"] {
+                if first.starts_with(quote) {
+                    return block_comment(first, lines, quote, quote);
+                }
+            }
+            line_comment(first, lines, &["#"])
+        }
+        Style::Hash => line_comment(first, lines, &["#"]),
+        Style::DoubleDash => line_comment(first, lines, &["--"]),
+        Style::Markup => {
+            let first = if first.starts_with("<?xml") || first.starts_with("<!DOCTYPE") {
+                lines.next()?
+            } else {
+                first
+            };
+            block_comment(first, lines, "<!--", "-->")
+        }
+        Style::Markdown | Style::Plain => None,
+    }
+}
+
+/// Text of the first non-empty line of a block comment opened by `open` on
+/// `first`, ignoring leading `*` decoration.
+fn block_comment<'a>(
+    first: &'a str,
+    rest: impl Iterator<Item = &'a str>,
+    open: &str,
+    close: &str,
+) -> Option<String> {
+    let body = first.strip_prefix(open)?;
+    std::iter::once(body)
+        .chain(rest)
+        .map(|l| l.split(close).next().unwrap_or(l).trim())
+        .map(|l| l.trim_start_matches('*').trim())
+        .find(|l| !l.is_empty())
+        .map(str::to_string)
+}
+
+/// Text of the first non-empty line in a run of `markers` comments starting
+/// at `first`.
+fn line_comment<'a>(
+    first: &'a str,
+    rest: impl Iterator<Item = &'a str>,
+    markers: &[&str],
+) -> Option<String> {
+    let strip = |l: &'a str| markers.iter().find_map(|m| l.strip_prefix(m));
+    std::iter::once(first)
+        .chain(rest)
+        .map_while(strip)
+        .map(|l| l.trim_start_matches(['/', '!', '#', '-']).trim())
+        .find(|l| !l.is_empty())
+        .map(str::to_string)
+}
+
+/// First line with a letter or digit in it, e.g. not a lone `{` or `---`.
+fn first_meaningful_line(text: &str) -> Option<String> {
+    text.lines()
+        .map(str::trim)
+        .find(|l| !l.starts_with("#!") && l.chars().any(char::is_alphanumeric))
+        .map(str::to_string)
+}
+
+fn truncate(s: &str) -> String {
+    if s.chars().count() <= MAX_CHARS {
+        return s.to_string();
+    }
+    let cut: String = s.chars().take(MAX_CHARS - 1).collect();
+    format!("{}…", cut.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(name: &str, content: &str) -> Option<String> {
+        summarize(Path::new(name), content.as_bytes())
+    }
+
+    #[test]
+    fn comment_line_uses_the_file_comment_syntax() {
+        let line = |name| comment_line(Path::new(name), name);
+        assert_eq!(line("main.rs"), "// main.rs");
+        assert_eq!(line("script.py"), "# script.py");
+        assert_eq!(line("schema.sql"), "-- schema.sql");
+        assert_eq!(line("index.html"), "<!-- index.html -->");
+        assert_eq!(line("notes.unknown"), "# notes.unknown");
+    }
+
+    #[test]
+    fn picks_language_specific_leading_comments() {
+        assert_eq!(
+            summary("lib.rs", "//! Fast path walker.\n//! More.\nuse std::fs;\n").as_deref(),
+            Some("Fast path walker.")
+        );
+        assert_eq!(
+            summary("a.ts", "/**\n * Parses configs.\n */\nexport {}").as_deref(),
+            Some("Parses configs.")
+        );
+        assert_eq!(
+            summary(
+                "m.py",
+                "#!/usr/bin/env python\n\"\"\"Build helpers.\"\"\"\nimport os"
+            )
+            .as_deref(),
+            Some("Build helpers.")
+        );
+        assert_eq!(
+            summary("run.sh", "#!/bin/sh\n# Deploy the site\nset -e\n").as_deref(),
+            Some("Deploy the site")
+        );
+        assert_eq!(
+            summary("q.sql", "-- Monthly totals\nSELECT 1;").as_deref(),
+            Some("Monthly totals")
+        );
+        assert_eq!(
+            summary("README.md", "\n# cxt: Context Extractor\n").as_deref(),
+            Some("cxt: Context Extractor")
+        );
+    }
+
+    #[test]
+    fn falls_back_to_first_meaningful_line() {
+        assert_eq!(
+            summary("main.rs", "\n{\nfn main() {}\n").as_deref(),
+            Some("fn main() {}")
+        );
+        assert_eq!(summary("data.txt", "\n\n---\n").as_deref(), None);
+        let long = "x".repeat(150);
+        assert_eq!(summary("a.txt", &long).unwrap().chars().count(), MAX_CHARS);
+    }
+}
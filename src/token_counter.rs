@@ -22,6 +22,33 @@ impl TokenCounter {
     }
 }
 
+/// Input price in USD per million tokens, by model (`--estimate-cost`).
+/// List prices at the time of writing; update as providers change them.
+pub const INPUT_PRICES: &[(&str, f64)] = &[
+    ("gpt-4o", 2.50),
+    ("gpt-4o-mini", 0.15),
+    ("gpt-4.1", 2.00),
+    ("gpt-4.1-mini", 0.40),
+    ("o3", 2.00),
+    ("claude-opus-4", 15.00),
+    ("claude-sonnet-4", 3.00),
+    ("claude-haiku-3.5", 0.80),
+    ("gemini-2.5-pro", 1.25),
+    ("gemini-2.5-flash", 0.30),
+];
+
+/// Approximate input cost of `tokens` for `model`, e.g. "$0.04"; None for
+/// models missing from `INPUT_PRICES`.
+pub fn estimate_cost(model: &str, tokens: usize) -> Option<String> {
+    let (_, per_million) = INPUT_PRICES.iter().find(|(name, _)| *name == model)?;
+    let cost = tokens as f64 * per_million / 1_000_000.0;
+    Some(if cost > 0.0 && cost < 0.01 {
+        format!("${cost:.4}")
+    } else {
+        format!("${cost:.2}")
+    })
+}
+
 /// Fast approximation: source code averages ~4 bytes per token.
 pub fn estimate_from_bytes(byte_count: u64) -> usize {
     (byte_count / 4) as usize
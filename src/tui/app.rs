@@ -4,13 +4,31 @@ use std::{
     collections::{HashMap, HashSet},
     env, fs, io,
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    time::{Duration, Instant, SystemTime},
 };
 
+/// Keystrokes arriving within this window are coalesced into a single walk.
+const SEARCH_DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// Entries read before a directory is first shown; the rest stream in behind it.
+const DIR_FIRST_BATCH: usize = 1_000;
+/// Entries per batch once a large directory is loading in the background.
+const DIR_BATCH: usize = 5_000;
+
+/// How much of a file the built-in preview reads.
+const PREVIEW_BYTES: u64 = 64 * 1024;
+
 #[derive(Clone, PartialEq, Eq)]
 pub enum AppMode {
     Normal,
     SearchFocused,
     SearchNavigating,
+    /// Typing an in-directory filter (`f`); the tree stays in place.
+    Filtering,
     GitTree,
 }
 
@@ -23,12 +41,76 @@ pub struct SearchResult {
     pub match_indices: Vec<usize>,
 }
 
+/// An in-flight background search walk. Setting `cancel` makes the worker
+/// stop at the next entry; dropping `rx` discards whatever it would have sent.
+/// `found` counts matches so far, `shown` is the count last drawn.
+struct SearchJob {
+    cancel: Arc<AtomicBool>,
+    found: Arc<AtomicUsize>,
+    shown: usize,
+    rx: mpsc::Receiver<Vec<SearchResult>>,
+}
+
+/// The rest of a large directory, still being read by `spawn_dir_reader`.
+/// Dropping `rx` makes the worker stop at its next batch.
+struct DirLoadJob {
+    dir: PathBuf,
+    rx: mpsc::Receiver<Vec<DirItem>>,
+}
+
+/// A `--preview-command` run for `path` on a worker thread. Dropping `rx`
+/// discards its output, so a cursor that has moved on never sees it.
+struct PreviewJob {
+    path: PathBuf,
+    rx: mpsc::Receiver<String>,
+}
+
+/// Preview pane contents for `path`; `text` may carry ANSI styling when it
+/// came from `--preview-command`.
+pub struct Preview {
+    pub path: PathBuf,
+    pub text: String,
+}
+
 #[derive(Clone)]
 pub struct GitCommit {
     pub display: String,
     pub hash: String,
 }
 
+/// Plain preview: a directory's entries, or the top of a text file.
+fn builtin_preview(path: &Path) -> String {
+    if path.is_dir() {
+        let mut names: Vec<String> = fs::read_dir(path)
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .map(|e| {
+                        let name = e.file_name().to_string_lossy().into_owned();
+                        if e.file_type().is_ok_and(|t| t.is_dir()) {
+                            format!("{name}/")
+                        } else {
+                            name
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        names.sort();
+        return names.join("\n");
+    }
+    let mut head = Vec::new();
+    let read = fs::File::open(path).and_then(|f| {
+        use std::io::Read;
+        f.take(PREVIEW_BYTES).read_to_end(&mut head)
+    });
+    match read {
+        Err(e) => format!("(cannot read: {e})"),
+        Ok(_) if head.contains(&0) => "(binary file)".to_string(),
+        Ok(_) => String::from_utf8_lossy(&head).into_owned(),
+    }
+}
+
 /// Detect whether `dir` is inside a git work-tree by walking up for a `.git` entry.
 fn is_git_repo(dir: &Path) -> bool {
     let mut current = dir;
@@ -43,11 +125,43 @@ fn is_git_repo(dir: &Path) -> bool {
     }
 }
 
+/// Order of entries within each directory; directories always come first.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SortMode {
+    Name,
+    /// Newest first.
+    Modified,
+    /// Largest first.
+    Size,
+}
+
+impl SortMode {
+    pub fn next(self) -> Self {
+        match self {
+            SortMode::Name => SortMode::Modified,
+            SortMode::Modified => SortMode::Size,
+            SortMode::Size => SortMode::Name,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortMode::Name => "name",
+            SortMode::Modified => "modified",
+            SortMode::Size => "size",
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct DirItem {
     path: PathBuf,
     file_name: std::ffi::OsString,
     is_dir: bool,
+    modified: Option<SystemTime>,
+    size: u64,
+    /// Where the entry points, as stored in the link, when it is a symlink.
+    symlink_target: Option<PathBuf>,
 }
 impl DirItem {
     pub fn path(&self) -> PathBuf {
@@ -59,28 +173,56 @@ impl DirItem {
     pub fn is_dir(&self) -> bool {
         self.is_dir
     }
+    pub fn symlink_target(&self) -> Option<&Path> {
+        self.symlink_target.as_deref()
+    }
 }
 
 pub struct AppState {
+    /// Directory shown as the tree root; empty at the virtual root of a multi-root session.
     pub root_dir: PathBuf,
+    /// Starting directories of a multi-root session (`cxt --tui a/ b/`); empty otherwise.
+    pub roots: Vec<PathBuf>,
+    /// Canonical starting directories, for spotting symlinks that lead outside them.
+    start_roots: Vec<PathBuf>,
     pub tree_state: tui_tree_widget::TreeState<PathBuf>,
     pub dir_cache: HashMap<PathBuf, Vec<DirItem>>,
+    /// Directories whose entries are still arriving in `dir_cache`.
+    dir_loads: Vec<DirLoadJob>,
     pub root_history: Vec<PathBuf>,
     pub selected: HashSet<PathBuf>,
     pub relative: bool,
     pub no_path: bool,
     pub respect_gitignore: bool,
+    pub sort_mode: SortMode,
     pub show_help: bool,
+    pub show_preview: bool,
+    /// `--preview-command`; the built-in reader is used when unset or failing.
+    pub preview_command: Option<String>,
+    pub preview: Option<Preview>,
+    /// Set by `Ctrl-y`; the event loop copies the selection and clears it.
+    pub copy_requested: bool,
+    /// Set by a first `C`; a second `C` right after clears the selection.
+    pub clear_armed: bool,
     pub search_history: HashMap<PathBuf, (String, Vec<SearchResult>)>,
     pub mode: AppMode,
+    /// Narrows the entries of `root_dir` to names containing it (case-insensitive).
+    pub dir_filter: String,
     pub search_query: String,
+    /// When false, search only matches direct children of `root_dir`.
+    pub search_recursive: bool,
     pub search_results: Vec<SearchResult>,
+    search_job: Option<SearchJob>,
+    preview_job: Option<PreviewJob>,
+    search_requested_at: Option<Instant>,
     pub search_cursor: usize,
     pub search_scroll_offset: usize,
     pub visible_height: usize,
     pub original_cursor: usize,
     pub original_scroll_offset: usize,
     pub list_area: Option<ratatui::layout::Rect>,
+    /// Clickable path-bar segments from the last draw: (row, x range, levels up).
+    pub breadcrumb_hits: Vec<(u16, std::ops::Range<u16>, usize)>,
     selected_file_count_cache: Option<usize>,
     selected_loc_cache: Option<u64>,
     pub git_commits: Vec<GitCommit>,
@@ -98,37 +240,75 @@ pub struct AppState {
     pub git_diff_scroll_offset: usize,
     pub git_diff_cursor: usize,
     dir_select_cache: RefCell<HashMap<PathBuf, bool>>,
-    matcher: fuzzy_matcher::skim::SkimMatcherV2,
+    dir_count_cache: RefCell<HashMap<PathBuf, usize>>,
 }
 
 impl AppState {
-    pub fn new(relative: bool, no_path: bool) -> io::Result<Self> {
-        let root_dir = env::current_dir()?;
-        let respect_gitignore = is_git_repo(&root_dir);
+    /// Start in the current directory, in `start_dirs[0]` if only one is given,
+    /// or at a virtual root listing all of them.
+    pub fn new(relative: bool, no_path: bool, start_dirs: Vec<PathBuf>) -> io::Result<Self> {
         let mut dir_cache = HashMap::new();
-        let root_entries = read_dir_sorted(&root_dir, respect_gitignore)?;
-        dir_cache.insert(root_dir.clone(), root_entries);
+        let mut dir_loads = Vec::new();
+        let (root_dir, roots, respect_gitignore) = if start_dirs.len() > 1 {
+            let respect_gitignore = start_dirs.iter().any(|d| is_git_repo(d));
+            let entries = start_dirs.iter().map(|d| root_item(d)).collect();
+            dir_cache.insert(PathBuf::new(), entries);
+            (PathBuf::new(), start_dirs, respect_gitignore)
+        } else {
+            let root_dir = match start_dirs.into_iter().next() {
+                Some(dir) => dir,
+                None => env::current_dir()?,
+            };
+            let respect_gitignore = is_git_repo(&root_dir);
+            let (root_entries, job) = load_dir(&root_dir, respect_gitignore, SortMode::Name);
+            dir_cache.insert(root_dir.clone(), root_entries);
+            dir_loads.extend(job);
+            (root_dir, Vec::new(), respect_gitignore)
+        };
 
+        let start_roots = if roots.is_empty() {
+            std::slice::from_ref(&root_dir)
+        } else {
+            &roots[..]
+        }
+        .iter()
+        .filter_map(|d| d.canonicalize().ok())
+        .collect();
         let mut app = Self {
             root_dir,
+            roots,
+            start_roots,
             tree_state: tui_tree_widget::TreeState::default(),
             dir_cache,
+            dir_loads,
             root_history: Vec::new(),
             selected: HashSet::new(),
             relative,
             no_path,
             respect_gitignore,
+            sort_mode: SortMode::Name,
             show_help: false,
+            show_preview: false,
+            preview_command: None,
+            preview: None,
+            copy_requested: false,
+            clear_armed: false,
             search_history: HashMap::new(),
             mode: AppMode::Normal,
+            dir_filter: String::new(),
             search_query: String::new(),
+            search_recursive: true,
             search_results: Vec::new(),
+            search_job: None,
+            preview_job: None,
+            search_requested_at: None,
             search_cursor: 0,
             search_scroll_offset: 0,
             visible_height: 10,
             original_cursor: 0,
             original_scroll_offset: 0,
             list_area: None,
+            breadcrumb_hits: Vec::new(),
             selected_file_count_cache: None,
             selected_loc_cache: None,
             git_commits: Vec::new(),
@@ -146,12 +326,17 @@ impl AppState {
             git_diff_scroll_offset: 0,
             git_diff_cursor: 0,
             dir_select_cache: RefCell::new(HashMap::new()),
-            matcher: fuzzy_matcher::skim::SkimMatcherV2::default(),
+            dir_count_cache: RefCell::new(HashMap::new()),
         };
         app.select_first_entry();
         Ok(app)
     }
 
+    /// True when the tree shows the list of starting directories.
+    pub fn at_virtual_root(&self) -> bool {
+        !self.roots.is_empty() && self.root_dir.as_os_str().is_empty()
+    }
+
     /// Select the first entry in the current root directory.
     pub fn select_first_entry(&mut self) {
         if let Some(entries) = self.dir_cache.get(&self.root_dir) {
@@ -162,7 +347,10 @@ impl AppState {
     }
 
     pub fn save_search_state(&mut self) {
-        if self.mode != AppMode::Normal {
+        if matches!(
+            self.mode,
+            AppMode::SearchFocused | AppMode::SearchNavigating
+        ) {
             self.search_history.insert(
                 self.root_dir.clone(),
                 (self.search_query.clone(), self.search_results.clone()),
@@ -177,6 +365,45 @@ impl AppState {
         self.selected_file_count_cache = None;
         self.selected_loc_cache = None;
         self.dir_select_cache.get_mut().clear();
+        self.dir_count_cache.get_mut().clear();
+    }
+
+    /// Select every file under the starting directories that matches one of
+    /// `patterns` (gitignore syntax, as for `--ignore`). Returns how many matched.
+    pub fn select_matching(&mut self, patterns: &[String]) -> usize {
+        self.invalidate_caches();
+        let roots = if self.roots.is_empty() {
+            vec![self.root_dir.clone()]
+        } else {
+            self.roots.clone()
+        };
+        let mut matched = 0;
+        for root in &roots {
+            let globs = crate::content_aggregator::build_gitignore(patterns, root);
+            for file in files_under(root, self.respect_gitignore) {
+                if globs.matched_path_or_any_parents(&file, false).is_ignore() {
+                    self.selected.insert(file);
+                    matched += 1;
+                }
+            }
+        }
+        matched
+    }
+
+    /// Select `paths`; a directory selects every file under it. Returns how
+    /// many files were added.
+    pub fn select_paths(&mut self, paths: &[PathBuf]) -> usize {
+        self.invalidate_caches();
+        let before = self.selected.len();
+        for path in paths {
+            if path.is_dir() {
+                self.selected
+                    .extend(files_under(path, self.respect_gitignore));
+            } else {
+                self.selected.insert(path.clone());
+            }
+        }
+        self.selected.len() - before
     }
 
     pub fn toggle_selection(&mut self, path: PathBuf, is_dir: bool) {
@@ -217,6 +444,22 @@ impl AppState {
         result
     }
 
+    /// Number of selected files under `dir`, i.e. what it would contribute on
+    /// confirm. Cached until the next selection change.
+    pub fn selected_count_under(&self, dir: &Path) -> usize {
+        if self.selected.is_empty() {
+            return 0;
+        }
+        if let Some(n) = self.dir_count_cache.borrow().get(dir).copied() {
+            return n;
+        }
+        let n = self.selected.iter().filter(|p| p.starts_with(dir)).count();
+        self.dir_count_cache
+            .borrow_mut()
+            .insert(dir.to_path_buf(), n);
+        n
+    }
+
     fn check_dir_fully_selected(&self, dir: &Path) -> bool {
         let Some(entries) = self.dir_cache.get(dir) else {
             return false;
@@ -243,6 +486,16 @@ impl AppState {
         }
     }
 
+    pub fn path_header(&self) -> crate::cli::PathHeader {
+        if self.no_path {
+            crate::cli::PathHeader::None
+        } else if self.relative {
+            crate::cli::PathHeader::Relative
+        } else {
+            crate::cli::PathHeader::Absolute
+        }
+    }
+
     pub fn collect_selected_paths(&self) -> Vec<String> {
         self.selected
             .iter()
@@ -250,6 +503,14 @@ impl AppState {
             .collect()
     }
 
+    /// Drop every selected path. Returns how many there were.
+    pub fn clear_selection(&mut self) -> usize {
+        self.invalidate_caches();
+        let count = self.selected.len();
+        self.selected.clear();
+        count
+    }
+
     /// Merge the session's last selection into the current selection.
     /// Returns the number of newly added paths (0 means nothing was cached).
     pub fn restore_last_selection(&mut self) -> usize {
@@ -298,6 +559,74 @@ impl AppState {
         self.tree_state.selected().last().cloned()
     }
 
+    /// The entry under the cursor in the tree or the search results.
+    pub fn cursor_path(&self) -> Option<PathBuf> {
+        match self.mode {
+            AppMode::Normal | AppMode::Filtering => self.highlighted_path(),
+            AppMode::SearchFocused | AppMode::SearchNavigating => self
+                .search_results
+                .get(self.search_cursor)
+                .map(|r| r.path.clone()),
+            AppMode::GitTree => None,
+        }
+    }
+
+    /// Regenerate the preview when the pane is open and the cursor has moved
+    /// to another entry. A `--preview-command` runs in the background (see
+    /// `poll_preview`) with the built-in preview shown until it finishes, so a
+    /// slow command never blocks the UI.
+    pub fn refresh_preview(&mut self) {
+        if !self.show_preview {
+            return;
+        }
+        let Some(path) = self.cursor_path() else {
+            self.preview = None;
+            self.preview_job = None;
+            return;
+        };
+        if self.preview.as_ref().is_some_and(|p| p.path == path) {
+            return;
+        }
+        self.preview_job = self
+            .preview_command
+            .clone()
+            .filter(|_| path.is_file())
+            .map(|cmd| spawn_preview(cmd, path.clone()));
+        let text = builtin_preview(&path);
+        self.preview = Some(Preview { path, text });
+    }
+
+    /// Called once per event-loop tick. Installs a finished preview command's
+    /// output if the cursor is still on its file. Returns true if a redraw is needed.
+    pub fn poll_preview(&mut self) -> bool {
+        let Some(job) = self.preview_job.as_ref() else {
+            return false;
+        };
+        let text = match job.rx.try_recv() {
+            Ok(text) => text,
+            Err(mpsc::TryRecvError::Empty) => return false,
+            Err(mpsc::TryRecvError::Disconnected) => {
+                // The command failed; the built-in preview stays.
+                self.preview_job = None;
+                return false;
+            }
+        };
+        let job = self.preview_job.take().expect("checked above");
+        match self.preview.as_mut() {
+            Some(preview) if preview.path == job.path => {
+                preview.text = text;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Drop the preview and any command still producing one.
+    pub fn clear_preview(&mut self) {
+        self.preview = None;
+        self.preview_job = None;
+    }
+
     pub fn enter_git_tree_mode(&mut self) {
         if let Ok(output) = std::process::Command::new("git")
             .args(["log", "--graph", "--pretty=format:%H%x00%s"])
@@ -560,22 +889,75 @@ impl AppState {
 // NavigationExt
 impl AppState {
     /// Load `dir`'s direct children into the cache if not already present.
-    /// Also eagerly loads one level of subdirectories for ▶ indicators.
+    /// Large directories show their first batch at once and fill in from
+    /// `poll_dir_loads`.
     pub fn ensure_dir_loaded(&mut self, dir: &PathBuf) {
         if self.dir_cache.contains_key(dir) {
             return;
         }
-        let Ok(entries) = read_dir_sorted(dir, self.respect_gitignore) else {
-            return;
-        };
+        let (entries, job) = load_dir(dir, self.respect_gitignore, self.sort_mode);
         self.dir_cache.insert(dir.clone(), entries);
+        self.dir_loads.extend(job);
     }
 
-    /// Change the tree root to the parent directory of root_dir.
+    /// True while `dir`'s entries are still streaming in.
+    pub fn dir_loading(&self, dir: &Path) -> bool {
+        self.dir_loads.iter().any(|job| job.dir == dir)
+    }
+
+    /// Called once per event-loop tick. Appends newly read entries to their
+    /// directories and re-sorts them; the tree selects by path, so the cursor
+    /// and selection stay put. Returns true if a redraw is needed.
+    pub fn poll_dir_loads(&mut self) -> bool {
+        let mut changed = false;
+        let dir_cache = &mut self.dir_cache;
+        let sort_mode = self.sort_mode;
+        self.dir_loads.retain(|job| {
+            let mut arrived = Vec::new();
+            let finished = loop {
+                match job.rx.try_recv() {
+                    Ok(batch) => arrived.extend(batch),
+                    Err(mpsc::TryRecvError::Empty) => break false,
+                    Err(mpsc::TryRecvError::Disconnected) => break true,
+                }
+            };
+            let grew = !arrived.is_empty();
+            if grew {
+                if let Some(entries) = dir_cache.get_mut(&job.dir) {
+                    entries.extend(arrived);
+                    sort_entries(entries, sort_mode);
+                }
+            }
+            changed |= finished || grew;
+            !finished
+        });
+        if changed {
+            self.invalidate_caches();
+        }
+        changed
+    }
+
+    /// Advance to the next sort mode and re-sort every loaded directory.
+    pub fn cycle_sort_mode(&mut self) {
+        self.sort_mode = self.sort_mode.next();
+        for entries in self.dir_cache.values_mut() {
+            sort_entries(entries, self.sort_mode);
+        }
+    }
+
+    /// Change the tree root to the parent directory of root_dir. In a multi-root
+    /// session, going up from a starting directory returns to the root list.
     pub fn go_up_root(&mut self) {
-        if let Some(parent) = self.root_dir.parent() {
+        let parent = if self.at_virtual_root() {
+            None
+        } else if self.roots.contains(&self.root_dir) {
+            Some(PathBuf::new())
+        } else {
+            self.root_dir.parent().map(Path::to_path_buf)
+        };
+        if let Some(parent) = parent {
             let old_root = self.root_dir.clone();
-            let parent_path = parent.to_path_buf();
+            let parent_path = parent;
             self.root_history.push(self.root_dir.clone());
             self.root_dir = parent_path.clone();
             self.ensure_dir_loaded(&parent_path);
@@ -583,23 +965,133 @@ impl AppState {
             // Restore cursor to the directory we just backed out of.
             self.tree_state.select(vec![old_root]);
             self.mode = AppMode::Normal;
+            self.dir_filter.clear();
+            self.cancel_search();
             self.search_query.clear();
             self.search_results.clear();
         }
     }
 
+    /// Path-bar segments for `root_dir`, outermost first. In a multi-root session
+    /// they start at the virtual root list rather than the filesystem root.
+    pub fn breadcrumbs(&self) -> Vec<String> {
+        const ROOTS_LABEL: &str = "[roots]";
+        if self.at_virtual_root() {
+            return vec![ROOTS_LABEL.to_string()];
+        }
+        let start = self.roots.iter().find(|r| self.root_dir.starts_with(r));
+        let mut crumbs = Vec::new();
+        for dir in self.root_dir.ancestors() {
+            let label = match dir.file_name() {
+                Some(name) if Some(dir) != start.map(PathBuf::as_path) => {
+                    name.to_string_lossy().into_owned()
+                }
+                _ => dir.display().to_string(),
+            };
+            crumbs.push(label);
+            if Some(dir) == start.map(PathBuf::as_path) {
+                crumbs.push(ROOTS_LABEL.to_string());
+                break;
+            }
+        }
+        crumbs.reverse();
+        crumbs
+    }
+
+    /// Move the tree root `levels` directories up (as repeated `Backspace`).
+    pub fn jump_up(&mut self, levels: usize) {
+        for _ in 0..levels {
+            if self.at_virtual_root() {
+                break;
+            }
+            self.go_up_root();
+        }
+    }
+
     /// Navigate into a directory from search mode (sets root_dir, resets tree).
+    /// For a symlinked `path`, the canonical directory it leads to and whether
+    /// that lies outside every starting directory. `None` for other paths.
+    pub fn symlink_destination(&self, path: &Path) -> Option<(PathBuf, bool)> {
+        if !path
+            .symlink_metadata()
+            .is_ok_and(|m| m.file_type().is_symlink())
+        {
+            return None;
+        }
+        let target = path.canonicalize().ok()?;
+        let escapes = !self.start_roots.iter().any(|r| target.starts_with(r));
+        Some((target, escapes))
+    }
+
     pub fn navigate_to_dir(&mut self, path: PathBuf) {
         self.root_dir = path.clone();
         self.ensure_dir_loaded(&path);
         self.tree_state = tui_tree_widget::TreeState::default();
         self.mode = AppMode::Normal;
+        self.dir_filter.clear();
+        self.cancel_search();
         self.search_query.clear();
         self.search_results.clear();
         self.select_first_entry();
     }
 }
 
+// FilterExt
+impl AppState {
+    pub fn enter_filter(&mut self) {
+        self.mode = AppMode::Filtering;
+    }
+
+    /// Leave filter input; the filter stays applied until `clear_filter`.
+    pub fn accept_filter(&mut self) {
+        self.mode = AppMode::Normal;
+    }
+
+    pub fn clear_filter(&mut self) {
+        self.mode = AppMode::Normal;
+        self.dir_filter.clear();
+    }
+
+    pub fn push_filter_char(&mut self, c: char) {
+        self.dir_filter.push(c);
+        self.keep_cursor_in_filter();
+    }
+
+    pub fn pop_filter_char(&mut self) {
+        self.dir_filter.pop();
+        self.keep_cursor_in_filter();
+    }
+
+    /// True if an entry of `root_dir` named `name` passes the filter.
+    pub fn filter_matches(&self, name: &std::ffi::OsStr) -> bool {
+        self.dir_filter.is_empty()
+            || name
+                .to_string_lossy()
+                .to_lowercase()
+                .contains(&self.dir_filter.to_lowercase())
+    }
+
+    /// Move the cursor to the first remaining entry when the filter hides the
+    /// top-level entry it was on (or inside).
+    fn keep_cursor_in_filter(&mut self) {
+        let current = self.tree_state.selected().first().cloned();
+        if current
+            .as_ref()
+            .and_then(|p| p.file_name())
+            .is_some_and(|name| self.filter_matches(name))
+        {
+            return;
+        }
+        let first = self.dir_cache.get(&self.root_dir).and_then(|entries| {
+            entries
+                .iter()
+                .find(|e| self.filter_matches(e.file_name()))
+                .map(DirItem::path)
+        });
+        self.tree_state.select(first.into_iter().collect());
+    }
+}
+
 // SearchExt
 impl AppState {
     pub fn enter_search(&mut self) {
@@ -615,6 +1107,7 @@ impl AppState {
 
     pub fn exit_search(&mut self) {
         self.mode = AppMode::Normal;
+        self.cancel_search();
         self.search_query.clear();
         self.search_results.clear();
         self.search_cursor = 0;
@@ -632,7 +1125,16 @@ impl AppState {
         self.update_search();
     }
 
+    /// Switch between recursive and current-directory-only search.
+    pub fn toggle_search_scope(&mut self) {
+        self.search_recursive = !self.search_recursive;
+        self.update_search();
+    }
+
+    /// Empty queries list the current directory synchronously; anything else
+    /// is debounced and handed to a background walk (see `poll_search`).
     pub fn update_search(&mut self) {
+        self.cancel_search();
         if self.search_query.is_empty() {
             self.search_results.clear();
             if let Some(entries) = self.dir_cache.get(&self.root_dir) {
@@ -653,21 +1155,139 @@ impl AppState {
             self.search_scroll_offset = 0;
             return;
         }
+        self.search_requested_at = Some(Instant::now());
+    }
+
+    /// True while a query is waiting out the debounce or being walked.
+    pub fn is_searching(&self) -> bool {
+        self.search_requested_at.is_some() || self.search_job.is_some()
+    }
+
+    /// Matches found so far by the running walk; None unless one is running.
+    pub fn search_progress(&self) -> Option<usize> {
+        self.search_job
+            .as_ref()
+            .map(|job| job.found.load(Ordering::Relaxed))
+    }
+
+    fn cancel_search(&mut self) {
+        if let Some(job) = self.search_job.take() {
+            job.cancel.store(true, Ordering::Relaxed);
+        }
+        self.search_requested_at = None;
+    }
+
+    /// Called once per event-loop tick. Starts the walk once the debounce has
+    /// elapsed and installs finished results. Returns true if a redraw is needed.
+    pub fn poll_search(&mut self) -> bool {
+        if let Some(requested_at) = self.search_requested_at {
+            if requested_at.elapsed() >= SEARCH_DEBOUNCE {
+                self.search_requested_at = None;
+                self.spawn_search();
+            }
+            return false;
+        }
+        let Some(job) = self.search_job.as_mut() else {
+            return false;
+        };
+        match job.rx.try_recv() {
+            Ok(results) => {
+                self.search_job = None;
+                self.search_results = results;
+                self.search_cursor = 0;
+                self.search_scroll_offset = 0;
+                if self.mode != AppMode::Normal {
+                    self.save_search_state();
+                }
+                true
+            }
+            Err(mpsc::TryRecvError::Empty) => {
+                let found = job.found.load(Ordering::Relaxed);
+                let changed = found != job.shown;
+                job.shown = found;
+                changed
+            }
+            Err(mpsc::TryRecvError::Disconnected) => {
+                self.search_job = None;
+                true
+            }
+        }
+    }
+
+    fn spawn_search(&mut self) {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = mpsc::channel();
+        let roots = if self.at_virtual_root() {
+            self.roots.clone()
+        } else {
+            vec![self.root_dir.clone()]
+        };
+        let query = self.search_query.clone();
+        let recursive = self.search_recursive;
+        let respect_gitignore = self.respect_gitignore;
+        let found = Arc::new(AtomicUsize::new(0));
+        let worker_cancel = Arc::clone(&cancel);
+        let worker_found = Arc::clone(&found);
+        std::thread::spawn(move || {
+            if let Some(results) = search_walk(
+                &roots,
+                &query,
+                recursive,
+                respect_gitignore,
+                &worker_cancel,
+                &worker_found,
+            ) {
+                let _ = tx.send(results);
+            }
+        });
+        self.search_job = Some(SearchJob {
+            cancel,
+            found,
+            shown: 0,
+            rx,
+        });
+    }
+}
 
-        let mut results = Vec::new();
+/// Fuzzy-match every entry under `roots` against `query`, best matches first.
+/// With several roots, names are shown relative to each root's parent so
+/// matches from different roots stay distinguishable.
+/// Returns None if `cancel` was raised before the walk finished; `found` is
+/// bumped per match so the UI can show progress.
+fn search_walk(
+    roots: &[PathBuf],
+    query: &str,
+    recursive: bool,
+    respect_gitignore: bool,
+    cancel: &AtomicBool,
+    found: &AtomicUsize,
+) -> Option<Vec<SearchResult>> {
+    let matcher = fuzzy_matcher::skim::SkimMatcherV2::default();
+    let mut results = Vec::new();
 
-        let walker = ignore::WalkBuilder::new(&self.root_dir)
+    let max_depth = if recursive { None } else { Some(1) };
+    for root in roots {
+        let walker = ignore::WalkBuilder::new(root)
             .hidden(false)
-            .git_ignore(self.respect_gitignore)
+            .git_ignore(respect_gitignore)
             .follow_links(false)
+            .max_depth(max_depth)
             .build();
+        let base = if roots.len() > 1 {
+            root.parent().unwrap_or(root)
+        } else {
+            root.as_path()
+        };
 
         for entry in walker.filter_map(|e| e.ok()) {
+            if cancel.load(Ordering::Relaxed) {
+                return None;
+            }
             let path = entry.path();
 
-            let display_name = if path.starts_with(&self.root_dir) {
-                let rel = path.strip_prefix(&self.root_dir).unwrap_or(path);
-                if rel == std::path::Path::new(".") {
+            let display_name = if path.starts_with(base) {
+                let rel = path.strip_prefix(base).unwrap_or(path);
+                if rel == Path::new(".") {
                     path.file_name()
                         .unwrap_or_default()
                         .to_string_lossy()
@@ -679,10 +1299,8 @@ impl AppState {
                 path.to_string_lossy().to_string()
             };
 
-            if let Some((score, indices)) = self
-                .matcher
-                .fuzzy_indices(&display_name, &self.search_query)
-            {
+            if let Some((score, indices)) = matcher.fuzzy_indices(&display_name, query) {
+                found.fetch_add(1, Ordering::Relaxed);
                 results.push(SearchResult {
                     path: path.to_path_buf(),
                     display_name,
@@ -692,26 +1310,20 @@ impl AppState {
                 });
             }
         }
-
-        results.sort_by(|a, b| {
-            b.match_score
-                .cmp(&a.match_score)
-                .then_with(|| b.is_dir.cmp(&a.is_dir))
-                .then_with(|| a.display_name.len().cmp(&b.display_name.len()))
-                .then_with(|| {
-                    a.display_name
-                        .to_lowercase()
-                        .cmp(&b.display_name.to_lowercase())
-                })
-        });
-
-        self.search_results = results;
-        self.search_cursor = 0;
-        self.search_scroll_offset = 0;
-        if self.mode != AppMode::Normal {
-            self.save_search_state();
-        }
     }
+
+    results.sort_by(|a, b| {
+        b.match_score
+            .cmp(&a.match_score)
+            .then_with(|| b.is_dir.cmp(&a.is_dir))
+            .then_with(|| a.display_name.len().cmp(&b.display_name.len()))
+            .then_with(|| {
+                a.display_name
+                    .to_lowercase()
+                    .cmp(&b.display_name.to_lowercase())
+            })
+    });
+    Some(results)
 }
 
 // ScrollExt (search mode only — tree widget self-manages scrolling)
@@ -757,28 +1369,230 @@ pub fn files_under(dir: &Path, respect_gitignore: bool) -> Vec<PathBuf> {
         .collect()
 }
 
-pub fn read_dir_sorted(dir: &PathBuf, respect_gitignore: bool) -> io::Result<Vec<DirItem>> {
-    let mut entries: Vec<DirItem> = ignore::WalkBuilder::new(dir)
-        .max_depth(Some(1))
-        .hidden(false)
-        .git_ignore(respect_gitignore)
-        .follow_links(true)
-        .build()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.depth() > 0)
-        .map(|e| {
-            let is_dir = e.file_type().map(|ft| ft.is_dir()).unwrap_or(false);
-            DirItem {
-                path: e.path().to_path_buf(),
-                file_name: e.file_name().to_os_string(),
-                is_dir,
+/// Entry for a starting directory at the virtual root, labelled by its full path.
+fn root_item(dir: &Path) -> DirItem {
+    let metadata = dir.metadata().ok();
+    DirItem {
+        path: dir.to_path_buf(),
+        file_name: dir.as_os_str().to_os_string(),
+        is_dir: true,
+        modified: metadata.as_ref().and_then(|m| m.modified().ok()),
+        size: 0,
+        symlink_target: None,
+    }
+}
+
+/// Run `--preview-command` for `path` on a worker thread. The channel closes
+/// without a message if the command fails.
+fn spawn_preview(command: String, path: PathBuf) -> PreviewJob {
+    let (tx, rx) = mpsc::channel();
+    let worker_path = path.clone();
+    std::thread::spawn(move || {
+        if let Ok(out) = crate::exec::capture(&command, &worker_path) {
+            let _ = tx.send(String::from_utf8_lossy(&out).into_owned());
+        }
+    });
+    PreviewJob { path, rx }
+}
+
+/// Read `dir`'s first batch of entries, sorted, plus a job for the remainder
+/// when the directory is larger than one batch.
+fn load_dir(
+    dir: &Path,
+    respect_gitignore: bool,
+    sort_mode: SortMode,
+) -> (Vec<DirItem>, Option<DirLoadJob>) {
+    let rx = spawn_dir_reader(dir.to_path_buf(), respect_gitignore);
+    let mut entries = rx.recv().unwrap_or_default();
+    sort_entries(&mut entries, sort_mode);
+    let job = (entries.len() == DIR_FIRST_BATCH).then(|| DirLoadJob {
+        dir: dir.to_path_buf(),
+        rx,
+    });
+    (entries, job)
+}
+
+/// Read `dir`'s direct children on a worker thread, sending the first
+/// `DIR_FIRST_BATCH` entries, then `DIR_BATCH` at a time. The final batch may
+/// be shorter (or empty); the channel closes when the directory is done.
+fn spawn_dir_reader(dir: PathBuf, respect_gitignore: bool) -> mpsc::Receiver<Vec<DirItem>> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let items = ignore::WalkBuilder::new(&dir)
+            .max_depth(Some(1))
+            .hidden(false)
+            .git_ignore(respect_gitignore)
+            .follow_links(true)
+            .build()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.depth() > 0)
+            .map(|e| {
+                let is_dir = e.file_type().map(|ft| ft.is_dir()).unwrap_or(false);
+                let metadata = e.metadata().ok();
+                DirItem {
+                    path: e.path().to_path_buf(),
+                    file_name: e.file_name().to_os_string(),
+                    is_dir,
+                    modified: metadata.as_ref().and_then(|m| m.modified().ok()),
+                    size: metadata.map(|m| m.len()).unwrap_or(0),
+                    symlink_target: e
+                        .path()
+                        .symlink_metadata()
+                        .is_ok_and(|m| m.file_type().is_symlink())
+                        .then(|| fs::read_link(e.path()).ok())
+                        .flatten(),
+                }
+            });
+        let mut batch = Vec::with_capacity(DIR_FIRST_BATCH);
+        let mut batch_size = DIR_FIRST_BATCH;
+        for item in items {
+            batch.push(item);
+            if batch.len() == batch_size {
+                if tx.send(std::mem::take(&mut batch)).is_err() {
+                    return;
+                }
+                batch_size = DIR_BATCH;
             }
-        })
-        .collect();
+        }
+        let _ = tx.send(batch);
+    });
+    rx
+}
+
+/// Directories first, then by `sort_mode`, falling back to name for ties.
+/// Directory sizes aren't meaningful, so `Size` orders them by name.
+fn sort_entries(entries: &mut [DirItem], sort_mode: SortMode) {
     entries.sort_unstable_by(|a, b| {
+        let by_mode = match sort_mode {
+            SortMode::Name => std::cmp::Ordering::Equal,
+            SortMode::Modified => b.modified.cmp(&a.modified),
+            SortMode::Size if a.is_dir && b.is_dir => std::cmp::Ordering::Equal,
+            SortMode::Size => b.size.cmp(&a.size),
+        };
         (!a.is_dir())
             .cmp(&(!b.is_dir()))
+            .then(by_mode)
             .then_with(|| a.file_name().cmp(b.file_name()))
     });
-    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search(root: &Path, query: &str, recursive: bool) -> Vec<String> {
+        let (cancel, found) = (AtomicBool::new(false), AtomicUsize::new(0));
+        let results = search_walk(
+            &[root.to_path_buf()],
+            query,
+            recursive,
+            true,
+            &cancel,
+            &found,
+        )
+        .unwrap();
+        assert_eq!(found.load(Ordering::Relaxed), results.len());
+        let mut names: Vec<String> = results.into_iter().map(|r| r.display_name).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn search_walk_returns_none_once_cancelled() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), "").unwrap();
+        let (cancel, found) = (AtomicBool::new(true), AtomicUsize::new(0));
+        let results = search_walk(
+            &[dir.path().to_path_buf()],
+            "a",
+            true,
+            true,
+            &cancel,
+            &found,
+        );
+        assert!(results.is_none());
+        assert_eq!(found.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn non_recursive_search_stays_in_the_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("sub/deeper")).unwrap();
+        fs::write(dir.path().join("top.rs"), "").unwrap();
+        fs::write(dir.path().join("sub/mid.rs"), "").unwrap();
+        fs::write(dir.path().join("sub/deeper/low.rs"), "").unwrap();
+        assert_eq!(search(dir.path(), "rs", false), ["top.rs"]);
+        let sep = std::path::MAIN_SEPARATOR;
+        assert_eq!(
+            search(dir.path(), "rs", true),
+            [
+                format!("sub{sep}deeper{sep}low.rs"),
+                format!("sub{sep}mid.rs"),
+                "top.rs".to_string()
+            ]
+        );
+    }
+
+    fn item(name: &str, is_dir: bool, modified_secs: u64, size: u64) -> DirItem {
+        DirItem {
+            path: PathBuf::from(name),
+            file_name: name.into(),
+            is_dir,
+            modified: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(modified_secs)),
+            size,
+            symlink_target: None,
+        }
+    }
+
+    #[test]
+    fn sort_entries_puts_directories_first_in_every_mode() {
+        let sorted = |mode| {
+            let mut entries = vec![
+                item("big.bin", false, 1, 900),
+                item("z_dir", true, 5, 4096),
+                item("new.txt", false, 9, 10),
+                item("a_dir", true, 2, 64),
+            ];
+            sort_entries(&mut entries, mode);
+            entries
+                .iter()
+                .map(|e| e.file_name().to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            sorted(SortMode::Name),
+            ["a_dir", "z_dir", "big.bin", "new.txt"]
+        );
+        assert_eq!(
+            sorted(SortMode::Modified),
+            ["z_dir", "a_dir", "new.txt", "big.bin"]
+        );
+        assert_eq!(
+            sorted(SortMode::Size),
+            ["a_dir", "z_dir", "big.bin", "new.txt"]
+        );
+    }
+
+    #[test]
+    fn load_dir_shows_the_first_batch_and_streams_the_rest() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..DIR_FIRST_BATCH + 7 {
+            fs::write(dir.path().join(format!("f{i:05}")), "").unwrap();
+        }
+        let (entries, job) = load_dir(dir.path(), true, SortMode::Name);
+        assert_eq!(entries.len(), DIR_FIRST_BATCH);
+        let rest: usize = job
+            .expect("a job for the remainder")
+            .rx
+            .iter()
+            .map(|b| b.len())
+            .sum();
+        assert_eq!(rest, 7);
+
+        let small = tempfile::tempdir().unwrap();
+        fs::write(small.path().join("only.txt"), "").unwrap();
+        let (entries, job) = load_dir(small.path(), true, SortMode::Name);
+        assert_eq!(entries.len(), 1);
+        assert!(job.is_none());
+    }
 }
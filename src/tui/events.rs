@@ -2,30 +2,46 @@ use crossterm::event::{
     KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
 };
 use ratatui::layout::Position;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::tui::app::{AppMode, AppState};
+use crate::tui::notify::Notifications;
 
 pub fn handle_key_event(
     app: &mut AppState,
     key_event: KeyEvent,
-    message: &mut String,
+    notes: &mut Notifications,
 ) -> Option<Vec<String>> {
     if key_event.kind != KeyEventKind::Press {
         return None;
     }
+    if app.show_help {
+        app.show_help = false;
+        return None;
+    }
+    if notes.show_log {
+        notes.show_log = false;
+        return None;
+    }
+    if key_event.code == KeyCode::Char('m')
+        && !matches!(app.mode, AppMode::SearchFocused | AppMode::Filtering)
+    {
+        notes.show_log = true;
+        return None;
+    }
     match app.mode {
         AppMode::SearchFocused => handle_search_focused(app, key_event),
-        AppMode::SearchNavigating => handle_search_navigating(app, key_event, message),
-        AppMode::Normal => handle_normal(app, key_event, message),
-        AppMode::GitTree => handle_git_tree(app, key_event, message),
+        AppMode::SearchNavigating => handle_search_navigating(app, key_event, notes),
+        AppMode::Normal => handle_normal(app, key_event, notes),
+        AppMode::Filtering => handle_filtering(app, key_event),
+        AppMode::GitTree => handle_git_tree(app, key_event, notes),
     }
 }
 
 fn handle_git_tree(
     app: &mut AppState,
     key_event: KeyEvent,
-    message: &mut String,
+    notes: &mut Notifications,
 ) -> Option<Vec<String>> {
     match key_event.code {
         KeyCode::Tab => {
@@ -35,26 +51,32 @@ fn handle_git_tree(
             app.mode = AppMode::Normal;
         }
         KeyCode::Char('q') => return Some(vec![]),
+        KeyCode::Char('?') => {
+            app.show_help = true;
+        }
         KeyCode::Char('c') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
             return Some(vec![])
         }
         KeyCode::Char('c') => {
             if app.selected.is_empty() {
-                *message = "No files or directories selected!".to_string();
+                notes.warn("No files or directories selected!");
             } else {
                 return Some(app.collect_selected_paths());
             }
         }
+        KeyCode::Char('y') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+            request_copy(app, notes);
+        }
         KeyCode::Char('p') => {
             let added = app.restore_last_selection();
-            *message = if added > 0 {
-                format!(
+            if added > 0 {
+                notes.info(format!(
                     "Restored last selection (+{added} file{}).",
                     if added == 1 { "" } else { "s" }
-                )
+                ));
             } else {
-                "No previous selection in this session.".to_string()
-            };
+                notes.warn("No previous selection in this session.");
+            }
         }
         KeyCode::Char(' ') => {
             if app.git_panel_focused {
@@ -126,6 +148,9 @@ fn handle_search_focused(app: &mut AppState, key_event: KeyEvent) -> Option<Vec<
         KeyCode::Enter | KeyCode::Down | KeyCode::Up => {
             app.mode = AppMode::SearchNavigating;
         }
+        KeyCode::Tab => {
+            app.toggle_search_scope();
+        }
         KeyCode::Char(c) => {
             app.push_search_char(c);
         }
@@ -134,23 +159,54 @@ fn handle_search_focused(app: &mut AppState, key_event: KeyEvent) -> Option<Vec<
     None
 }
 
+fn handle_filtering(app: &mut AppState, key_event: KeyEvent) -> Option<Vec<String>> {
+    match key_event.code {
+        KeyCode::Esc => {
+            app.clear_filter();
+        }
+        KeyCode::Enter => {
+            app.accept_filter();
+        }
+        KeyCode::Backspace => {
+            app.pop_filter_char();
+        }
+        KeyCode::Up => {
+            app.tree_state.key_up();
+        }
+        KeyCode::Down => {
+            app.tree_state.key_down();
+        }
+        KeyCode::Char(c) => {
+            app.push_filter_char(c);
+        }
+        _ => {}
+    }
+    None
+}
+
 fn handle_search_navigating(
     app: &mut AppState,
     key_event: KeyEvent,
-    message: &mut String,
+    notes: &mut Notifications,
 ) -> Option<Vec<String>> {
     match key_event.code {
         KeyCode::Char('q') => return Some(vec![]),
+        KeyCode::Char('?') => {
+            app.show_help = true;
+        }
         KeyCode::Char('c') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
             return Some(vec![])
         }
         KeyCode::Char('c') => {
             if app.selected.is_empty() {
-                *message = "No files or directories selected!".to_string();
+                notes.warn("No files or directories selected!");
             } else {
                 return Some(app.collect_selected_paths());
             }
         }
+        KeyCode::Char('y') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+            request_copy(app, notes);
+        }
         KeyCode::Char('/') => {
             app.mode = AppMode::SearchFocused;
         }
@@ -160,10 +216,14 @@ fn handle_search_navigating(
         KeyCode::Esc => {
             app.exit_search();
         }
+        KeyCode::Tab => {
+            app.toggle_search_scope();
+        }
         KeyCode::Enter => {
             if let Some(result) = app.search_results.get(app.search_cursor) {
                 if result.is_dir {
                     let path = result.path.clone();
+                    note_symlink(app, &path, notes);
                     app.navigate_to_dir(path);
                 } else {
                     let path = result.path.clone();
@@ -189,6 +249,7 @@ fn handle_search_navigating(
             if let Some(result) = app.search_results.get(app.search_cursor) {
                 if result.is_dir {
                     let path = result.path.clone();
+                    note_symlink(app, &path, notes);
                     app.navigate_to_dir(path);
                 }
             }
@@ -196,29 +257,100 @@ fn handle_search_navigating(
         KeyCode::Char('h') | KeyCode::Left | KeyCode::Backspace => {
             app.go_up_root();
         }
+        KeyCode::Char('v') => {
+            toggle_preview(app);
+        }
+        KeyCode::Char('y') => {
+            if let Some(result) = app.search_results.get(app.search_cursor) {
+                let path = result.path.clone();
+                yank_path(app, &path, notes);
+            }
+        }
         KeyCode::Char('p') => {
             let added = app.restore_last_selection();
-            *message = if added > 0 {
-                format!(
+            if added > 0 {
+                notes.info(format!(
                     "Restored last selection (+{added} file{}).",
                     if added == 1 { "" } else { "s" }
-                )
+                ));
             } else {
-                "No previous selection in this session.".to_string()
-            };
+                notes.warn("No previous selection in this session.");
+            }
         }
         _ => {}
     }
     None
 }
 
-pub fn handle_mouse_event(app: &mut AppState, mouse: MouseEvent, _message: &mut String) {
-    if app.show_help {
+/// Ask the event loop to copy the selection's content while staying in the TUI.
+fn request_copy(app: &mut AppState, notes: &mut Notifications) {
+    if app.selected.is_empty() {
+        notes.warn("No files or directories selected!");
+    } else {
+        app.copy_requested = true;
+    }
+}
+
+/// Before entering a symlinked directory, say where it leads, with a warning
+/// when that is outside the starting directories.
+fn note_symlink(app: &AppState, path: &Path, notes: &mut Notifications) {
+    let Some((target, escapes)) = app.symlink_destination(path) else {
+        return;
+    };
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    if escapes {
+        notes.warn(format!(
+            "{name}@ leads outside the starting directory, to {}",
+            target.display()
+        ));
+    } else {
+        notes.info(format!("{name}@ -> {}", target.display()));
+    }
+}
+
+/// Open or close the preview pane; closing drops the cached preview so the
+/// next open regenerates it.
+fn toggle_preview(app: &mut AppState) {
+    app.show_preview = !app.show_preview;
+    if !app.show_preview {
+        app.clear_preview();
+    }
+}
+
+/// Copy `path` itself (not its content) to the clipboard, honouring the
+/// relative-path toggle, and report the outcome in the status line.
+fn yank_path(app: &AppState, path: &Path, notes: &mut Notifications) {
+    let text = if app.relative {
+        std::env::current_dir()
+            .ok()
+            .and_then(|cwd| pathdiff::diff_paths(path, cwd))
+            .unwrap_or_else(|| path.to_path_buf())
+    } else {
+        path.to_path_buf()
+    }
+    .display()
+    .to_string();
+    let dest = crate::cli::Destination::Clipboard { echo: false };
+    match dest.write_with(|w| w.write_all(text.as_bytes()).map_err(Into::into)) {
+        Ok(()) => notes.info(format!("Copied path: {text}")),
+        Err(e) => notes.error(format!("Failed to copy path: {e}")),
+    }
+}
+
+pub fn handle_mouse_event(app: &mut AppState, mouse: MouseEvent, notes: &Notifications) {
+    if app.show_help || notes.show_log {
         return;
     }
     match mouse.kind {
         MouseEventKind::Down(MouseButton::Left) => {
-            if app.mode == AppMode::Normal {
+            let crumb = app
+                .breadcrumb_hits
+                .iter()
+                .find(|(row, xs, _)| *row == mouse.row && xs.contains(&mouse.column))
+                .map(|(_, _, levels)| *levels);
+            if let Some(levels) = crumb {
+                app.jump_up(levels);
+            } else if matches!(app.mode, AppMode::Normal | AppMode::Filtering) {
                 let pos = Position::new(mouse.column, mouse.row);
                 let clicked: Option<Vec<PathBuf>> =
                     app.tree_state.rendered_at(pos).map(|id| id.to_vec());
@@ -247,7 +379,7 @@ pub fn handle_mouse_event(app: &mut AppState, mouse: MouseEvent, _message: &mut
             }
         }
         MouseEventKind::ScrollDown => {
-            if app.mode == AppMode::Normal {
+            if matches!(app.mode, AppMode::Normal | AppMode::Filtering) {
                 app.tree_state.scroll_down(1);
             } else if app.mode == AppMode::GitTree {
                 if app.git_panel_focused {
@@ -264,7 +396,7 @@ pub fn handle_mouse_event(app: &mut AppState, mouse: MouseEvent, _message: &mut
             }
         }
         MouseEventKind::ScrollUp => {
-            if app.mode == AppMode::Normal {
+            if matches!(app.mode, AppMode::Normal | AppMode::Filtering) {
                 app.tree_state.scroll_up(1);
             } else if app.mode == AppMode::GitTree {
                 if app.git_panel_focused {
@@ -287,35 +419,57 @@ pub fn handle_mouse_event(app: &mut AppState, mouse: MouseEvent, _message: &mut
 fn handle_normal(
     app: &mut AppState,
     key_event: KeyEvent,
-    message: &mut String,
+    notes: &mut Notifications,
 ) -> Option<Vec<String>> {
-    if app.show_help {
-        match key_event.code {
-            KeyCode::Char('?') | KeyCode::Esc | KeyCode::Char('q') => {
-                app.show_help = false;
-            }
-            _ => {}
-        }
-        return None;
-    }
+    // Any other key disarms a pending clear.
+    let clear_armed = std::mem::take(&mut app.clear_armed);
     match key_event.code {
         KeyCode::Char('q') => return Some(vec![]),
+        KeyCode::Char('?') => {
+            app.show_help = true;
+        }
+        KeyCode::Char('C') => {
+            if app.selected.is_empty() {
+                notes.info("Nothing is selected.");
+            } else if clear_armed {
+                let count = app.clear_selection();
+                notes.info(format!(
+                    "Cleared the selection ({count} file{}).",
+                    if count == 1 { "" } else { "s" }
+                ));
+            } else {
+                app.clear_armed = true;
+                notes.warn(format!(
+                    "Press C again to clear all {} selected files.",
+                    app.selected.len()
+                ));
+            }
+        }
         KeyCode::Char('c') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
             return Some(vec![])
         }
         KeyCode::Char('c') => {
             if app.selected.is_empty() {
-                *message = "No files or directories selected!".to_string();
+                notes.warn("No files or directories selected!");
             } else {
                 return Some(app.collect_selected_paths());
             }
         }
+        KeyCode::Char('y') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+            request_copy(app, notes);
+        }
         KeyCode::Char('/') => {
             app.enter_search();
         }
         KeyCode::Char('f') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
             app.enter_search();
         }
+        KeyCode::Char('f') => {
+            app.enter_filter();
+        }
+        KeyCode::Esc if !app.dir_filter.is_empty() => {
+            app.clear_filter();
+        }
         KeyCode::Up | KeyCode::Char('k') => {
             app.tree_state.key_up();
         }
@@ -325,6 +479,7 @@ fn handle_normal(
         KeyCode::Right | KeyCode::Char('l') => {
             if let Some(path) = app.highlighted_path() {
                 if path.is_dir() {
+                    note_symlink(app, &path, notes);
                     app.ensure_dir_loaded(&path);
                 }
             }
@@ -333,6 +488,7 @@ fn handle_normal(
         KeyCode::Enter => {
             if let Some(path) = app.highlighted_path() {
                 if path.is_dir() {
+                    note_symlink(app, &path, notes);
                     app.ensure_dir_loaded(&path);
                 }
             }
@@ -350,8 +506,19 @@ fn handle_normal(
                 app.toggle_selection(path, is_dir);
             }
         }
-        KeyCode::Char('?') => {
-            app.show_help = true;
+        KeyCode::Char('y') => {
+            if let Some(path) = app.highlighted_path() {
+                yank_path(app, &path, notes);
+            }
+        }
+        KeyCode::Char('v') => {
+            toggle_preview(app);
+        }
+        KeyCode::Char('s') => {
+            app.cycle_sort_mode();
+        }
+        KeyCode::Char(c @ '1'..='9') => {
+            app.jump_up(c as usize - '0' as usize);
         }
         KeyCode::Char('r') if !app.no_path => {
             app.relative = !app.relative;
@@ -364,14 +531,14 @@ fn handle_normal(
         }
         KeyCode::Char('p') => {
             let added = app.restore_last_selection();
-            *message = if added > 0 {
-                format!(
+            if added > 0 {
+                notes.info(format!(
                     "Restored last selection (+{added} file{}).",
                     if added == 1 { "" } else { "s" }
-                )
+                ));
             } else {
-                "No previous selection in this session.".to_string()
-            };
+                notes.warn("No previous selection in this session.");
+            }
         }
         KeyCode::Tab => {
             app.enter_git_tree_mode();
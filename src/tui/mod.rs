@@ -1,5 +1,6 @@
 mod app;
 mod events;
+mod notify;
 mod render;
 mod theme;
 
@@ -10,9 +11,19 @@ use crossterm::{
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
-use std::{collections::HashSet, io, io::Write, path::PathBuf};
+use std::{
+    collections::HashSet,
+    io,
+    io::Write,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
 use app::{AppMode, AppState};
+use notify::Notifications;
+
+/// How long the event loop waits for input before servicing background work.
+const TICK: Duration = Duration::from_millis(100);
 
 // ── Cross-invocation last-selection cache ────────────────────────────────────
 // Stored in $XDG_RUNTIME_DIR (a per-user tmpfs, wiped on logout) so it
@@ -56,7 +67,27 @@ pub struct TuiOutcome {
     pub path_header: crate::cli::PathHeader,
 }
 
-pub fn run_tui(relative: bool, no_path: bool) -> Result<TuiOutcome> {
+/// Copies the given selection with the given header style, returning a status
+/// line. Supplied by `main` so `Ctrl-y` uses the same settings as a normal run.
+pub type CopySelection<'a> = &'a dyn Fn(&[String], crate::cli::PathHeader) -> Result<String>;
+
+/// `start_dirs` are the directories to browse; several become top-level roots.
+/// Files matching a `preselect` glob start out selected. A `preview_command`
+/// opens the preview pane and generates its contents.
+pub fn run_tui(
+    relative: bool,
+    no_path: bool,
+    start_dirs: Vec<PathBuf>,
+    preselect: &[String],
+    preselect_paths: &[PathBuf],
+    preview_command: Option<String>,
+    copy: CopySelection,
+) -> Result<TuiOutcome> {
+    let mut app = AppState::new(relative, no_path, start_dirs)
+        .context("Failed to read the starting directory")?;
+    app.show_preview = preview_command.is_some();
+    app.preview_command = preview_command;
+
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     crossterm_execute!(
@@ -67,7 +98,7 @@ pub fn run_tui(relative: bool, no_path: bool) -> Result<TuiOutcome> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let res = tui_main(&mut terminal, relative, no_path);
+    let res = tui_main(&mut terminal, app, preselect, preselect_paths, copy);
 
     disable_raw_mode()?;
     crossterm_execute!(
@@ -80,55 +111,87 @@ pub fn run_tui(relative: bool, no_path: bool) -> Result<TuiOutcome> {
 
 fn tui_main(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
-    relative: bool,
-    no_path: bool,
+    mut app: AppState,
+    preselect: &[String],
+    preselect_paths: &[PathBuf],
+    copy: CopySelection,
 ) -> Result<TuiOutcome> {
-    let mut app = AppState::new(relative, no_path).context("Failed to read current directory")?;
-    let mut message = String::new();
+    let mut notes = Notifications::default();
+    if !preselect.is_empty() {
+        let n = app.select_matching(preselect);
+        notes.info(format!(
+            "Pre-selected {n} file{} matching --select",
+            if n == 1 { "" } else { "s" }
+        ));
+    }
+    if !preselect_paths.is_empty() {
+        let n = app.select_paths(preselect_paths);
+        notes.info(format!(
+            "Pre-selected {n} file{} from the command line",
+            if n == 1 { "" } else { "s" }
+        ));
+    }
     let mut needs_redraw = true;
     let mut rendered_height: u16 = 0;
 
     loop {
+        if app.poll_search() {
+            needs_redraw = true;
+        }
+        if app.poll_dir_loads() {
+            needs_redraw = true;
+        }
+        if app.poll_preview() {
+            needs_redraw = true;
+        }
+        if notes.expire(Instant::now()) {
+            needs_redraw = true;
+        }
         if needs_redraw {
             // Search mode manages its own cursor scrolling; tree widget self-manages.
-            if app.mode != AppMode::Normal {
+            if !matches!(app.mode, AppMode::Normal | AppMode::Filtering) {
                 if app.mode == AppMode::GitTree {
                     app.sync_git_scroll(app.visible_height);
                 } else {
                     app.sync_search_scroll(app.visible_height);
                 }
             }
+            app.refresh_preview();
             let file_count = app.selected_file_count();
             let loc_count = app.selected_loc();
             terminal.draw(|f| {
-                rendered_height = render::draw(f, &mut app, &message, file_count, loc_count);
+                rendered_height = render::draw(f, &mut app, &notes, file_count, loc_count);
             })?;
             app.visible_height = rendered_height as usize;
             terminal.backend_mut().flush()?;
             needs_redraw = false;
         }
 
+        if !event::poll(TICK)? {
+            continue;
+        }
         match event::read()? {
             Event::Key(key_event) => {
-                if let Some(paths) = events::handle_key_event(&mut app, key_event, &mut message) {
+                if let Some(paths) = events::handle_key_event(&mut app, key_event, &mut notes) {
                     // Persist non-empty selections for this session so the
                     // user can restore them with `p` in the next invocation.
                     if !app.selected.is_empty() {
                         save_last_selection(&app.selected);
                     }
-                    let path_header = if app.no_path {
-                        crate::cli::PathHeader::None
-                    } else if app.relative {
-                        crate::cli::PathHeader::Relative
-                    } else {
-                        crate::cli::PathHeader::Absolute
-                    };
+                    let path_header = app.path_header();
                     return Ok(TuiOutcome { paths, path_header });
                 }
+                if app.copy_requested {
+                    app.copy_requested = false;
+                    match copy(&app.collect_selected_paths(), app.path_header()) {
+                        Ok(summary) => notes.info(summary),
+                        Err(e) => notes.error(format!("Copy failed: {e}")),
+                    }
+                }
                 needs_redraw = true;
             }
             Event::Mouse(mouse_event) => {
-                events::handle_mouse_event(&mut app, mouse_event, &mut message);
+                events::handle_mouse_event(&mut app, mouse_event, &notes);
                 needs_redraw = true;
             }
             Event::Resize(_, _) => {
@@ -0,0 +1,88 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How many past notices the message log (`m`) keeps.
+const LOG_LIMIT: usize = 50;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Warn,
+    Error,
+}
+
+impl Severity {
+    /// How long a notice stays in the status bar before it is dismissed.
+    fn lifetime(self) -> Duration {
+        match self {
+            Severity::Info => Duration::from_secs(3),
+            Severity::Warn => Duration::from_secs(5),
+            Severity::Error => Duration::from_secs(8),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Notice {
+    pub text: String,
+    pub severity: Severity,
+    pub at: Instant,
+}
+
+/// Status-bar notices that fade on their own, plus a short history of them.
+#[derive(Default)]
+pub struct Notifications {
+    current: Option<Notice>,
+    log: VecDeque<Notice>,
+    pub show_log: bool,
+}
+
+impl Notifications {
+    pub fn info(&mut self, text: impl Into<String>) {
+        self.push(Severity::Info, text.into());
+    }
+
+    pub fn warn(&mut self, text: impl Into<String>) {
+        self.push(Severity::Warn, text.into());
+    }
+
+    pub fn error(&mut self, text: impl Into<String>) {
+        self.push(Severity::Error, text.into());
+    }
+
+    fn push(&mut self, severity: Severity, text: String) {
+        let notice = Notice {
+            text,
+            severity,
+            at: Instant::now(),
+        };
+        if self.log.len() == LOG_LIMIT {
+            self.log.pop_front();
+        }
+        self.log.push_back(notice.clone());
+        self.current = Some(notice);
+    }
+
+    /// The notice to show in the status bar, if one is still live.
+    pub fn current(&self) -> Option<&Notice> {
+        self.current.as_ref()
+    }
+
+    /// Every notice still in the log, oldest first.
+    pub fn log(&self) -> impl DoubleEndedIterator<Item = &Notice> {
+        self.log.iter()
+    }
+
+    /// Dismiss the current notice once its time is up; returns whether the
+    /// status bar needs a redraw. Called from the poll loop.
+    pub fn expire(&mut self, now: Instant) -> bool {
+        let expired = self
+            .current
+            .as_ref()
+            .is_some_and(|n| now.duration_since(n.at) >= n.severity.lifetime());
+        if expired {
+            self.current = None;
+        }
+        expired
+    }
+}
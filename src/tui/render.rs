@@ -1,4 +1,3 @@
-use pathdiff::diff_paths;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Margin, Rect},
     style::{Color, Modifier, Style},
@@ -11,13 +10,15 @@ use ratatui::{
 };
 use std::{
     collections::{HashMap, HashSet},
-    env,
     path::PathBuf,
+    time::Instant,
 };
 use tui_tree_widget::{Tree, TreeItem};
+use unicode_width::UnicodeWidthStr;
 
 use super::theme;
 use crate::tui::app::{AppMode, AppState, DirItem};
+use crate::tui::notify::{Notice, Notifications, Severity};
 
 fn panel(title: &str, focused: bool) -> Block<'static> {
     Block::default()
@@ -41,7 +42,7 @@ fn panel(title: &str, focused: bool) -> Block<'static> {
 pub fn draw(
     f: &mut Frame,
     app: &mut AppState,
-    message: &str,
+    notes: &Notifications,
     file_count: usize,
     loc_count: u64,
 ) -> u16 {
@@ -55,70 +56,122 @@ pub fn draw(
         ])
         .split(f.area());
     let inner_list_height = chunks[1].height.saturating_sub(2);
-    app.list_area = Some(chunks[1]);
     render_path_bar(f, app, chunks[0]);
     if app.mode == AppMode::GitTree {
+        app.list_area = Some(chunks[1]);
         render_git_tree(f, app, chunks[1], inner_list_height as usize);
+    } else if app.show_preview {
+        let halves = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(chunks[1]);
+        app.list_area = Some(halves[0]);
+        render_file_list(f, app, halves[0], inner_list_height as usize);
+        render_preview(f, app, halves[1]);
     } else {
+        app.list_area = Some(chunks[1]);
         render_file_list(f, app, chunks[1], inner_list_height as usize);
     }
     render_status_bar(
         f,
         chunks[2],
-        message,
+        notes.current(),
         file_count,
         loc_count,
         app.mode == AppMode::GitTree,
     );
     if app.show_help {
         render_help_overlay(f, f.area());
+    } else if notes.show_log {
+        render_message_log(f, f.area(), notes);
     }
     inner_list_height
 }
 
-fn render_path_bar(f: &mut Frame, app: &AppState, area: Rect) {
-    let (path, title_str, path_style) =
-        if app.mode != AppMode::Normal && app.mode != AppMode::GitTree {
-            let search_display = format!("Search: {}", app.search_query);
-            let title = if app.mode == AppMode::SearchFocused {
-                "Enter to search, Esc to leave search".to_string()
-            } else {
-                "Esc to leave search".to_string()
-            };
-            let style = if app.mode == AppMode::SearchFocused {
-                Style::default()
-                    .fg(theme::MATCH)
-                    .bg(theme::CURSOR_BG)
-                    .add_modifier(Modifier::BOLD)
-            } else {
-                Style::default()
-            };
-            (search_display, title, style)
+fn render_path_bar(f: &mut Frame, app: &mut AppState, area: Rect) {
+    app.breadcrumb_hits.clear();
+    if matches!(app.mode, AppMode::SearchFocused | AppMode::SearchNavigating) {
+        let search_display = format!("Search: {}", app.search_query);
+        let title = if app.mode == AppMode::SearchFocused {
+            "Enter to search, Esc to leave search"
         } else {
-            let path = if app.no_path {
-                "[No Path Headers]".to_string()
-            } else if app.relative {
-                let cwd = env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-                diff_paths(&app.root_dir, &cwd)
-                    .unwrap_or_else(|| app.root_dir.clone())
-                    .display()
-                    .to_string()
-            } else {
-                app.root_dir.display().to_string()
-            };
-            let mut title_str = "Current Directory".to_string();
-            if app.no_path {
-                title_str.push_str(" [n: no path]");
-            } else if app.relative {
-                title_str.push_str(" [r: relative]");
-            }
-            (path, title_str, Style::default())
+            "Esc to leave search"
+        };
+        let style = if app.mode == AppMode::SearchFocused {
+            Style::default()
+                .fg(theme::MATCH)
+                .bg(theme::CURSOR_BG)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
         };
+        let path_widget = Paragraph::new(search_display)
+            .block(panel(title, true))
+            .style(style)
+            .wrap(Wrap { trim: true });
+        f.render_widget(path_widget, area);
+        return;
+    }
+
+    let mut title_str = "Current Directory".to_string();
+    if app.no_path {
+        title_str.push_str(" [n: no path]");
+    } else if app.relative {
+        title_str.push_str(" [r: relative]");
+    }
+    title_str.push_str(" [1-9: up]");
+    if app.dir_loading(&app.root_dir) {
+        title_str.push_str(" [loading…]");
+    }
+
+    // Breadcrumbs, dropping leading segments behind "…" when they don't fit.
+    const SEP: &str = " > ";
+    const ELLIPSIS: &str = "… > ";
+    let crumbs = app.breadcrumbs();
+    let width = area.width.saturating_sub(4) as usize;
+    let total = |from: usize| -> usize {
+        let body: usize = crumbs[from..].iter().map(|c| c.width()).sum::<usize>()
+            + SEP.width() * (crumbs.len() - from - 1);
+        if from > 0 {
+            body + ELLIPSIS.width()
+        } else {
+            body
+        }
+    };
+    let mut first = 0;
+    while first + 1 < crumbs.len() && total(first) > width {
+        first += 1;
+    }
 
-    let path_widget = Paragraph::new(path)
-        .block(panel(&title_str, app.mode != AppMode::Normal))
-        .style(path_style)
-        .wrap(Wrap { trim: true });
+    let row = area.y + 1;
+    let mut x = area.x + 2;
+    let mut spans = Vec::new();
+    let sep_style = Style::default().fg(theme::MUTED);
+    if first > 0 {
+        spans.push(Span::styled(ELLIPSIS, sep_style));
+        x += ELLIPSIS.width() as u16;
+    }
+    let last = crumbs.len() - 1;
+    for (i, crumb) in crumbs.iter().enumerate().skip(first) {
+        if i > first {
+            spans.push(Span::styled(SEP, sep_style));
+            x += SEP.width() as u16;
+        }
+        let w = crumb.width() as u16;
+        let style = if i == last {
+            Style::default().fg(theme::FG).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(theme::DIR)
+        };
+        spans.push(Span::styled(crumb.clone(), style));
+        if i < last {
+            app.breadcrumb_hits.push((row, x..x + w, last - i));
+        }
+        x += w;
+    }
+
+    let path_widget =
+        Paragraph::new(Line::from(spans)).block(panel(&title_str, app.mode == AppMode::GitTree));
     f.render_widget(path_widget, area);
 }
 
@@ -256,8 +309,7 @@ fn build_diff_lines(content: &str, cursor_line: Option<usize>, width: u16) -> Ve
             let is_cursor = cursor_line == Some(i);
             let text = if is_cursor {
                 style = style.bg(theme::CURSOR_BG);
-                let pad_width = width.max(line.chars().count() as u16) as usize;
-                format!("{:<pad_width$}", line)
+                pad_to_width(line, width as usize)
             } else {
                 line.to_string()
             };
@@ -267,7 +319,7 @@ fn build_diff_lines(content: &str, cursor_line: Option<usize>, width: u16) -> Ve
 }
 
 fn render_file_list(f: &mut Frame, app: &mut AppState, area: Rect, list_height: usize) {
-    if app.mode != AppMode::Normal {
+    if matches!(app.mode, AppMode::SearchFocused | AppMode::SearchNavigating) {
         let match_style = Style::default()
             .fg(theme::MATCH)
             .add_modifier(Modifier::BOLD);
@@ -320,7 +372,28 @@ fn render_file_list(f: &mut Frame, app: &mut AppState, area: Rect, list_height:
             })
             .collect();
 
-        let list = List::new(items).block(panel("Files", app.mode != AppMode::Normal));
+        let scope = if app.search_recursive {
+            "recursive · Tab: current dir"
+        } else {
+            "current dir · Tab: recursive"
+        };
+        let title = match app.search_progress() {
+            Some(found) => format!(
+                "Files ({scope}) Searching... ({} so far)",
+                crate::token_counter::format_count(found)
+            ),
+            None if app.is_searching() => format!("Files ({scope}) Searching..."),
+            None if !app.search_query.is_empty() => {
+                let n = app.search_results.len();
+                format!(
+                    "Files ({scope}) {} match{}",
+                    crate::token_counter::format_count(n),
+                    if n == 1 { "" } else { "es" }
+                )
+            }
+            None => format!("Files ({scope})"),
+        };
+        let list = List::new(items).block(panel(&title, app.mode != AppMode::Normal));
         f.render_widget(list, area);
 
         let mut sb_state =
@@ -345,24 +418,47 @@ fn render_file_list(f: &mut Frame, app: &mut AppState, area: Rect, list_height:
     let open = app.tree_state.opened().clone();
     let visible_dirs = collect_visible_dirs(&app.root_dir, &app.dir_cache, &open);
     let fully_selected_dirs: HashSet<PathBuf> = visible_dirs
-        .into_iter()
+        .iter()
         .filter(|d| app.dir_fully_selected(d))
+        .cloned()
+        .collect();
+    let selected_counts: HashMap<PathBuf, usize> = visible_dirs
+        .into_iter()
+        .filter_map(|d| {
+            let n = app.selected_count_under(&d);
+            (n > 0).then_some((d, n))
+        })
         .collect();
 
-    let items = build_styled_tree_items(
+    let mut items = build_styled_tree_items(
         &app.root_dir,
         &app.dir_cache,
         &open,
         &app.selected,
         &fully_selected_dirs,
+        &selected_counts,
     );
-
+    items.retain(|item| {
+        item.identifier()
+            .file_name()
+            .is_some_and(|name| app.filter_matches(name))
+    });
+
+    let mut title = format!("Files (sort: {})", app.sort_mode.label());
+    if app.mode == AppMode::Filtering {
+        title.push_str(&format!(
+            " [filter: {}▏ Enter keep, Esc clear]",
+            app.dir_filter
+        ));
+    } else if !app.dir_filter.is_empty() {
+        title.push_str(&format!(" [filter: {}, Esc clear]", app.dir_filter));
+    }
     let Ok(tree_widget) = Tree::new(&items) else {
-        f.render_widget(panel("Files", true), area);
+        f.render_widget(panel(&title, true), area);
         return;
     };
     let tree_widget = tree_widget
-        .block(panel("Files", app.mode == AppMode::Normal))
+        .block(panel(&title, true))
         .highlight_style(
             Style::default()
                 .bg(theme::CURSOR_BG)
@@ -372,10 +468,128 @@ fn render_file_list(f: &mut Frame, app: &mut AppState, area: Rect, list_height:
     f.render_stateful_widget(tree_widget, area, &mut app.tree_state);
 }
 
+/// The file (or directory listing) under the cursor, as far as it fits.
+fn render_preview(f: &mut Frame, app: &AppState, area: Rect) {
+    let (title, lines) = match &app.preview {
+        Some(preview) => {
+            let name = preview
+                .path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let height = area.height as usize;
+            let lines = preview
+                .text
+                .lines()
+                .take(height)
+                .map(ansi_line)
+                .collect::<Vec<_>>();
+            (format!("Preview: {name}"), lines)
+        }
+        None => ("Preview".to_string(), Vec::new()),
+    };
+    f.render_widget(Paragraph::new(lines).block(panel(&title, false)), area);
+}
+
+/// Turn one line of command output into styled spans, honouring SGR colour
+/// and attribute codes and dropping every other escape sequence.
+fn ansi_line(line: &str) -> Line<'static> {
+    let mut spans = Vec::new();
+    let mut style = Style::default().fg(theme::FG);
+    let mut text = String::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => {
+                match chars.next() {
+                    Some('[') => {
+                        let mut params = String::new();
+                        let mut last = None;
+                        for c in chars.by_ref() {
+                            if ('\x40'..='\x7e').contains(&c) {
+                                last = Some(c);
+                                break;
+                            }
+                            params.push(c);
+                        }
+                        if last == Some('m') {
+                            if !text.is_empty() {
+                                spans.push(Span::styled(std::mem::take(&mut text), style));
+                            }
+                            style = apply_sgr(style, &params);
+                        }
+                    }
+                    // OSC (e.g. hyperlinks): skip to BEL or ST.
+                    Some(']') => {
+                        while let Some(c) = chars.next() {
+                            if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                                break;
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            '\t' => text.push_str("    "),
+            '\r' => {}
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        spans.push(Span::styled(text, style));
+    }
+    Line::from(spans)
+}
+
+fn apply_sgr(mut style: Style, params: &str) -> Style {
+    let mut codes = params
+        .split(';')
+        .map(|p| p.parse::<u8>().unwrap_or(0))
+        .collect::<Vec<_>>()
+        .into_iter();
+    while let Some(code) = codes.next() {
+        style = match code {
+            0 => Style::default().fg(theme::FG),
+            1 => style.add_modifier(Modifier::BOLD),
+            2 => style.add_modifier(Modifier::DIM),
+            3 => style.add_modifier(Modifier::ITALIC),
+            4 => style.add_modifier(Modifier::UNDERLINED),
+            7 => style.add_modifier(Modifier::REVERSED),
+            22 => style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style.remove_modifier(Modifier::ITALIC),
+            24 => style.remove_modifier(Modifier::UNDERLINED),
+            27 => style.remove_modifier(Modifier::REVERSED),
+            30..=37 => style.fg(Color::Indexed(code - 30)),
+            90..=97 => style.fg(Color::Indexed(code - 90 + 8)),
+            39 => style.fg(theme::FG),
+            40..=47 => style.bg(Color::Indexed(code - 40)),
+            100..=107 => style.bg(Color::Indexed(code - 100 + 8)),
+            49 => style.bg(Color::Reset),
+            38 | 48 => {
+                let color = match codes.next() {
+                    Some(5) => codes.next().map(Color::Indexed),
+                    Some(2) => match (codes.next(), codes.next(), codes.next()) {
+                        (Some(r), Some(g), Some(b)) => Some(Color::Rgb(r, g, b)),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                match (code, color) {
+                    (38, Some(c)) => style.fg(c),
+                    (_, Some(c)) => style.bg(c),
+                    _ => style,
+                }
+            }
+            _ => style,
+        };
+    }
+    style
+}
+
 fn render_status_bar(
     f: &mut Frame,
     area: Rect,
-    message: &str,
+    notice: Option<&Notice>,
     file_count: usize,
     loc_count: u64,
     is_git_mode: bool,
@@ -383,16 +597,22 @@ fn render_status_bar(
     let hint_str = if is_git_mode {
         "space select   d toggle diff   c copy   ? help   q quit "
     } else {
-        "space select   c copy   ? help   q quit "
+        "space select   v preview   c copy   ? help   q quit "
     };
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([
             Constraint::Min(1),
-            Constraint::Length(hint_str.len() as u16),
+            Constraint::Length(hint_str.width() as u16),
         ])
         .split(area);
-    if message.is_empty() {
+    if let Some(notice) = notice {
+        let line = Line::from(vec![Span::styled(
+            format!(" {}", notice.text),
+            severity_style(notice.severity),
+        )]);
+        f.render_widget(Paragraph::new(line), chunks[0]);
+    } else {
         let left = Line::from(vec![Span::styled(
             format!(
                 " {file_count} file{} selected | {loc_count} LOC",
@@ -401,12 +621,6 @@ fn render_status_bar(
             Style::default().fg(theme::SELECTED),
         )]);
         f.render_widget(Paragraph::new(left), chunks[0]);
-    } else {
-        let error = Line::from(vec![Span::styled(
-            format!(" {message}"),
-            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
-        )]);
-        f.render_widget(Paragraph::new(error), chunks[0]);
     }
 
     let hint = Line::from(vec![Span::styled(
@@ -416,8 +630,57 @@ fn render_status_bar(
     f.render_widget(Paragraph::new(hint), chunks[1]);
 }
 
+fn severity_style(severity: Severity) -> Style {
+    match severity {
+        Severity::Info => Style::default().fg(theme::FG),
+        Severity::Warn => Style::default().fg(theme::MATCH),
+        Severity::Error => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+    }
+}
+
+/// Recent status-bar notices, newest at the bottom; any key closes it.
+fn render_message_log(f: &mut Frame, area: Rect, notes: &Notifications) {
+    let modal = area.inner(Margin {
+        vertical: 1,
+        horizontal: 2,
+    });
+    f.render_widget(Clear, modal);
+
+    let block = panel("Messages", true);
+    let inner = block.inner(modal);
+    f.render_widget(block, modal);
+
+    let now = Instant::now();
+    let mut lines: Vec<Line> = notes
+        .log()
+        .rev()
+        .take(inner.height as usize)
+        .map(|n| {
+            Line::from(vec![
+                Span::styled(
+                    format!("{:>5}s ago  ", now.duration_since(n.at).as_secs()),
+                    Style::default().fg(theme::MUTED),
+                ),
+                Span::styled(n.text.clone(), severity_style(n.severity)),
+            ])
+        })
+        .collect();
+    if lines.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "No messages yet",
+            Style::default().fg(theme::MUTED),
+        )));
+    }
+    lines.reverse();
+    f.render_widget(Paragraph::new(lines), inner);
+}
+
+/// Full-screen keybinding reference; any key closes it.
 fn render_help_overlay(f: &mut Frame, area: Rect) {
-    let modal = centered_rect(60, 85, area);
+    let modal = area.inner(Margin {
+        vertical: 1,
+        horizontal: 2,
+    });
     f.render_widget(Clear, modal);
 
     let block = panel("Keybindings", true);
@@ -439,69 +702,113 @@ fn render_help_overlay(f: &mut Frame, area: Rect) {
     f.render_widget(Paragraph::new(help_lines), content_area);
 
     let close_hint = Line::from(vec![Span::styled(
-        "? / Esc  close ",
+        "any key  close ",
         Style::default().fg(theme::MUTED),
     )])
     .right_aligned();
     f.render_widget(Paragraph::new(close_hint), hint_area);
 }
 
-fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
-    let vertical = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Percentage((100 - percent_y) / 2),
-            Constraint::Percentage(percent_y),
-            Constraint::Percentage((100 - percent_y) / 2),
-        ])
-        .split(area);
-    Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Percentage((100 - percent_x) / 2),
-            Constraint::Percentage(percent_x),
-            Constraint::Percentage((100 - percent_x) / 2),
-        ])
-        .split(vertical[1])[1]
+/// `text` padded with spaces to `width` terminal columns; wide (CJK, emoji)
+/// characters count as two. Text already that wide is returned as is.
+fn pad_to_width(text: &str, width: usize) -> String {
+    let pad = width.saturating_sub(text.width());
+    format!("{text}{}", " ".repeat(pad))
 }
 
-/// One keybinding per line, key padded to the width of the longest key.
+/// Every keybinding, grouped by where it applies; keys are padded to the
+/// width of the longest key so descriptions line up.
 fn build_help_lines() -> Vec<Line<'static>> {
-    const ALL: &[(&str, &str)] = &[
-        ("↑/k", "Move up"),
-        ("↓/j", "Move down"),
-        ("←/h", "Collapse dir"),
-        ("→/l", "Expand dir"),
-        ("Enter", "Toggle expand"),
-        ("Backspace", "Parent dir"),
-        ("Space", "Select/Unselect"),
-        ("d", "Toggle diff (Git mode)"),
-        ("/ or Ctrl-f", "Search files"),
-        ("Tab", "Toggle git tree"),
-        ("?", "Toggle help"),
-        ("c", "Confirm selection"),
-        ("p", "Restore last selection"),
-        ("q/Ctrl-c", "Quit"),
-        ("r", "Toggle relative path"),
-        ("n", "Toggle no path headers"),
+    const SECTIONS: &[(&str, &[(&str, &str)])] = &[
+        (
+            "Navigation",
+            &[
+                ("↑/k", "Move up"),
+                ("↓/j", "Move down"),
+                ("←/h", "Collapse dir"),
+                ("→/l", "Expand dir"),
+                ("Enter", "Toggle expand"),
+                ("Backspace", "Parent dir (root list in multi-root mode)"),
+                ("1-9", "Jump that many dirs up (or click a path segment)"),
+                ("s", "Cycle sort: name/modified/size"),
+                ("v", "Toggle preview pane"),
+            ],
+        ),
+        (
+            "Selection",
+            &[
+                ("Space", "Select/Unselect"),
+                ("p", "Restore last selection"),
+                ("C C", "Clear the whole selection"),
+                ("c", "Confirm selection and copy"),
+                ("Ctrl-y", "Copy selection and stay in the TUI"),
+                ("y", "Copy path under cursor"),
+            ],
+        ),
+        (
+            "Search",
+            &[
+                ("/ or Ctrl-f", "Search files"),
+                ("Enter/↓", "Move from query to results"),
+                ("Tab", "Toggle recursive search"),
+                ("Esc", "Leave search"),
+                ("f", "Filter this directory as you type"),
+                ("Esc", "Clear the filter"),
+            ],
+        ),
+        (
+            "Git tree",
+            &[
+                ("Tab", "Enter git tree / switch panel"),
+                ("Space", "Mark commit / select file"),
+                ("d", "Toggle diff"),
+                ("Esc", "Back to files"),
+            ],
+        ),
+        (
+            "Headers & general",
+            &[
+                ("r", "Toggle relative path"),
+                ("n", "Toggle no path headers"),
+                ("m", "Show recent messages"),
+                ("?", "Show this help"),
+                ("q/Ctrl-c", "Quit"),
+            ],
+        ),
     ];
 
-    let key_width = ALL.iter().map(|(k, _)| k.len()).max().unwrap_or(0);
-
-    ALL.iter()
-        .map(|(key, desc)| {
-            Line::from(vec![
+    let key_width = SECTIONS
+        .iter()
+        .flat_map(|(_, keys)| keys.iter())
+        .map(|(k, _)| k.width())
+        .max()
+        .unwrap_or(0);
+
+    let mut lines = Vec::new();
+    for (i, (title, keys)) in SECTIONS.iter().enumerate() {
+        if i > 0 {
+            lines.push(Line::default());
+        }
+        lines.push(Line::from(Span::styled(
+            title.to_string(),
+            Style::default()
+                .fg(theme::MUTED)
+                .add_modifier(Modifier::BOLD),
+        )));
+        for (key, desc) in keys.iter() {
+            lines.push(Line::from(vec![
                 Span::styled(
-                    format!("{:<key_width$}", key),
+                    format!("  {}", pad_to_width(key, key_width)),
                     Style::default()
                         .fg(theme::BORDER_FOCUS)
                         .add_modifier(Modifier::BOLD),
                 ),
                 Span::styled("  —  ", Style::default().fg(theme::MUTED)),
                 Span::styled(desc.to_string(), Style::default().fg(theme::FG)),
-            ])
-        })
-        .collect()
+            ]));
+        }
+    }
+    lines
 }
 
 /// Collect all directory paths visible in the current tree view
@@ -576,6 +883,7 @@ fn build_styled_tree_items(
     open: &HashSet<Vec<PathBuf>>,
     selected: &HashSet<PathBuf>,
     fully_selected_dirs: &HashSet<PathBuf>,
+    selected_counts: &HashMap<PathBuf, usize>,
 ) -> Vec<TreeItem<'static, PathBuf>> {
     let entries = match dir_cache.get(dir) {
         Some(e) => e,
@@ -588,7 +896,9 @@ fn build_styled_tree_items(
             let path = entry.path();
             let is_dir = entry.is_dir();
             let raw_name = entry.file_name().to_string_lossy().to_string();
-            let display_name = if is_dir {
+            let display_name = if entry.symlink_target().is_some() {
+                format!("{raw_name}@")
+            } else if is_dir {
                 format!("{}/", raw_name)
             } else {
                 raw_name
@@ -606,7 +916,7 @@ fn build_styled_tree_items(
             } else {
                 Style::default().fg(theme::FG)
             };
-            let text = Line::from(vec![
+            let mut spans = vec![
                 Span::styled(
                     marker,
                     Style::default()
@@ -614,12 +924,33 @@ fn build_styled_tree_items(
                         .add_modifier(Modifier::BOLD),
                 ),
                 Span::styled(display_name, name_style),
-            ]);
+            ];
+            if let Some(target) = entry.symlink_target() {
+                spans.push(Span::styled(
+                    format!(" -> {}", target.display()),
+                    Style::default().fg(theme::MUTED),
+                ));
+            }
+            // Show how many files a (partly) selected directory contributes.
+            if let Some(&n) = selected_counts.get(&path) {
+                spans.push(Span::styled(
+                    format!(" ({n} file{})", if n == 1 { "" } else { "s" }),
+                    Style::default().fg(theme::MUTED),
+                ));
+            }
+            let text = Line::from(spans);
 
             if is_dir {
                 let is_open = open.iter().any(|kp| kp.last() == Some(&path));
                 let children = if is_open {
-                    build_styled_tree_items(&path, dir_cache, open, selected, fully_selected_dirs)
+                    build_styled_tree_items(
+                        &path,
+                        dir_cache,
+                        open,
+                        selected,
+                        fully_selected_dirs,
+                        selected_counts,
+                    )
                 } else {
                     match dir_cache.get(&path) {
                         Some(sub_entries) if !sub_entries.is_empty() => sub_entries
@@ -643,3 +974,27 @@ fn build_styled_tree_items(
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pads_by_display_width() {
+        let dir = tempfile::tempdir().unwrap();
+        let name = "資料_📄.txt";
+        std::fs::write(dir.path().join(name), "").unwrap();
+        let listed = std::fs::read_dir(dir.path())
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .file_name();
+        let listed = listed.to_str().unwrap();
+
+        assert_eq!(listed.width(), 11);
+        assert_eq!(pad_to_width(listed, 13), format!("{name}  "));
+        assert_eq!(pad_to_width(listed, 4), name);
+        assert_eq!(pad_to_width("↑/k", 5).width(), 5);
+    }
+}
@@ -75,6 +75,628 @@ fn prints_content_without_headers() {
         .stdout(predicates::str::contains("--- File:").not());
 }
 
+#[test]
+fn separates_headerless_files_with_blank_line() {
+    let dir = tempdir().unwrap();
+    let a = dir.path().join("a.txt");
+    let b = dir.path().join("b.txt");
+    fs::write(&a, "first").unwrap();
+    fs::write(&b, "second\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("cxt").unwrap();
+    cmd.args([
+        "--ci",
+        "-p",
+        "--content-only",
+        a.to_str().unwrap(),
+        b.to_str().unwrap(),
+    ])
+    .assert()
+    .success()
+    .stdout(predicates::str::contains(
+        "first\n</file>\n\n<file>\nsecond\n",
+    ));
+}
+
+#[test]
+fn prints_wc_counts_to_stderr() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("test.txt");
+    fs::write(&file_path, "one two\nthree\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("cxt").unwrap();
+    cmd.args(["--ci", "-n", "-p", "--wc", file_path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("one two"))
+        .stderr(predicates::str::contains("lines,"))
+        .stderr(predicates::str::contains("words,"));
+}
+
+#[test]
+fn hash_matches_the_written_bytes() {
+    use sha2::{Digest, Sha256};
+
+    let dir = tempdir().unwrap();
+    let input = dir.path().join("a.txt");
+    let output = dir.path().join("out.txt");
+    fs::write(&input, "caf\u{e9}\n").unwrap();
+
+    let assert = Command::cargo_bin("cxt")
+        .unwrap()
+        .args(["--ci", "--ascii-only", "--hash", "-w"])
+        .args([&output, &input])
+        .assert()
+        .success();
+    let stderr = String::from_utf8(assert.get_output().stderr.clone()).unwrap();
+    let expected: String = Sha256::digest(fs::read(&output).unwrap())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    assert!(stderr.contains(&format!("sha256: {expected}")), "{stderr}");
+}
+
+#[test]
+fn report_json_describes_the_destination() {
+    let dir = tempdir().unwrap();
+    let input = dir.path().join("a.txt");
+    let output = dir.path().join("out.txt");
+    fs::write(&input, "alpha\n").unwrap();
+
+    let assert = Command::cargo_bin("cxt")
+        .unwrap()
+        .args(["--ci", "--report-json", "-w"])
+        .args([&output, &input])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+    let report: serde_json::Value = serde_json::from_str(stdout.trim()).unwrap();
+    assert_eq!(report["destination"], "file");
+    assert_eq!(report["files"], 1);
+    assert_eq!(report["bytes"], fs::metadata(&output).unwrap().len());
+    assert_eq!(report["paths"][0], output.to_str().unwrap());
+}
+
+#[test]
+fn embed_command_prepends_the_invocation() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.txt"), "alpha\n").unwrap();
+
+    Command::cargo_bin("cxt")
+        .unwrap()
+        .current_dir(dir.path())
+        .args(["--ci", "-p", "--embed-command", "a.txt"])
+        .assert()
+        .success()
+        .stdout(predicates::str::starts_with(
+            "# Generated by: cxt --ci -p --embed-command a.txt\n# Generated at: ",
+        ))
+        .stdout(predicates::str::contains("alpha"));
+}
+
+#[test]
+fn estimate_cost_prints_price_for_known_models_only() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("test.txt");
+    fs::write(&file_path, "one two\nthree\n").unwrap();
+
+    Command::cargo_bin("cxt")
+        .unwrap()
+        .args(["--ci", "-p", "--estimate-cost", "gpt-4o"])
+        .arg(&file_path)
+        .assert()
+        .success()
+        .stderr(predicates::str::contains("tokens ≈ $"))
+        .stderr(predicates::str::contains("(gpt-4o input)"));
+    Command::cargo_bin("cxt")
+        .unwrap()
+        .args(["--ci", "-p", "--estimate-cost", "gpt-0"])
+        .arg(&file_path)
+        .assert()
+        .failure();
+}
+
+#[test]
+fn prints_ext_summary_without_content() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.rs"), "fn a() {}").unwrap();
+    fs::write(dir.path().join("b.rs"), "fn b() {}").unwrap();
+    fs::write(dir.path().join("notes.md"), "# notes").unwrap();
+
+    let mut cmd = Command::cargo_bin("cxt").unwrap();
+    cmd.args(["--ci", "--ext-summary", dir.path().to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicates::str::is_match(r"rs\s+2\s+18").unwrap())
+        .stdout(predicates::str::is_match(r"md\s+1\s+7").unwrap())
+        .stdout(predicates::str::contains("fn a()").not());
+}
+
+#[test]
+fn applies_patterns_from_ignore_file() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("keep.txt"), "Kept content").unwrap();
+    fs::write(dir.path().join("drop.log"), "Dropped content").unwrap();
+    let ignore_file = dir.path().join("custom.ignore");
+    fs::write(&ignore_file, "# comment\n*.log\n*.ignore\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("cxt").unwrap();
+    cmd.args([
+        "--ci",
+        "-p",
+        "--ignore-file",
+        ignore_file.to_str().unwrap(),
+        dir.path().to_str().unwrap(),
+    ])
+    .assert()
+    .success()
+    .stdout(predicates::str::contains("Kept content"))
+    .stdout(predicates::str::contains("Dropped content").not());
+}
+
+#[test]
+fn exclude_generated_skips_build_output() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("dist")).unwrap();
+    fs::write(dir.path().join("dist").join("bundle.js"), "bundled()").unwrap();
+    fs::write(dir.path().join("app.min.js"), "minified()").unwrap();
+    fs::write(dir.path().join("api.pb.go"), "package api").unwrap();
+    fs::write(dir.path().join("app.js"), "source()").unwrap();
+
+    let mut cmd = Command::cargo_bin("cxt").unwrap();
+    cmd.args([
+        "--ci",
+        "-p",
+        "--exclude-generated",
+        dir.path().to_str().unwrap(),
+    ])
+    .assert()
+    .success()
+    .stdout(predicates::str::contains("source()"))
+    .stdout(predicates::str::contains("bundled()").not())
+    .stdout(predicates::str::contains("minified()").not())
+    .stdout(predicates::str::contains("package api").not());
+}
+
+#[test]
+fn ignore_file_patterns_are_anchored_at_its_directory() {
+    let dir = tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("build")).unwrap();
+    fs::create_dir_all(dir.path().join("sub/build")).unwrap();
+    fs::write(dir.path().join("build/top.txt"), "Top build").unwrap();
+    fs::write(dir.path().join("sub/build/nested.txt"), "Nested build").unwrap();
+    let ignore_file = dir.path().join("sub/.myignore");
+    fs::write(&ignore_file, "/build\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("cxt").unwrap();
+    cmd.args([
+        "--ci",
+        "--ignore-file",
+        ignore_file.to_str().unwrap(),
+        dir.path().to_str().unwrap(),
+    ])
+    .assert()
+    .success()
+    .stdout(predicates::str::contains("Top build"))
+    .stdout(predicates::str::contains("Nested build").not());
+}
+
+#[test]
+fn error_on_missing_ignore_file() {
+    let mut cmd = Command::cargo_bin("cxt").unwrap();
+    cmd.args(["--ci", "--ignore-file", "no_such.ignore", "."])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("Cannot read ignore file"));
+}
+
+#[test]
+fn includes_stdin_content_as_virtual_file() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("a.rs");
+    fs::write(&file_path, "fn a() {}").unwrap();
+
+    let mut cmd = Command::cargo_bin("cxt").unwrap();
+    cmd.args([
+        "--ci",
+        "-p",
+        "--stdin-content=notes.txt",
+        file_path.to_str().unwrap(),
+    ])
+    .write_stdin("piped notes\n")
+    .assert()
+    .success()
+    .stdout(predicates::str::contains(
+        "<file path=\"notes.txt\">\npiped notes\n</file>",
+    ))
+    .stdout(predicates::str::contains("fn a() {}"));
+}
+
+#[test]
+fn resolves_stdin_paths_against_paths_base() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/lib.rs"), "pub fn based() {}").unwrap();
+
+    let mut cmd = Command::cargo_bin("cxt").unwrap();
+    cmd.args(["--ci", "-p", "--paths-base", dir.path().to_str().unwrap()])
+        .write_stdin("src/lib.rs\n")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("pub fn based() {}"));
+}
+
+#[test]
+fn dot_slash_prefixes_relative_headers() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/lib.rs"), "pub fn lib() {}").unwrap();
+
+    let mut cmd = Command::cargo_bin("cxt").unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--ci", "-p", "-r", "--posix-paths", "--dot-slash", "src"])
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("<file path=\"./src/lib.rs\">"));
+}
+
+#[test]
+fn only_tracked_skips_untracked_files_from_a_subdirectory() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("sub")).unwrap();
+    fs::write(dir.path().join("sub/kept.txt"), "TRACKED").unwrap();
+    fs::write(dir.path().join("sub/scratch.txt"), "UNTRACKED").unwrap();
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .current_dir(dir.path())
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    git(&["init", "-q"]);
+    git(&["add", "sub/kept.txt"]);
+
+    let mut cmd = Command::cargo_bin("cxt").unwrap();
+    cmd.current_dir(dir.path().join("sub"))
+        .args(["--ci", "-p", "--only-tracked", "."])
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("TRACKED"))
+        .stdout(predicates::str::contains("UNTRACKED").not());
+
+    let outside = tempdir().unwrap();
+    let mut cmd = Command::cargo_bin("cxt").unwrap();
+    cmd.args([
+        "--ci",
+        "-p",
+        "--only-tracked",
+        outside.path().to_str().unwrap(),
+    ])
+    .assert()
+    .failure()
+    .stderr(predicates::str::contains("not inside a git repository"));
+}
+
+#[test]
+fn include_submodules_reads_initialized_submodules() {
+    let dir = tempdir().unwrap();
+    let git = |cwd: &std::path::Path, args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .current_dir(cwd)
+            .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+            .args(["-c", "protocol.file.allow=always"])
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    let lib = dir.path().join("lib");
+    let app = dir.path().join("app");
+    fs::create_dir(&lib).unwrap();
+    fs::create_dir(&app).unwrap();
+    fs::write(lib.join("lib.rs"), "SUBMODULE CODE").unwrap();
+    git(&lib, &["init", "-q"]);
+    git(&lib, &["add", "."]);
+    git(&lib, &["commit", "-qm", "lib"]);
+    fs::write(app.join("main.rs"), "APP CODE").unwrap();
+    git(&app, &["init", "-q"]);
+    git(&app, &["add", "main.rs"]);
+    git(&app, &["submodule", "add", "-q", "../lib", "vendor/lib"]);
+    let ghost = format!("160000,{},ghost", "1".repeat(40));
+    git(&app, &["update-index", "--add", "--cacheinfo", &ghost]);
+
+    let run = |extra: &[&str]| {
+        Command::cargo_bin("cxt")
+            .unwrap()
+            .current_dir(&app)
+            .args(["--ci", "--only-tracked"])
+            .args(extra)
+            .arg(".")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("APP CODE"))
+    };
+    run(&[]).stdout(predicates::str::contains("SUBMODULE CODE").not());
+    run(&["--include-submodules"])
+        .stdout(predicates::str::contains("SUBMODULE CODE"))
+        .stderr(predicates::str::contains(
+            "submodule './ghost' is not initialized",
+        ));
+}
+
+#[test]
+fn since_ref_with_diffstat_annotates_changed_files() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("changed.txt"), "one\ntwo\n").unwrap();
+    fs::write(dir.path().join("same.txt"), "SAME\n").unwrap();
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .current_dir(dir.path())
+            .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    git(&["init", "-q"]);
+    git(&["add", "."]);
+    git(&["commit", "-qm", "base"]);
+    fs::write(dir.path().join("changed.txt"), "one\nTWO\nthree\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("cxt").unwrap();
+    cmd.current_dir(dir.path())
+        .args([
+            "--ci",
+            "-p",
+            "--since-ref",
+            "HEAD",
+            "--with-diffstat",
+            "--with-diff",
+        ])
+        .assert()
+        .success()
+        .stdout(predicates::str::starts_with("<context>"))
+        .stdout(predicates::str::contains("meta=\"+2 -1\""))
+        .stdout(predicates::str::contains("=== Diff vs HEAD ==="))
+        .stdout(predicates::str::contains("+three"))
+        .stdout(predicates::str::contains("SAME").not())
+        .stderr(predicates::str::contains("  changed.txt\n"));
+
+    // Nothing changed: no content, but --report-json still prints its one line.
+    git(&["commit", "-qam", "change"]);
+    let mut cmd = Command::cargo_bin("cxt").unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--ci", "--since-ref", "HEAD", "--report-json"])
+        .assert()
+        .success()
+        .stdout("{\"bytes\":0,\"destination\":\"stdout\",\"files\":0}\n")
+        .stderr(predicates::str::contains("No changed files since HEAD."));
+}
+
+#[test]
+fn summarizes_skipped_files_at_the_end() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.rs"), "fn a() {}").unwrap();
+    fs::write(dir.path().join("blob.bin"), [0u8, 159, 146, 150]).unwrap();
+
+    let mut cmd = Command::cargo_bin("cxt").unwrap();
+    cmd.args(["--ci", "-p", dir.path().to_str().unwrap()])
+        .assert()
+        .success()
+        .stderr(predicates::str::contains("(1 file skipped: 1 binary"))
+        .stderr(predicates::str::contains("blob.bin").not());
+
+    let mut cmd = Command::cargo_bin("cxt").unwrap();
+    cmd.args(["--ci", "-p", "--verbose", dir.path().to_str().unwrap()])
+        .assert()
+        .success()
+        .stderr(predicates::str::contains("blob.bin: binary"));
+}
+
+#[test]
+fn ascii_only_replaces_non_ascii_characters() {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("quotes.txt");
+    fs::write(&file_path, "\u{201c}hi\u{201d} caf\u{e9}").unwrap();
+
+    let mut cmd = Command::cargo_bin("cxt").unwrap();
+    cmd.args([
+        "--ci",
+        "-p",
+        "-n",
+        "--ascii-only=?",
+        file_path.to_str().unwrap(),
+    ])
+    .assert()
+    .success()
+    .stdout(predicates::str::contains("?hi? caf?"))
+    .stderr(predicates::str::contains("3 non-ASCII characters replaced"));
+}
+
+#[test]
+fn no_final_newline_trims_only_the_last_newline() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.txt"), "AAA\n").unwrap();
+    fs::write(dir.path().join("b.txt"), "BBB\n").unwrap();
+
+    let out = Command::cargo_bin("cxt")
+        .unwrap()
+        .current_dir(dir.path())
+        .args(["--ci", "-p", "-n", "--no-final-newline", "a.txt", "b.txt"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert!(stdout.ends_with("</context>"));
+    assert!(stdout.contains("AAA\n</file>\n\n<file>\nBBB\n"));
+}
+
+#[cfg(unix)]
+#[test]
+fn exec_runs_command_per_file_and_batched() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.txt"), "AAA").unwrap();
+    fs::write(dir.path().join("b.txt"), "BBB").unwrap();
+
+    let mut cmd = Command::cargo_bin("cxt").unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--ci", "--exec", "echo got {}", "a.txt", "b.txt"])
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("got "))
+        .stdout(predicates::str::contains("AAA").not());
+
+    let out = Command::cargo_bin("cxt")
+        .unwrap()
+        .current_dir(dir.path())
+        .args(["--ci", "--exec", "echo {} +", "a.txt", "b.txt"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert_eq!(stdout.lines().count(), 1);
+    assert!(stdout.contains("a.txt") && stdout.contains("b.txt"));
+}
+
+#[test]
+fn as_uri_list_prints_file_uris_instead_of_content() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a b.txt"), "AAA").unwrap();
+
+    let out = Command::cargo_bin("cxt")
+        .unwrap()
+        .current_dir(dir.path())
+        .args(["--ci", "-p", "--as-uri-list", "."])
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert!(stdout.starts_with("file://"));
+    assert!(stdout.ends_with("/a%20b.txt\r\n"));
+    assert!(!stdout.contains("AAA"));
+}
+
+#[test]
+fn chunk_by_tokens_writes_one_file_per_chunk() {
+    let dir = tempdir().unwrap();
+    for name in ["a.txt", "b.txt", "c.txt"] {
+        fs::write(dir.path().join(name), "word ".repeat(300)).unwrap();
+    }
+
+    let mut cmd = Command::cargo_bin("cxt").unwrap();
+    cmd.current_dir(dir.path())
+        .args([
+            "--ci",
+            "-r",
+            "-w",
+            "out.txt",
+            "--chunk-by-tokens",
+            "700",
+            "a.txt",
+            "b.txt",
+            "c.txt",
+        ])
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Wrote 2 chunks"));
+
+    let first = fs::read_to_string(dir.path().join("out.1.txt")).unwrap();
+    let second = fs::read_to_string(dir.path().join("out.2.txt")).unwrap();
+    assert!(first.contains("a.txt") && first.contains("b.txt"));
+    assert!(second.contains("c.txt") && !second.contains("a.txt"));
+    assert!(second.starts_with("<context>"));
+}
+
+#[test]
+fn append_with_dedup_skips_files_already_in_target() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.txt"), "AAA").unwrap();
+    fs::write(dir.path().join("b.txt"), "BBB").unwrap();
+
+    let append = |files: &[&str]| {
+        let mut cmd = Command::cargo_bin("cxt").unwrap();
+        cmd.current_dir(dir.path())
+            .args(["--ci", "-r", "-w", "ctx.txt", "--append"])
+            .arg("--dedup-headers-in-append")
+            .args(files)
+            .assert()
+            .success();
+    };
+    append(&["a.txt"]);
+    append(&["a.txt", "b.txt"]);
+
+    let content = fs::read_to_string(dir.path().join("ctx.txt")).unwrap();
+    assert_eq!(content.matches("AAA").count(), 1);
+    assert_eq!(content.matches("BBB").count(), 1);
+}
+
+#[test]
+fn manifest_stdout_lists_paths_while_content_goes_to_file() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/a.rs"), "AAA").unwrap();
+    fs::write(dir.path().join("src/b.rs"), "BBB").unwrap();
+
+    let out = Command::cargo_bin("cxt")
+        .unwrap()
+        .current_dir(dir.path())
+        .args(["--ci", "--manifest-stdout", "-w", "out.txt", "src"])
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+    let sep = std::path::MAIN_SEPARATOR;
+    assert_eq!(
+        String::from_utf8(out.stdout).unwrap(),
+        format!("src{sep}a.rs\nsrc{sep}b.rs\n")
+    );
+    assert!(String::from_utf8(out.stderr).unwrap().contains("Wrote"));
+    let content = fs::read_to_string(dir.path().join("out.txt")).unwrap();
+    assert!(content.contains("AAA") && content.contains("BBB"));
+}
+
+#[test]
+fn list_preview_shows_each_first_line() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join("a.rs"),
+        "\n\n  // generated by protoc\nfn a() {}\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("b.txt"), "x".repeat(100)).unwrap();
+
+    let out = Command::cargo_bin("cxt")
+        .unwrap()
+        .current_dir(dir.path())
+        .args([
+            "--ci",
+            "--manifest-stdout",
+            "--list-preview",
+            "a.rs",
+            "b.txt",
+        ])
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+    assert_eq!(
+        String::from_utf8(out.stdout).unwrap(),
+        format!("a.rs  // generated by protoc\nb.txt  {}…\n", "x".repeat(59))
+    );
+}
+
+#[test]
+fn clipboard_paginate_needs_a_terminal() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.txt"), "alpha\n").unwrap();
+
+    Command::cargo_bin("cxt")
+        .unwrap()
+        .current_dir(dir.path())
+        .args(["--clipboard-paginate", "100", "a.txt"])
+        .write_stdin("")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("needs a terminal"));
+}
+
 #[test]
 fn writes_content_to_file() {
     let dir = tempdir().unwrap();
@@ -96,6 +718,138 @@ fn writes_content_to_file() {
     assert!(content.contains("Test content"));
 }
 
+#[test]
+fn repeated_write_fills_every_target() {
+    let dir = tempdir().unwrap();
+    let input_file = dir.path().join("input.txt");
+    fs::write(&input_file, "Shared content").unwrap();
+    fs::create_dir(dir.path().join("shared")).unwrap();
+
+    let mut cmd = Command::cargo_bin("cxt").unwrap();
+    cmd.current_dir(dir.path())
+        .args([
+            "--ci",
+            "-w",
+            "local.txt",
+            "-w",
+            "shared/ctx.txt",
+            "input.txt",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("to local.txt, shared/ctx.txt."));
+
+    let local = fs::read_to_string(dir.path().join("local.txt")).unwrap();
+    assert!(local.contains("Shared content"));
+    assert_eq!(
+        local,
+        fs::read_to_string(dir.path().join("shared/ctx.txt")).unwrap()
+    );
+}
+
+// Without a display there is no clipboard, so the copy must land in the fallback file.
+#[cfg(target_os = "linux")]
+#[test]
+fn headless_clipboard_falls_back_to_file() {
+    let dir = tempdir().unwrap();
+    let input_file = dir.path().join("input.txt");
+    let fallback = dir.path().join("fallback.txt");
+    fs::write(&input_file, "headless content").unwrap();
+
+    let mut cmd = Command::cargo_bin("cxt").unwrap();
+    for var in ["DISPLAY", "WAYLAND_DISPLAY", "WSL_DISTRO_NAME", "WSL_ENV"] {
+        cmd.env_remove(var);
+    }
+    cmd.args([
+        "--clipboard-fallback-file",
+        fallback.to_str().unwrap(),
+        input_file.to_str().unwrap(),
+    ])
+    .assert()
+    .success()
+    .stdout(predicates::str::contains(fallback.to_str().unwrap()));
+
+    let content = fs::read_to_string(&fallback).unwrap();
+    assert!(content.contains("headless content"));
+}
+
+#[test]
+fn csv_format_writes_one_row_per_file() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a,b.rs"), "fn a() {}\nfn b() {}\n").unwrap();
+    fs::write(dir.path().join("notes"), "hi").unwrap();
+    let output_file = dir.path().join("files.csv");
+
+    let mut cmd = Command::cargo_bin("cxt").unwrap();
+    cmd.current_dir(dir.path())
+        .args([
+            "--ci",
+            "-r",
+            "--format",
+            "csv",
+            "-w",
+            output_file.to_str().unwrap(),
+            "a,b.rs",
+            "notes",
+        ])
+        .assert()
+        .success();
+
+    let content = fs::read_to_string(&output_file).unwrap();
+    assert_eq!(
+        content,
+        "path,size,lines,extension\n\"a,b.rs\",20,2,rs\nnotes,2,1,\n"
+    );
+}
+
+#[test]
+fn summary_only_lists_one_line_per_file() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join("lib.rs"),
+        "//! Walks the tree.\nfn walk() {}\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("notes.txt"), "\nfirst real line\nmore\n").unwrap();
+
+    let out = Command::cargo_bin("cxt")
+        .unwrap()
+        .current_dir(dir.path())
+        .args(["--ci", "-p", "-r", "--summary-only", "."])
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(out.stdout).unwrap(),
+        "lib.rs: Walks the tree.\nnotes.txt: first real line\n"
+    );
+}
+
+#[test]
+fn write_target_inside_walked_directory_is_not_aggregated() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.txt"), "AAA").unwrap();
+
+    // The second run walks over the first run's output; it must not include it.
+    for _ in 0..2 {
+        let mut cmd = Command::cargo_bin("cxt").unwrap();
+        cmd.current_dir(dir.path())
+            .args(["--ci", "-r", "-w", "out.txt", "."])
+            .assert()
+            .success();
+    }
+    let content = fs::read_to_string(dir.path().join("out.txt")).unwrap();
+    assert_eq!(content.matches("AAA").count(), 1);
+    assert!(!content.contains("out.txt"));
+
+    let mut cmd = Command::cargo_bin("cxt").unwrap();
+    cmd.current_dir(dir.path())
+        .args(["--ci", "-w", "a.txt", "a.txt"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("is also an input file"));
+    assert_eq!(fs::read_to_string(dir.path().join("a.txt")).unwrap(), "AAA");
+}
+
 // Wildcard expansion is handled by the shell on Linux/macOS (or by `wild` on Windows).
 // These tests pass explicit paths to verify the underlying aggregation logic.
 #[test]
@@ -151,6 +905,135 @@ fn handles_multiple_explicit_paths_in_subdirectory() {
     .stdout(predicates::str::contains("Header file").not());
 }
 
+#[test]
+fn order_flag_controls_argument_order() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("lib")).unwrap();
+    fs::write(dir.path().join("b.txt"), "BBB").unwrap();
+    fs::write(dir.path().join("a.txt"), "AAA").unwrap();
+    fs::write(dir.path().join("lib/z.txt"), "ZZZ").unwrap();
+    fs::write(dir.path().join("lib/y.txt"), "YYY").unwrap();
+
+    let run = |order: &str| {
+        let out = Command::cargo_bin("cxt")
+            .unwrap()
+            .current_dir(dir.path())
+            .args([
+                "--ci", "-p", "-n", "--order", order, "b.txt", "lib", "a.txt",
+            ])
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8(out.stdout).unwrap();
+        ["AAA", "BBB", "YYY", "ZZZ"]
+            .iter()
+            .map(|m| (stdout.find(m).unwrap(), *m))
+            .collect::<std::collections::BTreeMap<_, _>>()
+            .into_values()
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(run("as-listed"), ["BBB", "YYY", "ZZZ", "AAA"]);
+    assert_eq!(run("sorted"), ["AAA", "BBB", "YYY", "ZZZ"]);
+}
+
+#[test]
+fn rust_preset_leads_with_the_manifest() {
+    let dir = tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("src")).unwrap();
+    fs::create_dir_all(dir.path().join("target/debug")).unwrap();
+    fs::create_dir(dir.path().join("examples")).unwrap();
+    fs::write(dir.path().join("Cargo.toml"), "[package] MANIFEST").unwrap();
+    fs::write(dir.path().join("Cargo.lock"), "LOCKFILE").unwrap();
+    fs::write(dir.path().join("README.md"), "README").unwrap();
+    fs::write(dir.path().join("src/lib.rs"), "LIBRARY").unwrap();
+    fs::write(dir.path().join("target/debug/out.rs"), "BUILT").unwrap();
+    fs::write(dir.path().join("examples/demo.rs"), "EXAMPLE").unwrap();
+
+    let out = Command::cargo_bin("cxt")
+        .unwrap()
+        .current_dir(dir.path())
+        .args(["--ci", "-p", "--rust", "examples"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    let at = |marker: &str| stdout.find(marker);
+    assert!(at("MANIFEST") < at("LIBRARY") && at("LIBRARY") < at("EXAMPLE"));
+    assert!(at("MANIFEST").is_some());
+    for left_out in ["LOCKFILE", "README", "BUILT"] {
+        assert!(at(left_out).is_none(), "{left_out} in {stdout}");
+    }
+}
+
+#[test]
+fn paste_writes_files_back_and_respects_conflicts() {
+    let dir = tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/a.rs"), "fn a() {}\n").unwrap();
+    fs::write(dir.path().join("notes.md"), "# T\n```\nx\n```\n").unwrap();
+    for format in ["xml", "markdown"] {
+        Command::cargo_bin("cxt")
+            .unwrap()
+            .current_dir(dir.path())
+            .args(["--ci", "-r", "--format", format, "-w"])
+            .arg(format!("out.{format}"))
+            .args(["src", "notes.md"])
+            .assert()
+            .success();
+        let paste = |extra: &[&str]| {
+            Command::cargo_bin("cxt")
+                .unwrap()
+                .current_dir(dir.path())
+                .args(["paste", "--into", format, "--from"])
+                .arg(format!("out.{format}"))
+                .args(extra)
+                .assert()
+                .success()
+        };
+        paste(&[]);
+        let copy = dir.path().join(format);
+        assert_eq!(
+            fs::read_to_string(copy.join("src/a.rs")).unwrap(),
+            "fn a() {}\n"
+        );
+        assert_eq!(
+            fs::read_to_string(copy.join("notes.md")).unwrap(),
+            "# T\n```\nx\n```\n"
+        );
+        fs::write(copy.join("src/a.rs"), "edited\n").unwrap();
+        paste(&[]).stderr(predicates::str::contains("pass --overwrite or --append"));
+        assert_eq!(
+            fs::read_to_string(copy.join("src/a.rs")).unwrap(),
+            "edited\n"
+        );
+        paste(&["--append"]);
+        assert_eq!(
+            fs::read_to_string(copy.join("src/a.rs")).unwrap(),
+            "edited\nfn a() {}\n"
+        );
+        paste(&["--overwrite"]);
+        assert_eq!(
+            fs::read_to_string(copy.join("src/a.rs")).unwrap(),
+            "fn a() {}\n"
+        );
+    }
+}
+
+#[test]
+fn ci_prints_piped_paths_in_order() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("b.txt"), "BBB").unwrap();
+    fs::write(dir.path().join("a.txt"), "AAA").unwrap();
+    let out = Command::cargo_bin("cxt")
+        .unwrap()
+        .current_dir(dir.path())
+        .args(["--ci", "-n"])
+        .write_stdin("b.txt\na.txt\n")
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert!(stdout.find("BBB").unwrap() < stdout.find("AAA").unwrap());
+}
+
 #[test]
 fn handles_no_matching_files() {
     let dir = tempdir().unwrap();
@@ -164,3 +1047,27 @@ fn handles_no_matching_files() {
     .failure()
     .stderr(predicates::str::contains("Path does not exist"));
 }
+
+#[test]
+fn init_config_writes_once_unless_forced() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cxt").join("config.toml");
+    let init = |extra: &[&str]| {
+        let mut cmd = Command::cargo_bin("cxt").unwrap();
+        cmd.env("CXT_CONFIG", &path).arg("init-config").args(extra);
+        cmd.assert()
+    };
+    init(&[])
+        .success()
+        .stdout(predicate::str::contains(path.to_str().unwrap()));
+    assert!(fs::read_to_string(&path).unwrap().contains("# path_header"));
+
+    fs::write(&path, "default_output = \"stdout\"\n").unwrap();
+    init(&[])
+        .failure()
+        .stderr(predicate::str::contains("--force"));
+    init(&["--force"]).success();
+    assert!(fs::read_to_string(&path)
+        .unwrap()
+        .contains("# default_output"));
+}